@@ -19,9 +19,53 @@ fn find_avahi_compat_dns_sd() {
 	// on unix but not darwin link avahi compat
 	if cfg_family_is("unix") && !(cfg_os_is("macos") || cfg_os_is("ios")) {
 		pkg_config::probe_library("avahi-compat-libdns_sd").unwrap();
+		println!("cargo:rustc-cfg=dnssd_avahi");
 	}
 }
 
+// build client library from mDNSResponder sources instead of linking a
+// system library (unix but not darwin, which always has it)
+#[cfg(feature = "vendored")]
+fn build_vendored_dns_sd() -> bool {
+	use std::path::PathBuf;
+
+	if !cfg_family_is("unix") || cfg_os_is("macos") || cfg_os_is("ios") {
+		return false;
+	}
+
+	println!("cargo:rerun-if-env-changed=MDNSRESPONDER_SRC");
+	println!("cargo:rerun-if-env-changed=MDNSRESPONDER_UDS_PATH");
+	let src = var_os("MDNSRESPONDER_SRC")
+		.map_or_else(|| PathBuf::from("vendor/mDNSResponder"), PathBuf::from);
+	let shared = src.join("mDNSShared");
+	if !shared.join("dnssd_clientstub.c").exists() {
+		panic!(
+			"Can't find mDNSResponder sources (download from https://github.com/apple-oss-distributions/mDNSResponder) at {:?}; set MDNSRESPONDER_SRC",
+			src
+		);
+	}
+	// path of the daemon socket; mDNSPosix builds default to /var/run/mdnsd
+	let uds_path = var("MDNSRESPONDER_UDS_PATH").unwrap_or_else(|_| "/var/run/mdnsd".to_string());
+
+	cc::Build::new()
+		.include(&shared)
+		.file(shared.join("dnssd_clientstub.c"))
+		.file(shared.join("dnssd_ipc.c"))
+		.define("_GNU_SOURCE", None)
+		.define("HAVE_IPV6", "1")
+		.define("NOT_HAVE_SA_LEN", None)
+		.define("USES_NETLINK", None)
+		.define("MDNS_UDS_SERVERPATH", format!("{:?}", uds_path).as_str())
+		.warnings(false)
+		.compile("dns_sd");
+	true
+}
+
+#[cfg(not(feature = "vendored"))]
+fn build_vendored_dns_sd() -> bool {
+	false
+}
+
 fn find_windows_dns_sd() {
 	if cfg_family_is("windows") {
 		let platform = match cfg_arch().as_str() {
@@ -40,6 +84,9 @@ fn find_windows_dns_sd() {
 
 fn main() {
 	println!("cargo:rerun-if-changed=build.rs");
-	find_avahi_compat_dns_sd();
+	println!("cargo:rustc-check-cfg=cfg(dnssd_avahi)");
+	if !build_vendored_dns_sd() {
+		find_avahi_compat_dns_sd();
+	}
 	find_windows_dns_sd();
 }
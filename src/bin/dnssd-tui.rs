@@ -0,0 +1,571 @@
+//! Interactive browser: domains -> service types -> instances
+//!
+//! Build with `cargo run --features tui --bin dnssd-tui`.
+//!
+//! Keys: up/down (or k/j) move, right/enter (or l) expand, left (or h)
+//! collapse, q quits.  Expanding an instance resolves it.
+
+use async_dnssd::{
+	BrowseData,
+	BrowseDebounceExt,
+	BrowseResult,
+	BrowsedFlags,
+	Enumerate,
+	EnumeratedFlags,
+	Interface,
+	ReconnectData,
+	ResolveResult,
+	ResolvedHostFlags,
+	TxtRecordRef,
+};
+use crossterm::{
+	cursor,
+	event::{
+		self,
+		KeyCode,
+		KeyEvent,
+		KeyEventKind,
+		KeyModifiers,
+	},
+	queue,
+	style::{
+		Attribute,
+		Print,
+		SetAttribute,
+	},
+	terminal,
+};
+use futures_channel::mpsc;
+use futures_util::{
+	StreamExt,
+	TryStream,
+	TryStreamExt,
+};
+use std::{
+	collections::{
+		BTreeMap,
+		BTreeSet,
+	},
+	io::{
+		self,
+		Write,
+	},
+	time::Duration,
+};
+
+// (domain, reg_type, instance name)
+type Path = (String, String, String);
+
+type Sender = mpsc::UnboundedSender<Event>;
+
+enum Event {
+	Key(KeyEvent),
+	Resize,
+	Domain(String, bool),
+	ServiceType(String, String, bool),
+	Service(BrowseResult),
+	Resolved(Path, ResolveResult),
+	Address(Path, String, bool),
+	Error(String),
+	// reading the terminal failed; quits
+	InputFailed(io::Error),
+}
+
+// background task forwarding results; aborted when dropped
+struct Task(tokio::task::JoinHandle<()>);
+
+impl Drop for Task {
+	fn drop(&mut self) {
+		self.0.abort();
+	}
+}
+
+fn forward<S, F>(tx: &Sender, stream: S, mut map: F) -> Task
+where
+	S: TryStream<Error = io::Error> + Send + 'static,
+	S::Ok: Send,
+	F: FnMut(S::Ok) -> Option<Event> + Send + 'static,
+{
+	let tx = tx.clone();
+	Task(tokio::spawn(async move {
+		let mut stream = Box::pin(stream.into_stream());
+		loop {
+			let event = match stream.try_next().await {
+				Ok(Some(item)) => match map(item) {
+					Some(event) => event,
+					None => continue,
+				},
+				Ok(None) => return,
+				Err(e) => Event::Error(e.to_string()),
+			};
+			if tx.unbounded_send(event).is_err() {
+				return;
+			}
+		}
+	}))
+}
+
+fn browse(reg_type: &str, domain: &str) -> impl TryStream<Ok = BrowseResult, Error = io::Error> {
+	async_dnssd::browse_reconnecting(
+		reg_type,
+		BrowseData {
+			domain: Some(domain),
+			..Default::default()
+		},
+		ReconnectData::default(),
+	)
+	.debounce(Duration::from_secs(2))
+}
+
+struct Instance {
+	interfaces: BTreeSet<Interface>,
+	service: BrowseResult,
+	expanded: bool,
+	resolve: Option<Task>,
+	resolved: BTreeMap<Interface, ResolveResult>,
+	addresses: BTreeSet<String>,
+	address_tasks: BTreeMap<Interface, Task>,
+}
+
+#[derive(Default)]
+struct ServiceType {
+	expanded: bool,
+	instances: BTreeMap<String, Instance>,
+	_browse: Option<Task>,
+}
+
+#[derive(Default)]
+struct Domain {
+	expanded: bool,
+	types: BTreeMap<String, ServiceType>,
+	_browse: Option<Task>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Node {
+	Domain(String),
+	Type(String, String),
+	Instance(Path),
+	Detail,
+}
+
+struct App {
+	tx: Sender,
+	domains: BTreeMap<String, Domain>,
+	_enumerate: Option<Task>,
+	cursor: usize,
+	scroll: usize,
+	status: String,
+}
+
+impl App {
+	fn new(tx: Sender) -> Self {
+		let enumerate = forward(
+			&tx,
+			async_dnssd::enumerate_domains(Enumerate::BrowseDomains, Interface::Any),
+			|result| {
+				Some(Event::Domain(
+					result.domain,
+					result.flags.contains(EnumeratedFlags::ADD),
+				))
+			},
+		);
+		let mut app = Self {
+			tx,
+			domains: BTreeMap::new(),
+			_enumerate: Some(enumerate),
+			cursor: 0,
+			scroll: 0,
+			status: String::new(),
+		};
+		// always show the link-local domain
+		app.add_domain("local.".to_string());
+		app
+	}
+
+	fn add_domain(&mut self, domain: String) {
+		if self.domains.contains_key(&domain) {
+			return;
+		}
+		let key = domain.clone();
+		let stream = async_dnssd::enumerate_service_types(&domain);
+		let task = forward(&self.tx, stream, move |service_type| {
+			Some(Event::ServiceType(
+				key.clone(),
+				service_type.reg_type.trim_end_matches('.').to_string(),
+				service_type.added,
+			))
+		});
+		self.domains.insert(
+			domain,
+			Domain {
+				_browse: Some(task),
+				..Default::default()
+			},
+		);
+	}
+
+	fn add_service_type(&mut self, domain_name: String, reg_type: String) {
+		let domain = match self.domains.get_mut(&domain_name) {
+			Some(domain) => domain,
+			None => return,
+		};
+		if domain.types.contains_key(&reg_type) {
+			return;
+		}
+		let stream = browse(&reg_type, &domain_name);
+		let task = forward(&self.tx, stream, |result| Some(Event::Service(result)));
+		domain.types.insert(
+			reg_type,
+			ServiceType {
+				_browse: Some(task),
+				..Default::default()
+			},
+		);
+	}
+
+	fn service(&mut self, result: BrowseResult) {
+		let added = result.flags.contains(BrowsedFlags::ADD);
+		let reg_type = result.reg_type.trim_end_matches('.');
+		let reg_type = reg_type
+			.strip_suffix(result.domain.trim_end_matches('.'))
+			.unwrap_or(reg_type)
+			.trim_end_matches('.');
+		let service_type = match self
+			.domains
+			.get_mut(&result.domain)
+			.and_then(|domain| domain.types.get_mut(reg_type))
+		{
+			Some(service_type) => service_type,
+			None => return,
+		};
+		if added {
+			let instance = service_type
+				.instances
+				.entry(result.service_name.clone())
+				.or_insert_with(|| Instance {
+					interfaces: BTreeSet::new(),
+					service: result.clone(),
+					expanded: false,
+					resolve: None,
+					resolved: BTreeMap::new(),
+					addresses: BTreeSet::new(),
+					address_tasks: BTreeMap::new(),
+				});
+			instance.interfaces.insert(result.interface);
+		} else if let Some(instance) = service_type.instances.get_mut(&result.service_name) {
+			instance.interfaces.remove(&result.interface);
+			if instance.interfaces.is_empty() {
+				service_type.instances.remove(&result.service_name);
+			}
+		}
+	}
+
+	fn instance_mut(&mut self, path: &Path) -> Option<&mut Instance> {
+		self.domains
+			.get_mut(&path.0)?
+			.types
+			.get_mut(&path.1)?
+			.instances
+			.get_mut(&path.2)
+	}
+
+	fn resolved(&mut self, path: Path, result: ResolveResult) {
+		let tx = self.tx.clone();
+		let instance = match self.instance_mut(&path) {
+			Some(instance) => instance,
+			None => return,
+		};
+		let task = forward(&tx, result.resolve_socket_address(), move |address| {
+			Some(Event::Address(
+				path.clone(),
+				address.address.to_string(),
+				address.flags.contains(ResolvedHostFlags::ADD),
+			))
+		});
+		// replaces (and aborts) a previous lookup on the interface
+		instance.address_tasks.insert(result.interface, task);
+		instance.resolved.insert(result.interface, result);
+	}
+
+	fn expand(&mut self, node: &Node, expand: bool) {
+		match node {
+			Node::Domain(domain) => {
+				if let Some(domain) = self.domains.get_mut(domain) {
+					domain.expanded = expand;
+				}
+			},
+			Node::Type(domain, reg_type) => {
+				if let Some(service_type) = self
+					.domains
+					.get_mut(domain)
+					.and_then(|domain| domain.types.get_mut(reg_type))
+				{
+					service_type.expanded = expand;
+				}
+			},
+			Node::Instance(path) => {
+				let tx = self.tx.clone();
+				if let Some(instance) = self.instance_mut(path) {
+					instance.expanded = expand;
+					if expand && instance.resolve.is_none() {
+						let path = path.clone();
+						instance.resolve =
+							Some(forward(&tx, instance.service.resolve(), move |result| {
+								Some(Event::Resolved(path.clone(), result))
+							}));
+					}
+				}
+			},
+			Node::Detail => (),
+		}
+	}
+
+	// returns false to quit
+	fn handle(&mut self, event: Event) -> bool {
+		match event {
+			Event::Key(key) => return self.key(key),
+			Event::Resize => (),
+			Event::Domain(domain, true) => self.add_domain(domain),
+			Event::Domain(domain, false) => {
+				// keep the link-local domain
+				if domain != "local." {
+					self.domains.remove(&domain);
+				}
+			},
+			Event::ServiceType(domain, reg_type, true) => self.add_service_type(domain, reg_type),
+			Event::ServiceType(domain, reg_type, false) => {
+				if let Some(domain) = self.domains.get_mut(&domain) {
+					domain.types.remove(&reg_type);
+				}
+			},
+			Event::Service(result) => self.service(result),
+			Event::Resolved(path, result) => self.resolved(path, result),
+			Event::Address(path, address, added) => {
+				if let Some(instance) = self.instance_mut(&path) {
+					if added {
+						instance.addresses.insert(address);
+					} else {
+						instance.addresses.remove(&address);
+					}
+				}
+			},
+			Event::Error(e) => self.status = e,
+			Event::InputFailed(_) => return false,
+		}
+		true
+	}
+
+	fn key(&mut self, key: KeyEvent) -> bool {
+		if key.kind != KeyEventKind::Press {
+			return true;
+		}
+		let rows = self.rows();
+		let node = rows.get(self.cursor).map(|row| row.1.clone());
+		match key.code {
+			KeyCode::Char('q') | KeyCode::Esc => return false,
+			KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+			KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+			KeyCode::Down | KeyCode::Char('j') => self.cursor += 1,
+			KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') => {
+				if let Some(node) = node {
+					self.expand(&node, true);
+				}
+			},
+			KeyCode::Left | KeyCode::Char('h') => {
+				if let Some(node) = node {
+					self.expand(&node, false);
+				}
+			},
+			_ => (),
+		}
+		true
+	}
+
+	// visible rows: (text, node)
+	fn rows(&self) -> Vec<(String, Node)> {
+		fn marker(expanded: bool) -> &'static str {
+			if expanded {
+				"[-]"
+			} else {
+				"[+]"
+			}
+		}
+
+		let mut rows = Vec::new();
+		for (name, domain) in &self.domains {
+			rows.push((
+				format!(
+					"{} {} ({} types)",
+					marker(domain.expanded),
+					name,
+					domain.types.len()
+				),
+				Node::Domain(name.clone()),
+			));
+			if !domain.expanded {
+				continue;
+			}
+			for (reg_type, service_type) in &domain.types {
+				rows.push((
+					format!(
+						"    {} {} ({})",
+						marker(service_type.expanded),
+						reg_type,
+						service_type.instances.len()
+					),
+					Node::Type(name.clone(), reg_type.clone()),
+				));
+				if !service_type.expanded {
+					continue;
+				}
+				for (instance_name, instance) in &service_type.instances {
+					let interfaces: Vec<_> = instance
+						.interfaces
+						.iter()
+						.map(ToString::to_string)
+						.collect();
+					rows.push((
+						format!(
+							"        {} {} [{}]",
+							marker(instance.expanded),
+							instance_name,
+							interfaces.join(", ")
+						),
+						Node::Instance((name.clone(), reg_type.clone(), instance_name.clone())),
+					));
+					if !instance.expanded {
+						continue;
+					}
+					if instance.resolved.is_empty() {
+						rows.push(("            resolving...".to_string(), Node::Detail));
+					}
+					for result in instance.resolved.values() {
+						rows.push((format!("            {}", result), Node::Detail));
+						if let Some(txt) = TxtRecordRef::parse(&result.txt) {
+							for (key, value) in txt.iter() {
+								let key = String::from_utf8_lossy(key);
+								let line = match value {
+									Some(value) => {
+										format!(
+											"              {}={}",
+											key,
+											String::from_utf8_lossy(value)
+										)
+									},
+									None => format!("              {}", key),
+								};
+								rows.push((line, Node::Detail));
+							}
+						}
+					}
+					for address in &instance.addresses {
+						rows.push((format!("            address {}", address), Node::Detail));
+					}
+				}
+			}
+		}
+		rows
+	}
+
+	fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+		let (width, height) = terminal::size()?;
+		let (width, height) = (width as usize, height as usize);
+		let rows = self.rows();
+		self.cursor = self.cursor.min(rows.len().saturating_sub(1));
+		// last line is the status line
+		let lines = height.saturating_sub(1).max(1);
+		if self.cursor < self.scroll {
+			self.scroll = self.cursor;
+		} else if self.cursor >= self.scroll + lines {
+			self.scroll = self.cursor + 1 - lines;
+		}
+
+		queue!(out, terminal::Clear(terminal::ClearType::All))?;
+		for (line, (text, _)) in rows.iter().enumerate().skip(self.scroll).take(lines) {
+			let text: String = text.chars().take(width).collect();
+			queue!(out, cursor::MoveTo(0, (line - self.scroll) as u16))?;
+			if line == self.cursor {
+				queue!(
+					out,
+					SetAttribute(Attribute::Reverse),
+					Print(text),
+					SetAttribute(Attribute::Reset)
+				)?;
+			} else {
+				queue!(out, Print(text))?;
+			}
+		}
+		let status = format!("q: quit, arrows: navigate  {}", self.status);
+		let status: String = status.chars().take(width).collect();
+		queue!(out, cursor::MoveTo(0, lines as u16), Print(status))?;
+		out.flush()
+	}
+}
+
+fn read_keys(tx: Sender) {
+	loop {
+		let event = match event::read() {
+			Ok(event::Event::Key(key)) => Event::Key(key),
+			Ok(event::Event::Resize(..)) => Event::Resize,
+			Ok(_) => continue,
+			Err(e) => {
+				// retrying would just spin on the same error
+				let _ = tx.unbounded_send(Event::InputFailed(e));
+				return;
+			},
+		};
+		if tx.unbounded_send(event).is_err() {
+			return;
+		}
+	}
+}
+
+async fn run(out: &mut impl Write) -> io::Result<()> {
+	let (tx, mut rx) = mpsc::unbounded();
+	{
+		let tx = tx.clone();
+		std::thread::spawn(move || read_keys(tx));
+	}
+
+	let mut app = App::new(tx);
+	app.draw(out)?;
+	while let Some(event) = rx.next().await {
+		if let Event::InputFailed(e) = event {
+			return Err(e);
+		}
+		if !app.handle(event) {
+			break;
+		}
+		app.draw(out)?;
+	}
+	Ok(())
+}
+
+// best effort: also used from the panic hook
+fn restore_terminal() {
+	let mut out = io::stdout();
+	let _ = queue!(out, cursor::Show, terminal::LeaveAlternateScreen);
+	let _ = out.flush();
+	let _ = terminal::disable_raw_mode();
+}
+
+fn main() -> io::Result<()> {
+	let runtime = tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()?;
+
+	// otherwise the panic message ends up in the alternate screen
+	let default_hook = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		restore_terminal();
+		default_hook(info);
+	}));
+
+	let mut out = io::stdout();
+	terminal::enable_raw_mode()?;
+	let result = queue!(out, terminal::EnterAlternateScreen, cursor::Hide)
+		.and_then(|()| runtime.block_on(run(&mut out)));
+	restore_terminal();
+	result
+}
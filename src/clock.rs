@@ -0,0 +1,35 @@
+use std::future::Future;
+use tokio::time::Instant;
+
+/// Source of time for the timers in stream adapters like
+/// [`TimeoutStream`](struct.TimeoutStream.html)
+///
+/// The default [`TokioClock`](struct.TokioClock.html) already works with
+/// the test utilities of tokio (e.g. `tokio::time::pause`); a custom clock
+/// allows full control over timers in tests.
+pub trait Clock {
+	/// Timer returned by [`sleep_until`](#tymethod.sleep_until)
+	type Sleep: Future<Output = ()>;
+
+	/// Current time
+	fn now(&self) -> Instant;
+
+	/// Create a timer completing at (or after) `deadline`
+	fn sleep_until(&self, deadline: Instant) -> Self::Sleep;
+}
+
+/// Uses the timers of tokio
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+	type Sleep = tokio::time::Sleep;
+
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+
+	fn sleep_until(&self, deadline: Instant) -> Self::Sleep {
+		tokio::time::sleep_until(deadline)
+	}
+}
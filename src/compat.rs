@@ -0,0 +1,59 @@
+//! Normalize known differences between mDNSResponder and the avahi
+//! compat layer (see `set_normalize_daemon_behavior`)
+
+use std::sync::Once;
+
+use crate::ffi;
+
+fn active() -> bool {
+	cfg!(dnssd_avahi) && crate::config::normalize_daemon_behavior()
+}
+
+// avahi fails operations with unsupported flags; drop them instead
+pub(crate) fn filter_flags(
+	function: &str,
+	flags: ffi::DNSServiceFlags,
+	supported: ffi::DNSServiceFlags,
+) -> ffi::DNSServiceFlags {
+	let unsupported = flags & !supported;
+	if unsupported == 0 || !active() {
+		return flags;
+	}
+	log::warn!(
+		"{}: flags {:#x} not supported by avahi, ignoring them",
+		function,
+		unsupported
+	);
+	flags & supported
+}
+
+// avahi doesn't support `DNSServiceCreateConnection`
+pub(crate) fn allow_shared_connection() -> bool {
+	static WARN: Once = Once::new();
+
+	if !active() {
+		return true;
+	}
+	WARN.call_once(|| {
+		log::warn!("shared connections not supported by avahi, using a connection per operation")
+	});
+	false
+}
+
+// avahi reports a lost D-Bus connection to the daemon as `Unknown`
+pub(crate) fn callback_error(error_code: ffi::DNSServiceErrorType) -> ffi::DNSServiceErrorType {
+	if error_code == ffi::DNSServiceError::Unknown as ffi::DNSServiceErrorType && active() {
+		ffi::DNSServiceError::ServiceNotRunning as ffi::DNSServiceErrorType
+	} else {
+		error_code
+	}
+}
+
+// avahi doesn't set `kDNSServiceFlagsAdd` on successful registrations
+pub(crate) fn register_flags(flags: ffi::DNSServiceFlags) -> ffi::DNSServiceFlags {
+	if active() {
+		flags | ffi::FLAGS_ADD
+	} else {
+		flags
+	}
+}
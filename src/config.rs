@@ -0,0 +1,183 @@
+#[cfg(windows)]
+use std::sync::atomic::AtomicU32;
+use std::{
+	sync::atomic::{
+		AtomicBool,
+		AtomicU64,
+		AtomicU8,
+		Ordering,
+	},
+	time::Duration,
+};
+
+#[cfg(windows)]
+use crate::ffi;
+
+/// How to handle names reported by the daemon that aren't valid UTF-8
+///
+/// Applies to results of [`browse`], [`resolve`], [`query_record`] and
+/// [`enumerate_domains`]; set with [`set_name_decoding`].
+///
+/// [`browse`]: fn.browse.html
+/// [`resolve`]: fn.resolve.html
+/// [`query_record`]: fn.query_record.html
+/// [`enumerate_domains`]: fn.enumerate_domains.html
+/// [`set_name_decoding`]: fn.set_name_decoding.html
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum NameDecoding {
+	/// Return an error for the result
+	Strict,
+	/// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`
+	///
+	/// Only used for service instance names (e.g.
+	/// `BrowseResult::service_name`); the other names (service types,
+	/// domains, host and full names) are passed back to the daemon later
+	/// and would refer to something else, so these are handled as
+	/// `Strict`.
+	#[default]
+	Lossy,
+	/// Drop the result silently
+	Skip,
+}
+
+static NAME_DECODING: AtomicU8 = AtomicU8::new(NameDecoding::Lossy as u8);
+
+/// Set how to handle names that aren't valid UTF-8 (default:
+/// [`NameDecoding::Lossy`])
+///
+/// [`NameDecoding::Lossy`]: enum.NameDecoding.html#variant.Lossy
+pub fn set_name_decoding(policy: NameDecoding) {
+	NAME_DECODING.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Current policy for names that aren't valid UTF-8
+pub fn name_decoding() -> NameDecoding {
+	match NAME_DECODING.load(Ordering::Relaxed) {
+		0 => NameDecoding::Strict,
+		2 => NameDecoding::Skip,
+		_ => NameDecoding::Lossy,
+	}
+}
+
+static SHARED_CONNECTION: AtomicBool = AtomicBool::new(false);
+
+/// Run operations over a single shared connection to the daemon
+/// (default: disabled)
+///
+/// When enabled [`browse`], [`resolve`], [`query_record`] and
+/// [`enumerate_domains`] (and everything built on them) started
+/// afterwards use one connection (and socket) to the daemon instead of
+/// a connection per operation, using
+/// [`kDNSServiceFlagsShareConnection`].  The connection is created
+/// lazily and closed when the last operation using it is dropped; it is
+/// driven by a task on the tokio runtime that created it.
+///
+/// Requires daemon support (mDNSResponder); the avahi compat layer fails
+/// these operations with `Unsupported`.
+///
+/// To pick the connection explicitly use the methods of a
+/// [`Connection`] (e.g. [`Connection::browse`]) instead.
+///
+/// [`Connection`]: struct.Connection.html
+/// [`Connection::browse`]: struct.Connection.html#method.browse
+/// [`browse`]: fn.browse.html
+/// [`resolve`]: fn.resolve.html
+/// [`query_record`]: fn.query_record.html
+/// [`enumerate_domains`]: fn.enumerate_domains.html
+/// [`kDNSServiceFlagsShareConnection`]: https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsshareconnection
+pub fn set_shared_connection(enabled: bool) {
+	SHARED_CONNECTION.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether operations use a single shared connection (see
+/// [`set_shared_connection`](fn.set_shared_connection.html))
+pub fn shared_connection() -> bool {
+	SHARED_CONNECTION.load(Ordering::Relaxed)
+}
+
+static NORMALIZE_DAEMON_BEHAVIOR: AtomicBool = AtomicBool::new(false);
+
+/// Paper over known differences between mDNSResponder and the avahi
+/// compat layer (default: disabled)
+///
+/// Only has an effect when linked against the avahi compat library;
+/// when enabled:
+///
+/// - [`RegisterResult::flags`] always contains `ADD` (avahi reports
+///   successful registrations without any flags),
+/// - `Unknown` errors reported for running operations (avahi's
+///   translation of a lost D-Bus connection) become `ServiceNotRunning`,
+///   so [`Error::is_service_not_running`] and reconnect logic work the same
+///   way,
+/// - flags avahi rejects are dropped with a warning (through the `log`
+///   crate) instead of failing the operation, and
+/// - [`set_shared_connection`] is ignored (with a warning), as avahi
+///   doesn't support shared connections.
+///
+/// [`RegisterResult::flags`]: struct.RegisterResult.html#structfield.flags
+/// [`Error::is_service_not_running`]: enum.Error.html#method.is_service_not_running
+/// [`set_shared_connection`]: fn.set_shared_connection.html
+pub fn set_normalize_daemon_behavior(enabled: bool) {
+	NORMALIZE_DAEMON_BEHAVIOR.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether daemon differences are normalized (see
+/// [`set_normalize_daemon_behavior`](fn.set_normalize_daemon_behavior.html))
+pub fn normalize_daemon_behavior() -> bool {
+	NORMALIZE_DAEMON_BEHAVIOR.load(Ordering::Relaxed)
+}
+
+static DEFAULT_DOMAIN_CACHE_MS: AtomicU64 = AtomicU64::new(60_000);
+
+/// How long [`default_browse_domain`] and [`default_registration_domain`]
+/// reuse a previously found domain (default: one minute; zero disables
+/// caching)
+///
+/// [`default_browse_domain`]: fn.default_browse_domain.html
+/// [`default_registration_domain`]: fn.default_registration_domain.html
+pub fn set_default_domain_cache_duration(duration: Duration) {
+	let ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+	DEFAULT_DOMAIN_CACHE_MS.store(ms, Ordering::Relaxed);
+}
+
+/// How long default domains are cached (see
+/// [`set_default_domain_cache_duration`](fn.set_default_domain_cache_duration.html))
+pub fn default_domain_cache_duration() -> Duration {
+	Duration::from_millis(DEFAULT_DOMAIN_CACHE_MS.load(Ordering::Relaxed))
+}
+
+#[cfg(windows)]
+bitflags::bitflags! {
+	/// Flags for initializing the DNS-SD client library on Windows
+	///
+	/// See `DNSServiceInitialize` in `dns_sd.h` of the Bonjour SDK.
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct InitOptions: ffi::DNSServiceInitializeFlags {
+		/// Advertise the host (register its hostname and addresses)
+		const ADVERTISE = ffi::INITIALIZE_FLAGS_ADVERTISE;
+
+		/// Don't check whether the daemon is running and compatible
+		/// (also skips `DNSServiceCheckVersion`)
+		const NO_SERVER_CHECK = ffi::INITIALIZE_FLAGS_NO_SERVER_CHECK;
+	}
+}
+
+#[cfg(windows)]
+static INIT_OPTIONS: AtomicU32 = AtomicU32::new(0);
+
+/// Flags to initialize the DNS-SD client library with (default: none)
+///
+/// The library is initialized when the first operation starts; call
+/// this before starting any operation.
+#[cfg(windows)]
+pub fn set_init_options(options: InitOptions) {
+	INIT_OPTIONS.store(options.bits(), Ordering::Relaxed);
+}
+
+/// Flags the DNS-SD client library gets initialized with (see
+/// [`set_init_options`](fn.set_init_options.html))
+#[cfg(windows)]
+pub fn init_options() -> InitOptions {
+	InitOptions::from_bits_retain(INIT_OPTIONS.load(Ordering::Relaxed))
+}
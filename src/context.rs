@@ -0,0 +1,390 @@
+use std::{
+	io,
+	sync::{
+		Arc,
+		Mutex,
+	},
+};
+
+use crate::{
+	domain_name::DomainName,
+	inner::{
+		SharedService,
+		WeakSharedService,
+	},
+	service::{
+		browse_on,
+		default_domain,
+		query_record_on,
+		resolve_on,
+		DefaultDomainCache,
+		DomainCacheRef,
+	},
+	Browse,
+	BrowseData,
+	BrowseFlags,
+	Connection,
+	DefaultDomain,
+	Enumerate,
+	Interface,
+	InterfacePolicy,
+	QueryRecord,
+	QueryRecordData,
+	Register,
+	RegisterData,
+	Resolve,
+	ResolveData,
+	Type,
+};
+
+/// Owns connections, operations and caches created through it
+///
+/// [`Connection`]s, [`Register`]/[`Registration`]s and streams are
+/// driven by background tasks; [`shutdown`] (or dropping the context)
+/// stops all of them and closes their connections to the daemon right
+/// away.  This includes registrations still lingering (see
+/// [`Registration::set_linger`]) and the delayed removal of records
+/// (see [`RecordDropPolicy::KeepForTtl`]).  Handles still held by the
+/// application fail with `NotConnected` afterwards.
+///
+/// Streams (like [`browse`](#method.browse)) run as subordinate
+/// operations on a connection of the context (created when first
+/// needed, and again after it failed); the process-wide connection
+/// enabled by [`set_shared_connection`] isn't used.  Default domains
+/// (see [`default_browse_domain`](#method.default_browse_domain)) are
+/// cached per context.
+///
+/// All operations started through the context follow its
+/// [`InterfacePolicy`] (see
+/// [`with_interface_policy`](#method.with_interface_policy)); records
+/// registered on a [`Connection`] of the context aren't restricted.
+///
+/// [`Connection`]: struct.Connection.html
+/// [`Register`]: struct.Register.html
+/// [`Registration`]: struct.Registration.html
+/// [`Registration::set_linger`]: struct.Registration.html#method.set_linger
+/// [`RecordDropPolicy::KeepForTtl`]: enum.RecordDropPolicy.html#variant.KeepForTtl
+/// [`shutdown`]: #method.shutdown
+/// [`set_shared_connection`]: fn.set_shared_connection.html
+/// [`InterfacePolicy`]: enum.InterfacePolicy.html
+pub struct DnssdContext {
+	// `None` after shutdown
+	services: Mutex<Option<Vec<WeakSharedService>>>,
+	// connection the streams run on
+	streams: Mutex<Option<SharedService>>,
+	domains: Arc<DefaultDomainCache>,
+	policy: InterfacePolicy,
+}
+
+impl DnssdContext {
+	/// Create new context
+	pub fn new() -> Self {
+		Self::with_interface_policy(InterfacePolicy::Unrestricted)
+	}
+
+	/// Create new context restricting the interfaces its operations use
+	///
+	/// E.g. to constrain discovery to a single interface:
+	///
+	/// ```
+	/// # use async_dnssd::{DnssdContext, Interface, InterfaceIndex, InterfacePolicy};
+	/// let policy = match InterfaceIndex::from_name("eth0") {
+	///     Some(ndx) => InterfacePolicy::Only(Interface::Index(ndx)),
+	///     None => InterfacePolicy::ExcludePeerToPeer,
+	/// };
+	/// let context = DnssdContext::with_interface_policy(policy);
+	/// ```
+	pub fn with_interface_policy(policy: InterfacePolicy) -> Self {
+		Self {
+			services: Mutex::new(Some(Vec::new())),
+			streams: Mutex::new(None),
+			domains: Arc::new(Mutex::new(None)),
+			policy,
+		}
+	}
+
+	/// Interface policy of the context
+	pub fn interface_policy(&self) -> InterfacePolicy {
+		self.policy
+	}
+
+	fn track(&self, service: &SharedService) -> io::Result<()> {
+		let mut services = self.services.lock().unwrap();
+		match &mut *services {
+			Some(services) => {
+				// forget services that are gone already
+				services.retain(WeakSharedService::is_alive);
+				services.push(service.downgrade());
+				Ok(())
+			},
+			None => {
+				service.close();
+				Err(io::Error::new(
+					io::ErrorKind::NotConnected,
+					"context already shut down",
+				))
+			},
+		}
+	}
+
+	/// Create [`Connection`](struct.Connection.html) owned by the context
+	///
+	/// See [`connect`](fn.connect.html).
+	pub fn connect(&self) -> io::Result<Connection> {
+		let connection = crate::connect()?;
+		self.track(connection.service())?;
+		Ok(connection)
+	}
+
+	/// Register a service owned by the context
+	///
+	/// See [`register_extended`](fn.register_extended.html).
+	pub fn register_extended(
+		&self,
+		reg_type: &str,
+		port: u16,
+		mut data: RegisterData<'_>,
+	) -> io::Result<Register> {
+		data.interface = self.policy.interface(data.interface)?;
+		let register = crate::register_extended(reg_type, port, data)?;
+		self.track(register.service())?;
+		Ok(register)
+	}
+
+	/// Register a service owned by the context
+	///
+	/// See [`register`](fn.register.html).
+	pub fn register(&self, reg_type: &str, port: u16) -> io::Result<Register> {
+		self.register_extended(reg_type, port, RegisterData::default())
+	}
+
+	// connection for streams; replaced after it failed
+	fn stream_connection(&self) -> io::Result<SharedService> {
+		let mut streams = self.streams.lock().unwrap();
+		if let Some(service) = &*streams {
+			if !service.has_failed() {
+				return Ok(service.clone());
+			}
+		}
+		let service = crate::connect()?.service().clone();
+		self.track(&service)?;
+		*streams = Some(service.clone());
+		Ok(service)
+	}
+
+	/// Browse for available services on a connection of the context
+	///
+	/// See [`browse_extended`](fn.browse_extended.html).
+	#[doc(alias = "DNSServiceBrowse")]
+	pub fn browse_extended(&self, reg_type: &str, mut data: BrowseData<'_>) -> io::Result<Browse> {
+		data.interface = self.policy.interface(data.interface)?;
+		if !self.policy.allows_peer_to_peer() {
+			data.flags
+				.remove(BrowseFlags::INCLUDE_P2P | BrowseFlags::INCLUDE_AWDL);
+		}
+		Ok(browse_on(Some(&self.stream_connection()?), reg_type, data))
+	}
+
+	/// Browse for available services on a connection of the context
+	///
+	/// See [`browse`](fn.browse.html).
+	#[doc(alias = "DNSServiceBrowse")]
+	pub fn browse(&self, reg_type: &str) -> io::Result<Browse> {
+		self.browse_extended(reg_type, BrowseData::default())
+	}
+
+	/// Find hostname and port (and more) for a service on a connection
+	/// of the context
+	///
+	/// See [`resolve_extended`](fn.resolve_extended.html).
+	#[doc(alias = "DNSServiceResolve")]
+	pub fn resolve_extended(
+		&self,
+		interface: Interface,
+		name: &str,
+		reg_type: &str,
+		domain: impl Into<DomainName>,
+		data: ResolveData,
+	) -> io::Result<Resolve> {
+		let interface = self.policy.interface(interface)?;
+		Ok(resolve_on(
+			Some(&self.stream_connection()?),
+			interface,
+			name,
+			reg_type,
+			&domain.into(),
+			data,
+		))
+	}
+
+	/// Find hostname and port (and more) for a service on a connection
+	/// of the context
+	///
+	/// See [`resolve`](fn.resolve.html).
+	#[doc(alias = "DNSServiceResolve")]
+	pub fn resolve(
+		&self,
+		interface: Interface,
+		name: &str,
+		reg_type: &str,
+		domain: impl Into<DomainName>,
+	) -> io::Result<Resolve> {
+		self.resolve_extended(interface, name, reg_type, domain, ResolveData::default())
+	}
+
+	/// Query for an arbitrary DNS record on a connection of the context
+	///
+	/// See [`query_record_extended`](fn.query_record_extended.html).
+	#[doc(alias = "DNSServiceQueryRecord")]
+	pub fn query_record_extended(
+		&self,
+		fullname: &str,
+		rr_type: Type,
+		mut data: QueryRecordData,
+	) -> io::Result<QueryRecord> {
+		data.interface = self.policy.interface(data.interface)?;
+		Ok(query_record_on(
+			Some(&self.stream_connection()?),
+			fullname,
+			rr_type,
+			data,
+		))
+	}
+
+	/// Query for an arbitrary DNS record on a connection of the context
+	///
+	/// See [`query_record`](fn.query_record.html).
+	#[doc(alias = "DNSServiceQueryRecord")]
+	pub fn query_record(&self, fullname: &str, rr_type: Type) -> io::Result<QueryRecord> {
+		self.query_record_extended(fullname, rr_type, QueryRecordData::default())
+	}
+
+	// interface to enumerate default domains on (`Any` is always allowed)
+	fn domain_interface(&self) -> Interface {
+		self.policy
+			.interface(Interface::Any)
+			.unwrap_or(Interface::Any)
+	}
+
+	/// Find the default domain to browse for services in
+	///
+	/// Like [`default_browse_domain`](fn.default_browse_domain.html), but
+	/// enumerates on the interface of the policy and caches found
+	/// domains in the context.
+	#[doc(alias = "DNSServiceEnumerateDomains")]
+	pub fn default_browse_domain(&self) -> DefaultDomain {
+		default_domain(
+			Enumerate::BrowseDomains,
+			self.domain_interface(),
+			DomainCacheRef::context(&self.domains),
+		)
+	}
+
+	/// Find the default domain to register services on
+	///
+	/// Like
+	/// [`default_registration_domain`](fn.default_registration_domain.html),
+	/// but enumerates on the interface of the policy and caches found
+	/// domains in the context.
+	#[doc(alias = "DNSServiceEnumerateDomains")]
+	pub fn default_registration_domain(&self) -> DefaultDomain {
+		default_domain(
+			Enumerate::RegistrationDomains,
+			self.domain_interface(),
+			DomainCacheRef::context(&self.domains),
+		)
+	}
+
+	/// Stop all background tasks, close all connections and clear the
+	/// caches
+	///
+	/// Creating new connections, registrations or streams through the
+	/// context fails afterwards.
+	pub fn shutdown(&self) {
+		let services = self.services.lock().unwrap().take();
+		for service in services.into_iter().flatten() {
+			service.close();
+		}
+		self.streams.lock().unwrap().take();
+		self.domains.lock().unwrap().take();
+	}
+}
+
+impl Default for DnssdContext {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Drop for DnssdContext {
+	fn drop(&mut self) {
+		self.shutdown();
+	}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use crate::RecordDropPolicy;
+	use std::time::Duration;
+
+	#[tokio::test]
+	async fn shutdown_stops_background_tasks() {
+		let context = DnssdContext::new();
+		let service = SharedService::unconnected();
+		context.track(&service).unwrap();
+		service.set_linger(Some(Duration::from_secs(3600)));
+		let mut record = service.unregistered_record();
+		record.set_drop_policy(RecordDropPolicy::KeepForTtl);
+		drop(record);
+		let weak = service.downgrade();
+		drop(service);
+
+		// processing (lingering), linger timeout and record removal
+		assert_eq!(weak.running_tasks(), 3);
+		context.shutdown();
+		for _ in 0..10 {
+			tokio::task::yield_now().await;
+		}
+		assert_eq!(weak.running_tasks(), 0);
+		// aborted processing released the service
+		assert!(!weak.is_alive());
+	}
+
+	#[tokio::test]
+	async fn shutdown_closes_stream_connection() {
+		let context = DnssdContext::new();
+		let service = SharedService::unconnected();
+		context.track(&service).unwrap();
+		let weak = service.downgrade();
+		*context.streams.lock().unwrap() = Some(service);
+		// reused while it works
+		assert!(!context.stream_connection().unwrap().has_failed());
+
+		context.shutdown();
+		for _ in 0..10 {
+			tokio::task::yield_now().await;
+		}
+		assert!(!weak.is_alive());
+		assert!(context.stream_connection().is_err());
+	}
+
+	#[test]
+	fn interface_policy() {
+		let ndx = Interface::from_raw(2);
+		let context = DnssdContext::with_interface_policy(InterfacePolicy::Only(ndx));
+		assert_eq!(context.domain_interface(), ndx);
+		// rejected before connecting
+		let data = BrowseData {
+			interface: Interface::from_raw(3),
+			..Default::default()
+		};
+		let result = context.browse_extended("_ipp._tcp", data);
+		assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidInput));
+
+		let policy = InterfacePolicy::ExcludePeerToPeer;
+		assert_eq!(policy.interface(Interface::Any).unwrap(), Interface::Any);
+		assert!(policy.interface(Interface::PeerToPeer).is_err());
+		assert!(!policy.allows_peer_to_peer());
+	}
+}
@@ -1,15 +1,66 @@
 use std::{
 	borrow::Cow,
+	error,
 	ffi,
+	fmt,
 	io,
 	os::raw::c_char,
 	ptr::null,
 };
 
+use crate::config::{
+	name_decoding,
+	NameDecoding,
+};
+
 pub unsafe fn from_cstr(s: *const c_char) -> io::Result<&'static str> {
-	ffi::CStr::from_ptr(s)
-		.to_str()
-		.map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+	ffi::CStr::from_ptr(s).to_str().map_err(io::Error::other)
+}
+
+// error for results that should be dropped (`NameDecoding::Skip`)
+#[derive(Debug)]
+struct SkipResult;
+
+impl fmt::Display for SkipResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("skipped result with invalid name")
+	}
+}
+
+impl error::Error for SkipResult {}
+
+pub fn is_skip_result(e: &io::Error) -> bool {
+	e.get_ref().is_some_and(|e| e.is::<SkipResult>())
+}
+
+fn decode_name(s: &'static ffi::CStr, policy: NameDecoding) -> io::Result<Cow<'static, str>> {
+	match s.to_str() {
+		Ok(s) => Ok(Cow::Borrowed(s)),
+		Err(e) => match policy {
+			NameDecoding::Strict => Err(io::Error::other(e)),
+			NameDecoding::Lossy => Ok(s.to_string_lossy()),
+			NameDecoding::Skip => Err(io::Error::other(SkipResult)),
+		},
+	}
+}
+
+/// decode (display) name reported by the daemon according to the
+/// configured `NameDecoding`
+pub unsafe fn from_cstr_name(s: *const c_char) -> io::Result<Cow<'static, str>> {
+	decode_name(ffi::CStr::from_ptr(s), name_decoding())
+}
+
+/// decode name reported by the daemon that is passed back to it later
+/// (service types, domains, host and full names)
+///
+/// A lossy decoded name would refer to something else, so
+/// `NameDecoding::Lossy` is handled like `NameDecoding::Strict`.
+pub unsafe fn from_cstr_protocol_name(s: *const c_char) -> io::Result<Cow<'static, str>> {
+	let policy = match name_decoding() {
+		NameDecoding::Lossy => NameDecoding::Strict,
+		policy => policy,
+	};
+	decode_name(ffi::CStr::from_ptr(s), policy)
 }
 
 #[derive(Clone, Debug)]
@@ -26,6 +77,10 @@ impl<'a> CStr<'a> {
 	pub fn as_ptr(&self) -> *const c_char {
 		self.0.as_ptr()
 	}
+
+	pub fn to_string_lossy(&self) -> Cow<'_, str> {
+		self.0.to_string_lossy()
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -65,3 +120,24 @@ impl<'a, T: AsRef<str>> CStrFrom<'a, Option<T>> for NullableCStr<'a> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_invalid_name() {
+		let invalid = c"caf\xe9";
+		assert_eq!(
+			decode_name(invalid, NameDecoding::Lossy).unwrap(),
+			"caf\u{fffd}"
+		);
+		assert!(!is_skip_result(
+			&decode_name(invalid, NameDecoding::Strict).unwrap_err()
+		));
+		assert!(is_skip_result(
+			&decode_name(invalid, NameDecoding::Skip).unwrap_err()
+		));
+		assert_eq!(decode_name(c"cafe", NameDecoding::Strict).unwrap(), "cafe");
+	}
+}
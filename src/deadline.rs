@@ -0,0 +1,129 @@
+use futures_core::{
+	stream::FusedStream,
+	Stream,
+};
+use futures_util::StreamExt;
+use std::{
+	future::Future,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+
+use crate::clock::{
+	Clock,
+	TokioClock,
+};
+
+/// Ends a stream after a fixed time
+///
+/// The wrapped stream (i.e. the underlying `DNSServiceRef`) gets dropped
+/// right away when the timer fires.
+pub(crate) struct Deadline<S, C: Clock = TokioClock> {
+	stream: Option<S>,
+	timer: Option<Pin<Box<C::Sleep>>>,
+	timed_out: bool,
+}
+
+impl<S> Deadline<S> {
+	pub(crate) fn new(stream: S, timeout: Option<Duration>) -> Self {
+		Self::with_clock(stream, timeout, &TokioClock)
+	}
+}
+
+impl<S, C: Clock> Deadline<S, C> {
+	pub(crate) fn with_clock(stream: S, timeout: Option<Duration>, clock: &C) -> Self {
+		Self {
+			stream: Some(stream),
+			timer: timeout.map(|timeout| Box::pin(clock.sleep_until(clock.now() + timeout))),
+			timed_out: false,
+		}
+	}
+
+	// whether the stream ended because of the timeout
+	pub(crate) fn timed_out(&self) -> bool {
+		self.timed_out
+	}
+}
+
+impl<S: Stream + Unpin, C: Clock> Stream for Deadline<S, C> {
+	type Item = S::Item;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		let stream = match &mut this.stream {
+			Some(stream) => stream,
+			None => return Poll::Ready(None),
+		};
+		if let Poll::Ready(item) = stream.poll_next_unpin(cx) {
+			if item.is_none() {
+				this.stream = None;
+				this.timer = None;
+			}
+			return Poll::Ready(item);
+		}
+		if let Some(timer) = &mut this.timer {
+			if timer.as_mut().poll(cx).is_ready() {
+				this.stream = None;
+				this.timer = None;
+				this.timed_out = true;
+				return Poll::Ready(None);
+			}
+		}
+		Poll::Pending
+	}
+}
+
+impl<S: FusedStream + Unpin, C: Clock> FusedStream for Deadline<S, C> {
+	fn is_terminated(&self) -> bool {
+		self.stream.as_ref().is_none_or(S::is_terminated)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io;
+	use tokio::time::Instant;
+
+	#[tokio::test(start_paused = true)]
+	async fn drops_stream() {
+		let (tx, rx) = futures_channel::mpsc::unbounded::<io::Result<()>>();
+		let start = Instant::now();
+		let mut stream = Deadline::new(rx, Some(Duration::from_secs(10)));
+
+		tx.unbounded_send(Ok(())).unwrap();
+		stream.next().await.unwrap().unwrap();
+		assert!(stream.next().await.is_none());
+		assert!(stream.timed_out());
+		assert_eq!(start.elapsed(), Duration::from_secs(10));
+		// receiver got dropped
+		assert!(tx.is_closed());
+	}
+
+	// clock whose timers fire right away
+	struct Expired;
+
+	impl Clock for Expired {
+		type Sleep = futures_util::future::Ready<()>;
+
+		fn now(&self) -> Instant {
+			Instant::now()
+		}
+
+		fn sleep_until(&self, _deadline: Instant) -> Self::Sleep {
+			futures_util::future::ready(())
+		}
+	}
+
+	#[tokio::test]
+	async fn uses_clock() {
+		let (_tx, rx) = futures_channel::mpsc::unbounded::<io::Result<()>>();
+		let mut stream = Deadline::with_clock(rx, Some(Duration::from_secs(3600)), &Expired);
+		assert!(stream.next().await.is_none());
+		assert!(stream.timed_out());
+	}
+}
@@ -0,0 +1,252 @@
+use futures_core::{
+	Stream,
+	TryStream,
+};
+use std::{
+	collections::VecDeque,
+	future::Future,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+use tokio::time::Instant;
+
+use crate::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
+	service::{
+		BrowseResult,
+		BrowsedFlags,
+	},
+};
+
+/// `Stream` extension to simplify building
+/// [`BrowseDebounce`](struct.BrowseDebounce.html)
+pub trait BrowseDebounceExt: TryStream<Ok = BrowseResult> + Sized {
+	/// Create new [`BrowseDebounce`](struct.BrowseDebounce.html)
+	fn debounce(self, window: Duration) -> BrowseDebounce<Self>;
+}
+
+impl<S: TryStream<Ok = BrowseResult>> BrowseDebounceExt for S {
+	fn debounce(self, window: Duration) -> BrowseDebounce<Self> {
+		BrowseDebounce::new(self, window)
+	}
+}
+
+/// Suppress services flapping between removed and added
+///
+/// A removal is held back for the configured window; if the same
+/// service (see [`BrowseResult::service_key`]) gets added again within
+/// the window neither the removal nor the new addition are forwarded.
+///
+/// Removals that are forwarded after the window passed keep their
+/// flags, but the last held back removal never has `MORE_COMING` set
+/// (the results it announced might have been suppressed); when the
+/// underlying stream ends all held back removals are flushed.
+///
+/// [`BrowseResult::service_key`]: struct.BrowseResult.html#method.service_key
+#[must_use = "streams do nothing unless polled"]
+pub struct BrowseDebounce<S, C: Clock = TokioClock> {
+	stream: S,
+	window: Duration,
+	clock: C,
+	// removals ordered by deadline (all use the same window)
+	pending: VecDeque<(Instant, BrowseResult)>,
+	timer: Option<(Instant, Pin<Box<C::Sleep>>)>,
+	stream_done: bool,
+}
+
+impl<S: TryStream<Ok = BrowseResult>> BrowseDebounce<S> {
+	/// Create new `BrowseDebounce`.
+	///
+	/// Also see [`BrowseDebounceExt::debounce`](trait.BrowseDebounceExt.html#method.debounce).
+	pub fn new(stream: S, window: Duration) -> Self {
+		Self::with_clock(stream, window, TokioClock)
+	}
+}
+
+impl<S: TryStream<Ok = BrowseResult>, C: Clock> BrowseDebounce<S, C> {
+	/// Create new `BrowseDebounce` using the given [`Clock`](trait.Clock.html)
+	pub fn with_clock(stream: S, window: Duration, clock: C) -> Self {
+		Self {
+			stream,
+			window,
+			clock,
+			pending: VecDeque::new(),
+			timer: None,
+			stream_done: false,
+		}
+	}
+
+	fn stream(self: Pin<&mut Self>) -> Pin<&mut S> {
+		// `stream` is structurally pinned
+		unsafe { self.map_unchecked_mut(|s| &mut s.stream) }
+	}
+
+	fn fields(self: Pin<&mut Self>) -> &mut Self {
+		// `stream` is the only structurally pinned field; must not move it
+		unsafe { self.get_unchecked_mut() }
+	}
+
+	fn pop_pending(&mut self) -> Option<BrowseResult> {
+		let (_, mut removed) = self.pending.pop_front()?;
+		if self.pending.is_empty() {
+			removed.flags.remove(BrowsedFlags::MORE_COMING);
+		}
+		Some(removed)
+	}
+
+	fn pop_expired(&mut self, now: Instant) -> Option<BrowseResult> {
+		match self.pending.front() {
+			Some(&(deadline, _)) if deadline <= now => self.pop_pending(),
+			_ => None,
+		}
+	}
+
+	// returns true if a held back removal got canceled by `added`
+	fn cancel_removal(&mut self, added: &BrowseResult) -> bool {
+		let key = added.service_key();
+		let pos = self
+			.pending
+			.iter()
+			.position(|(_, removed)| removed.service_key() == key);
+		match pos {
+			Some(pos) => {
+				self.pending.remove(pos);
+				true
+			},
+			None => false,
+		}
+	}
+}
+
+impl<S: TryStream<Ok = BrowseResult>, C: Clock> Stream for BrowseDebounce<S, C> {
+	type Item = Result<BrowseResult, S::Error>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let now = self.clock.now();
+		if let Some(removed) = self.as_mut().fields().pop_expired(now) {
+			return Poll::Ready(Some(Ok(removed)));
+		}
+
+		while !self.stream_done {
+			match self.as_mut().stream().try_poll_next(cx) {
+				Poll::Ready(None) => {
+					self.as_mut().fields().stream_done = true;
+				},
+				Poll::Ready(Some(Ok(item))) => {
+					let this = self.as_mut().fields();
+					if !item.flags.contains(BrowsedFlags::ADD) {
+						let deadline = this.clock.now() + this.window;
+						this.pending.push_back((deadline, item));
+					} else if !this.cancel_removal(&item) {
+						return Poll::Ready(Some(Ok(item)));
+					}
+				},
+				Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+				Poll::Pending => break,
+			}
+		}
+
+		let this = self.as_mut().fields();
+		if this.stream_done {
+			// flush held back removals
+			return Poll::Ready(this.pop_pending().map(Ok));
+		}
+
+		let deadline = match this.pending.front() {
+			Some(&(deadline, _)) => deadline,
+			None => return Poll::Pending,
+		};
+		let timer = match &mut this.timer {
+			Some((timer_deadline, timer)) if *timer_deadline == deadline => timer,
+			timer => {
+				let sleep = Box::pin(this.clock.sleep_until(deadline));
+				&mut timer.insert((deadline, sleep)).1
+			},
+		};
+		futures_core::ready!(timer.as_mut().poll(cx));
+		Poll::Ready(this.pop_pending().map(Ok))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Interface;
+	use futures_util::StreamExt;
+	use std::io;
+
+	fn result(name: &str, added: bool) -> io::Result<BrowseResult> {
+		flagged(
+			name,
+			if added {
+				BrowsedFlags::ADD
+			} else {
+				BrowsedFlags::empty()
+			},
+		)
+	}
+
+	fn flagged(name: &str, flags: BrowsedFlags) -> io::Result<BrowseResult> {
+		Ok(BrowseResult {
+			flags,
+			interface: Interface::Any,
+			service_name: name.to_string(),
+			reg_type: "_ssh._tcp.".to_string(),
+			domain: "local.".to_string(),
+		})
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn suppresses_flapping() {
+		let (tx, rx) = futures_channel::mpsc::unbounded();
+		let mut debounced = rx.debounce(Duration::from_secs(2));
+
+		tx.unbounded_send(result("a", true)).unwrap();
+		tx.unbounded_send(result("b", true)).unwrap();
+		let names = debounced
+			.by_ref()
+			.take(2)
+			.map(|r| r.unwrap().service_name)
+			.collect::<Vec<_>>()
+			.await;
+		assert_eq!(names, ["a", "b"]);
+
+		// "a" flaps, "b" goes away
+		tx.unbounded_send(result("a", false)).unwrap();
+		tx.unbounded_send(result("b", false)).unwrap();
+		tx.unbounded_send(result("a", true)).unwrap();
+		let start = Instant::now();
+		let removed = debounced.next().await.unwrap().unwrap();
+		assert_eq!(removed.service_name, "b");
+		assert!(!removed.flags.contains(BrowsedFlags::ADD));
+		assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+		drop(tx);
+		assert!(debounced.next().await.is_none());
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn keeps_more_coming() {
+		let (tx, rx) = futures_channel::mpsc::unbounded();
+		let mut debounced = rx.debounce(Duration::from_secs(2));
+
+		tx.unbounded_send(flagged("a", BrowsedFlags::MORE_COMING))
+			.unwrap();
+		tx.unbounded_send(flagged("b", BrowsedFlags::MORE_COMING))
+			.unwrap();
+		let first = debounced.next().await.unwrap().unwrap();
+		assert_eq!(first.service_name, "a");
+		assert!(first.flags.contains(BrowsedFlags::MORE_COMING));
+		// nothing announced by "b" is held back anymore
+		let last = debounced.next().await.unwrap().unwrap();
+		assert_eq!(last.service_name, "b");
+		assert!(!last.flags.contains(BrowsedFlags::MORE_COMING));
+	}
+}
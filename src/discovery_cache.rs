@@ -0,0 +1,237 @@
+use futures_core::{
+	Stream,
+	TryStream,
+};
+use std::{
+	collections::{
+		HashMap,
+		VecDeque,
+	},
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::service::{
+	BrowseResult,
+	BrowsedFlags,
+	ServiceKey,
+};
+
+/// `Stream` extension to simplify building
+/// [`DiscoveryCache`](struct.DiscoveryCache.html)
+pub trait DiscoveryCacheExt: TryStream<Ok = BrowseResult> + Sized {
+	/// Create new [`DiscoveryCache`](struct.DiscoveryCache.html)
+	fn discovery_cache(self) -> DiscoveryCache<Self>;
+}
+
+impl<S: TryStream<Ok = BrowseResult>> DiscoveryCacheExt for S {
+	fn discovery_cache(self) -> DiscoveryCache<Self> {
+		DiscoveryCache::new(self)
+	}
+}
+
+/// Change of the service set tracked by
+/// [`DiscoveryCache`](struct.DiscoveryCache.html)
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum CacheEvent {
+	/// Service wasn't present before
+	Added(BrowseResult),
+	/// Service is gone; contains the last add result of it
+	Removed(BrowseResult),
+}
+
+/// Track the set of currently present services of a browse stream
+///
+/// Services are identified by [`BrowseResult::service_key`]; repeated
+/// adds of a present service only replace the stored result.  Changes
+/// are collected until a result without `MORE_COMING` arrives, then
+/// the set is updated and the changes are yielded as [`CacheEvent`]s;
+/// services added and removed within a batch are not reported at all.
+///
+/// The accessors show the set as of the last completed batch; all
+/// stored results have the `ADD` flag (and not `MORE_COMING`).  When the
+/// underlying stream ends an incomplete batch is applied too; the set
+/// is kept afterwards.
+///
+/// [`BrowseResult::service_key`]: struct.BrowseResult.html#method.service_key
+/// [`CacheEvent`]: enum.CacheEvent.html
+#[must_use = "streams do nothing unless polled"]
+pub struct DiscoveryCache<S> {
+	stream: S,
+	services: HashMap<ServiceKey, BrowseResult>,
+	// latest state per service in the current batch (`None`: removed)
+	batch: Vec<(ServiceKey, Option<BrowseResult>)>,
+	pending: VecDeque<CacheEvent>,
+	stream_done: bool,
+}
+
+impl<S: TryStream<Ok = BrowseResult>> DiscoveryCache<S> {
+	/// Create new `DiscoveryCache`.
+	///
+	/// Also see [`DiscoveryCacheExt::discovery_cache`](trait.DiscoveryCacheExt.html#method.discovery_cache).
+	pub fn new(stream: S) -> Self {
+		Self {
+			stream,
+			services: HashMap::new(),
+			batch: Vec::new(),
+			pending: VecDeque::new(),
+			stream_done: false,
+		}
+	}
+
+	/// Currently present services
+	pub fn services(&self) -> impl Iterator<Item = &BrowseResult> + '_ {
+		self.services.values()
+	}
+
+	/// Copy of the currently present services (sorted by service key)
+	pub fn snapshot(&self) -> Vec<BrowseResult> {
+		let mut services: Vec<BrowseResult> = self.services.values().cloned().collect();
+		services.sort_by_key(BrowseResult::service_key);
+		services
+	}
+
+	/// Lookup a present service
+	pub fn get(&self, key: &ServiceKey) -> Option<&BrowseResult> {
+		self.services.get(key)
+	}
+
+	/// Number of present services
+	pub fn len(&self) -> usize {
+		self.services.len()
+	}
+
+	/// Whether no services are present
+	pub fn is_empty(&self) -> bool {
+		self.services.is_empty()
+	}
+
+	fn stream(self: Pin<&mut Self>) -> Pin<&mut S> {
+		// `stream` is structurally pinned
+		unsafe { self.map_unchecked_mut(|s| &mut s.stream) }
+	}
+
+	fn fields(self: Pin<&mut Self>) -> &mut Self {
+		// `stream` is the only structurally pinned field; must not move it
+		unsafe { self.get_unchecked_mut() }
+	}
+
+	fn handle(&mut self, mut result: BrowseResult) {
+		let more_coming = result.flags.contains(BrowsedFlags::MORE_COMING);
+		let key = result.service_key();
+		let state = if result.flags.contains(BrowsedFlags::ADD) {
+			result.flags.remove(BrowsedFlags::MORE_COMING);
+			Some(result)
+		} else {
+			None
+		};
+		match self.batch.iter_mut().find(|(k, _)| *k == key) {
+			Some((_, s)) => *s = state,
+			None => self.batch.push((key, state)),
+		}
+		if !more_coming {
+			self.apply_batch();
+		}
+	}
+
+	fn apply_batch(&mut self) {
+		for (key, state) in self.batch.drain(..) {
+			match state {
+				Some(result) => {
+					if self.services.insert(key, result.clone()).is_none() {
+						self.pending.push_back(CacheEvent::Added(result));
+					}
+				},
+				None => {
+					if let Some(result) = self.services.remove(&key) {
+						self.pending.push_back(CacheEvent::Removed(result));
+					}
+				},
+			}
+		}
+	}
+}
+
+impl<S: TryStream<Ok = BrowseResult>> Stream for DiscoveryCache<S> {
+	type Item = Result<CacheEvent, S::Error>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		loop {
+			if let Some(event) = self.as_mut().fields().pending.pop_front() {
+				return Poll::Ready(Some(Ok(event)));
+			}
+			if self.stream_done {
+				return Poll::Ready(None);
+			}
+			match futures_core::ready!(self.as_mut().stream().try_poll_next(cx)) {
+				Some(Ok(result)) => self.as_mut().fields().handle(result),
+				Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+				None => {
+					let this = self.as_mut().fields();
+					this.stream_done = true;
+					this.apply_batch();
+				},
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Interface;
+	use futures_util::StreamExt;
+	use std::io;
+
+	fn result(name: &str, flags: BrowsedFlags) -> io::Result<BrowseResult> {
+		Ok(BrowseResult {
+			flags,
+			interface: Interface::Any,
+			service_name: name.to_string(),
+			reg_type: "_ssh._tcp.".to_string(),
+			domain: "local.".to_string(),
+		})
+	}
+
+	#[tokio::test]
+	async fn batches() {
+		let add = BrowsedFlags::ADD;
+		let more = BrowsedFlags::ADD | BrowsedFlags::MORE_COMING;
+		let (tx, rx) = futures_channel::mpsc::unbounded();
+		let mut cache = rx.discovery_cache();
+
+		// "b" is added and removed within the batch, "a" added twice
+		tx.unbounded_send(result("a", more)).unwrap();
+		tx.unbounded_send(result("b", more)).unwrap();
+		tx.unbounded_send(result("b", BrowsedFlags::MORE_COMING))
+			.unwrap();
+		tx.unbounded_send(result("a", add)).unwrap();
+		tx.unbounded_send(result("c", add)).unwrap();
+		match cache.next().await.unwrap().unwrap() {
+			CacheEvent::Added(r) => assert_eq!(r.service_name, "a"),
+			e => panic!("unexpected {:?}", e),
+		}
+		assert_eq!(cache.len(), 1);
+		assert!(!cache.snapshot()[0]
+			.flags
+			.contains(BrowsedFlags::MORE_COMING));
+
+		tx.unbounded_send(result("a", BrowsedFlags::empty()))
+			.unwrap();
+		drop(tx);
+		let events = cache.by_ref().map(|e| e.unwrap()).collect::<Vec<_>>().await;
+		assert!(matches!(&events[..], [
+			CacheEvent::Added(c),
+			CacheEvent::Removed(a),
+		] if c.service_name == "c" && a.service_name == "a"));
+		let names: Vec<_> = cache
+			.snapshot()
+			.into_iter()
+			.map(|r| r.service_name)
+			.collect();
+		assert_eq!(names, ["c"]);
+	}
+}
@@ -0,0 +1,22 @@
+use std::io;
+
+use async_dnssd_types::name;
+
+/// Decode wire format name at `start` of `message`
+///
+/// See [`name::read_name`]; errors are reported as `InvalidData`.
+pub(crate) fn read_name(
+	message: &[u8],
+	start: usize,
+	compression: bool,
+) -> io::Result<(String, usize)> {
+	name::read_name(message, start, compression)
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Encode name in presentation format as uncompressed wire format
+///
+/// Errors are reported as `InvalidInput`.
+pub(crate) fn encode_name(name: &str) -> io::Result<Vec<u8>> {
+	name::encode_name(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
@@ -0,0 +1,193 @@
+use async_dnssd_types::name::{
+	split_labels,
+	unescape_label,
+	MAX_LABEL,
+	MAX_WIRE,
+};
+use std::{
+	fmt,
+	io,
+	str::FromStr,
+};
+
+/// Domain name in presentation format (labels separated by dots, special
+/// characters escaped as `\.` or `\DDD`)
+///
+/// Always stored with a trailing dot (`"local"` becomes `"local."`).
+/// [`new`](#method.new) checks the escaping and the label and name
+/// lengths; the `From` conversions (used by functions taking
+/// `impl Into<DomainName>`) remember a failed check and the function
+/// using the name reports it as `InvalidInput` error.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DomainName {
+	name: String,
+	error: Option<&'static str>,
+}
+
+impl DomainName {
+	/// Normalize and check name
+	pub fn new(name: &str) -> io::Result<Self> {
+		let name = Self::from(name);
+		name.check()?;
+		Ok(name)
+	}
+
+	/// The root domain `.`
+	pub fn root() -> Self {
+		Self {
+			name: ".".to_string(),
+			error: None,
+		}
+	}
+
+	/// Name in presentation format (with trailing dot)
+	pub fn as_str(&self) -> &str {
+		&self.name
+	}
+
+	/// Whether the name passed all checks
+	pub fn is_valid(&self) -> bool {
+		self.error.is_none()
+	}
+
+	/// Number of labels (zero for the root domain)
+	pub fn label_count(&self) -> usize {
+		split_labels(&self.name).map_or(0, |labels| labels.len())
+	}
+
+	/// Encode as uncompressed wire format (e.g. for rdata of records
+	/// being registered)
+	pub fn to_wire(&self) -> io::Result<Vec<u8>> {
+		self.check()?;
+		crate::dns_name::encode_name(&self.name)
+	}
+
+	/// Decode wire format name at `offset` in `message`
+	///
+	/// `message` is the data compression pointers are relative to (the
+	/// full DNS message); the daemon delivers rdata uncompressed, so for
+	/// names in rdata (SRV targets, PTR, NSEC next names) pass the rdata.
+	/// Compression pointers must point backwards (before the previous
+	/// jump target), which rules out loops; the name is limited to 255
+	/// bytes in wire format.
+	///
+	/// Returns the name and the number of bytes it used at `offset`.
+	/// Bytes that aren't valid UTF-8 are escaped as `\DDD`.
+	pub fn from_wire(message: &[u8], offset: usize) -> io::Result<(Self, usize)> {
+		let (name, used) = crate::dns_name::read_name(message, offset, true)?;
+		let name = Self::from(name);
+		name.check()
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		Ok((name, used))
+	}
+
+	pub(crate) fn check(&self) -> io::Result<()> {
+		match self.error {
+			None => Ok(()),
+			Some(e) => Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("invalid domain name {:?}: {}", self.name, e),
+			)),
+		}
+	}
+
+	fn validate(name: &str) -> Result<(), &'static str> {
+		if name == "." {
+			return Ok(());
+		}
+		let labels = split_labels(name).ok_or("empty label or broken escape")?;
+		if labels.is_empty() {
+			return Err("empty name");
+		}
+		let mut wire_len = 1; // root label
+		for label in labels {
+			let label = unescape_label(label).ok_or("invalid escape")?;
+			if label.len() > MAX_LABEL {
+				return Err("label too long");
+			}
+			wire_len += 1 + label.len();
+		}
+		if wire_len > MAX_WIRE || name.len() >= crate::ffi::MAX_DOMAIN_NAME {
+			return Err("name too long");
+		}
+		Ok(())
+	}
+}
+
+impl From<&str> for DomainName {
+	fn from(name: &str) -> Self {
+		let mut name = name.to_string();
+		if !name.is_empty() && !ends_with_dot(&name) {
+			name.push('.');
+		}
+		let error = Self::validate(&name).err();
+		Self { name, error }
+	}
+}
+
+impl From<&String> for DomainName {
+	fn from(name: &String) -> Self {
+		Self::from(name.as_str())
+	}
+}
+
+impl From<String> for DomainName {
+	fn from(name: String) -> Self {
+		Self::from(name.as_str())
+	}
+}
+
+impl FromStr for DomainName {
+	type Err = io::Error;
+
+	fn from_str(name: &str) -> io::Result<Self> {
+		Self::new(name)
+	}
+}
+
+impl AsRef<str> for DomainName {
+	fn as_ref(&self) -> &str {
+		&self.name
+	}
+}
+
+impl fmt::Display for DomainName {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.name)
+	}
+}
+
+impl fmt::Debug for DomainName {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(&self.name, f)
+	}
+}
+
+// whether the name ends with an unescaped dot
+fn ends_with_dot(name: &str) -> bool {
+	match name.strip_suffix('.') {
+		Some(rest) => rest.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 0,
+		None => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::DomainName;
+
+	#[test]
+	fn normalize_and_check() {
+		assert_eq!(DomainName::new("local").unwrap().as_str(), "local.");
+		assert_eq!(
+			DomainName::new("example.com.").unwrap().as_str(),
+			"example.com."
+		);
+		assert_eq!(DomainName::new("a\\.b").unwrap().as_str(), "a\\.b.");
+		assert_eq!(DomainName::new(".").unwrap().label_count(), 0);
+		assert!(DomainName::new("").is_err());
+		assert!(DomainName::new("a..b").is_err());
+		assert!(DomainName::new("a\\300").is_err());
+		assert!(DomainName::new(&"x".repeat(64)).is_err());
+		assert!(!DomainName::from("a..b").is_valid());
+	}
+}
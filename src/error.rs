@@ -7,6 +7,9 @@ use std::{
 use crate::ffi;
 
 /// API Error
+///
+/// New variants might be added in minor releases.
+#[non_exhaustive]
 pub enum Error {
 	/// If error code used some recognized name
 	KnownError(ffi::DNSServiceError),
@@ -14,6 +17,14 @@ pub enum Error {
 	UnknownError(i32),
 	/// IO error
 	IoError(io::Error),
+	/// Client library and daemon versions are incompatible (only
+	/// detected on Windows)
+	Incompatible {
+		/// version of the client library
+		client_version: u32,
+		/// version of the daemon
+		server_version: u32,
+	},
 }
 
 impl Error {
@@ -32,6 +43,180 @@ impl Error {
 	}
 }
 
+/// Rough classification of [`Error`](enum.Error.html)s
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ErrorCategory {
+	/// Daemon not available, incompatible or refusing the request
+	Daemon,
+	/// Invalid parameters or conflicting names
+	Input,
+	/// Lookups that failed or timed out (might succeed later)
+	Network,
+	/// Anything else (including unknown error codes)
+	Internal,
+}
+
+impl Error {
+	/// Extract `Error` from an `io::Error` (as returned by most
+	/// operations)
+	///
+	/// Looks through an [`OperationError`](struct.OperationError.html)
+	/// wrapping it.
+	pub fn from_io_error(e: &io::Error) -> Option<&Self> {
+		let inner = e.get_ref()?;
+		match inner.downcast_ref::<OperationError>() {
+			Some(op) => Self::from_io_error(&op.error),
+			None => inner.downcast_ref::<Self>(),
+		}
+	}
+
+	fn known(&self) -> Option<ffi::DNSServiceError> {
+		match self {
+			Self::KnownError(e) => Some(*e),
+			Self::IoError(e) => Self::from_io_error(e).and_then(Self::known),
+			_ => None,
+		}
+	}
+
+	/// Whether the name is already in use (e.g. registering a unique
+	/// record)
+	pub fn is_name_conflict(&self) -> bool {
+		self.known() == Some(ffi::DNSServiceError::NameConflict)
+	}
+
+	/// Whether the daemon isn't running (or the connection to it broke)
+	pub fn is_service_not_running(&self) -> bool {
+		matches!(
+			self.known(),
+			Some(ffi::DNSServiceError::ServiceNotRunning | ffi::DNSServiceError::DefunctConnection)
+		)
+	}
+
+	/// Whether retrying the operation later might succeed
+	pub fn is_transient(&self) -> bool {
+		use ffi::DNSServiceError::*;
+		match self {
+			Self::IoError(e) if Self::from_io_error(e).is_none() => matches!(
+				e.kind(),
+				io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+			),
+			_ => matches!(
+				self.known(),
+				Some(Transient | Timeout | ServiceNotRunning | DefunctConnection | NoMemory)
+			),
+		}
+	}
+
+	/// Classify error
+	pub fn category(&self) -> ErrorCategory {
+		use ffi::DNSServiceError::*;
+		match self {
+			Self::Incompatible { .. } => return ErrorCategory::Daemon,
+			Self::IoError(e) if Self::from_io_error(e).is_none() => {
+				return match e.kind() {
+					io::ErrorKind::TimedOut => ErrorCategory::Network,
+					io::ErrorKind::NotConnected | io::ErrorKind::ConnectionRefused => {
+						ErrorCategory::Daemon
+					},
+					io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => {
+						ErrorCategory::Input
+					},
+					_ => ErrorCategory::Internal,
+				};
+			},
+			_ => (),
+		}
+		match self.known() {
+			Some(
+				ServiceNotRunning | DefunctConnection | Incompatible | NotInitialized | Refused
+				| Unsupported,
+			) => ErrorCategory::Daemon,
+			Some(
+				BadParam | BadFlags | BadInterfaceIndex | Invalid | NameConflict
+				| AlreadyRegistered | NoSuchKey,
+			) => ErrorCategory::Input,
+			Some(NoSuchName | NoSuchRecord | NoAuth | Transient | Timeout) => {
+				ErrorCategory::Network
+			},
+			_ => ErrorCategory::Internal,
+		}
+	}
+}
+
+/// Error of an operation, with the operation and its key arguments
+///
+/// Errors returned by futures and streams of this crate carry this
+/// inside the `io::Error` (keeping the error kind); the display shows
+/// the operation first, e.g. `register _http._tcp. name="Foo": name
+/// conflict`.
+///
+/// [`Error::from_io_error`](enum.Error.html#method.from_io_error) looks
+/// through it.
+#[derive(Debug)]
+pub struct OperationError {
+	operation: String,
+	error: io::Error,
+}
+
+impl OperationError {
+	/// Extract `OperationError` from an `io::Error`
+	pub fn from_io_error(e: &io::Error) -> Option<&Self> {
+		e.get_ref().and_then(|e| e.downcast_ref::<Self>())
+	}
+
+	/// Operation and its key arguments (e.g. `browse _http._tcp.`)
+	pub fn operation(&self) -> &str {
+		&self.operation
+	}
+
+	/// Underlying error
+	pub fn error(&self) -> &io::Error {
+		&self.error
+	}
+}
+
+impl fmt::Display for OperationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.operation, self.error)
+	}
+}
+
+impl error::Error for OperationError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
+// attach operation to error (unless there is one already); keeps the
+// error kind
+pub(crate) fn with_context(e: io::Error, operation: &str) -> io::Error {
+	if OperationError::from_io_error(&e).is_some() {
+		return e;
+	}
+	io::Error::new(
+		e.kind(),
+		OperationError {
+			operation: operation.to_string(),
+			error: e,
+		},
+	)
+}
+
+// whether the connection to the daemon broke (e.g. because the daemon
+// got restarted)
+pub(crate) fn is_daemon_gone(e: &io::Error) -> bool {
+	Error::from_io_error(e).is_some_and(Error::is_service_not_running)
+}
+
+// errors a new attempt of the operation might not run into
+pub(crate) fn is_daemon_transient(e: &io::Error) -> bool {
+	use ffi::DNSServiceError::*;
+	matches!(
+		Error::from_io_error(e).and_then(Error::known),
+		Some(ServiceNotRunning | Transient | DefunctConnection)
+	)
+}
+
 impl From<io::Error> for Error {
 	fn from(e: io::Error) -> Self {
 		Self::IoError(e)
@@ -42,7 +227,7 @@ impl From<Error> for io::Error {
 	fn from(e: Error) -> Self {
 		match e {
 			Error::IoError(e) => e,
-			e => Self::new(io::ErrorKind::Other, e),
+			e => Self::other(e),
 		}
 	}
 }
@@ -53,6 +238,14 @@ impl fmt::Debug for Error {
 			Self::KnownError(ffi_err) => write!(f, "known error {:?}: {}", ffi_err, ffi_err),
 			Self::UnknownError(e) => write!(f, "unknown error code: {:?}", e),
 			Self::IoError(e) => write!(f, "io error: {:?}", e),
+			Self::Incompatible {
+				client_version,
+				server_version,
+			} => write!(
+				f,
+				"incompatible versions: client {:#x}, daemon {:#x}",
+				client_version, server_version
+			),
 		}
 	}
 }
@@ -62,6 +255,14 @@ impl fmt::Display for Error {
 			Self::KnownError(ffi_err) => write!(f, "{}", ffi_err),
 			Self::UnknownError(e) => write!(f, "unknown error code: {:?}", e),
 			Self::IoError(e) => write!(f, "io error: {}", e),
+			Self::Incompatible {
+				client_version,
+				server_version,
+			} => write!(
+				f,
+				"client library (version {:#x}) incompatible with daemon (version {:#x})",
+				client_version, server_version
+			),
 		}
 	}
 }
@@ -71,6 +272,7 @@ impl error::Error for Error {
 			Self::KnownError(ffi_err) => Some(ffi_err),
 			Self::UnknownError(_) => None,
 			Self::IoError(e) => Some(e),
+			Self::Incompatible { .. } => None,
 		}
 	}
 }
@@ -100,6 +302,10 @@ impl ffi::DNSServiceError {
 			NoSuchKey => "no such key",
 			NoValue => "no value",
 			BufferTooSmall => "buffer too small",
+			Transient => "transient failure",
+			ServiceNotRunning => "service not running",
+			Timeout => "timeout",
+			DefunctConnection => "defunct connection to daemon",
 		}
 	}
 }
@@ -129,4 +335,50 @@ mod tests {
 			"no auth"
 		);
 	}
+
+	#[test]
+	fn categories() {
+		let conflict = Error::KnownError(ffi::DNSServiceError::NameConflict);
+		assert!(conflict.is_name_conflict());
+		assert!(!conflict.is_transient());
+		assert_eq!(conflict.category(), ErrorCategory::Input);
+
+		// wrapped in io::Error
+		let gone: io::Error = Error::KnownError(ffi::DNSServiceError::ServiceNotRunning).into();
+		let gone = Error::IoError(gone);
+		assert!(gone.is_service_not_running());
+		assert!(gone.is_transient());
+		assert_eq!(gone.category(), ErrorCategory::Daemon);
+
+		let timeout = Error::IoError(io::ErrorKind::TimedOut.into());
+		assert!(timeout.is_transient());
+		assert_eq!(timeout.category(), ErrorCategory::Network);
+	}
+
+	#[test]
+	fn context() {
+		let e = with_context(
+			Error::KnownError(ffi::DNSServiceError::ServiceNotRunning).into(),
+			"query_record foo._ipp._tcp.local. TXT",
+		);
+		assert!(is_daemon_gone(&e));
+		assert_eq!(
+			e.to_string(),
+			"query_record foo._ipp._tcp.local. TXT: service not running"
+		);
+		// the wrapped error is still matchable
+		assert!(matches!(
+			Error::from_io_error(&e),
+			Some(Error::KnownError(ffi::DNSServiceError::ServiceNotRunning))
+		));
+		// keeps kind, doesn't nest
+		let e = with_context(io::ErrorKind::TimedOut.into(), "browse _http._tcp");
+		let e = with_context(e, "other");
+		assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+		assert_eq!(
+			OperationError::from_io_error(&e).map(OperationError::operation),
+			Some("browse _http._tcp")
+		);
+		assert!(Error::from_io_error(&e).is_none());
+	}
 }
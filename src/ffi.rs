@@ -29,16 +29,72 @@ pub const FLAGS_REGISTRATION_DOMAINS: DNSServiceFlags = 0x80;
 pub const FLAGS_LONG_LIVED_QUERY: DNSServiceFlags = 0x100;
 #[cfg(not(unix))]
 pub const FLAGS_LONG_LIVED_QUERY: DNSServiceFlags = 0;
-// avahi only?
-// pub const FLAGS_ALLOW_REMOTE_QUERY: DNSServiceFlags = 0x200;
-// pub const FLAGS_FORCE_MULTICAS: DNSServiceFlags = 0x400;
-// pub const FLAGS_RETURN_CNAME: DNSServiceFlags = 0x800;
+// same bit as avahi's FLAGS_RETURN_CNAME
+pub const FLAGS_FORCE: DNSServiceFlags = 0x800;
+pub const FLAGS_RETURN_INTERMEDIATES: DNSServiceFlags = 0x1000;
+pub const FLAGS_NON_BROWSABLE: DNSServiceFlags = 0x2000;
+pub const FLAGS_SHARE_CONNECTION: DNSServiceFlags = 0x4000;
+pub const FLAGS_SUPPRESS_UNUSABLE: DNSServiceFlags = 0x8000;
+pub const FLAGS_TIMEOUT: DNSServiceFlags = 0x10000;
+pub const FLAGS_INCLUDE_P2P: DNSServiceFlags = 0x20000;
+pub const FLAGS_WAKE_ON_RESOLVE: DNSServiceFlags = 0x40000;
+pub const FLAGS_INCLUDE_AWDL: DNSServiceFlags = 0x100000;
+pub const FLAGS_UNICAST_RESPONSE: DNSServiceFlags = 0x400000;
+// DNSSEC; mDNSResponder reuses bits of other flags for the status in
+// results, always combined with FLAGS_VALIDATE
+pub const FLAGS_VALIDATE: DNSServiceFlags = 0x200000;
+pub const FLAGS_VALIDATE_OPTIONAL: DNSServiceFlags = 0x800000;
+pub const FLAGS_SECURE: DNSServiceFlags = 0x200010;
+pub const FLAGS_INSECURE: DNSServiceFlags = 0x200020;
+pub const FLAGS_BOGUS: DNSServiceFlags = 0x200040;
+pub const FLAGS_INDETERMINATE: DNSServiceFlags = 0x200080;
+// same bit as kDNSServiceFlagsExpiredAnswer in results
+pub const FLAGS_ALLOW_EXPIRED_ANSWERS: DNSServiceFlags = 0x80000000;
+pub const FLAGS_EXPIRED_ANSWER: DNSServiceFlags = 0x80000000;
+// network policy; only in mDNSResponder on Apple platforms
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const FLAGS_DENY_CELLULAR: DNSServiceFlags = 0x8000000;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const FLAGS_DENY_EXPENSIVE: DNSServiceFlags = 0x20000000;
+// from older dns_sd.h versions (the avahi compat header still is one);
+// current mDNSResponder uses the same values for the first two, but
+// 0x800 became kDNSServiceFlagsForce/KnownUnique
+pub const FLAGS_ALLOW_REMOTE_QUERY: DNSServiceFlags = 0x200;
+pub const FLAGS_FORCE_MULTICAST: DNSServiceFlags = 0x400;
+pub const FLAGS_RETURN_CNAME: DNSServiceFlags = 0x800;
 
 /// Maximum length of full name including trailing dot and terminating NULL
 ///
 /// See [`kDNSServiceMaxDomainName`](https://developer.apple.com/documentation/dnssd/kdnsservicemaxdomainname)
 pub const MAX_DOMAIN_NAME: usize = 1009;
 
+// `DNSServiceGetAddrInfo` isn't implemented by the avahi compat layer
+#[cfg(not(dnssd_avahi))]
+pub type DNSServiceProtocol = u32;
+#[cfg(not(dnssd_avahi))]
+pub const PROTOCOL_IPV4: DNSServiceProtocol = 0x01;
+#[cfg(not(dnssd_avahi))]
+pub const PROTOCOL_IPV6: DNSServiceProtocol = 0x02;
+
+#[cfg(all(unix, not(dnssd_avahi)))]
+pub use libc::{
+	sockaddr,
+	sockaddr_in,
+	sockaddr_in6,
+	AF_INET,
+	AF_INET6,
+};
+#[cfg(all(windows, not(dnssd_avahi)))]
+pub use winapi::shared::{
+	ws2def::{
+		AF_INET,
+		AF_INET6,
+		SOCKADDR as sockaddr,
+		SOCKADDR_IN as sockaddr_in,
+	},
+	ws2ipdef::SOCKADDR_IN6_LH as sockaddr_in6,
+};
+
 pub const INTERFACE_INDEX_ANY: u32 = 0;
 pub const INTERFACE_INDEX_LOCAL_ONLY: u32 = !0;
 pub const INTERFACE_INDEX_UNICAST: u32 = !1;
@@ -107,6 +163,10 @@ DNSServiceError: i32 =>
 	NoSuchKey             = -65556,
 	NoValue               = -65557,
 	BufferTooSmall        = -65558,
+	Transient             = -65562,
+	ServiceNotRunning     = -65563,
+	Timeout               = -65568,
+	DefunctConnection     = -65569,
 }
 
 pub type DNSServiceDomainEnumReply = Option<
@@ -289,11 +349,76 @@ extern "C" {
 	) -> c_int;
 }
 
+// only available in mDNSResponder on Apple platforms
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub type DNSServiceSleepKeepaliveReply = Option<
+	unsafe extern "C" fn(
+		sd_ref: DNSServiceRef,
+		error_code: DNSServiceErrorType,
+		context: *mut c_void,
+	),
+>;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+extern "C" {
+	pub fn DNSServiceCreateDelegateConnection(
+		sd_ref: *mut DNSServiceRef,
+		pid: i32,
+		uuid: *const u8, // uuid_t: [u8; 16]
+	) -> DNSServiceErrorType;
+	pub fn DNSServiceSleepKeepalive(
+		sd_ref: *mut DNSServiceRef,
+		flags: DNSServiceFlags,
+		fd: c_int,
+		timeout: u32,
+		callback: DNSServiceSleepKeepaliveReply,
+		context: *mut c_void,
+	) -> DNSServiceErrorType;
+}
+
+#[cfg(not(dnssd_avahi))]
+pub type DNSServiceGetAddrInfoReply = Option<
+	unsafe extern "C" fn(
+		sd_ref: DNSServiceRef,
+		flags: DNSServiceFlags,
+		interface_index: u32,
+		error_code: DNSServiceErrorType,
+		hostname: *const c_char,
+		address: *const sockaddr,
+		ttl: u32,
+		context: *mut c_void,
+	),
+>;
+
+#[cfg(not(dnssd_avahi))]
+extern "C" {
+	pub fn DNSServiceGetAddrInfo(
+		sd_ref: *mut DNSServiceRef,
+		flags: DNSServiceFlags,
+		interface_index: u32,
+		protocol: DNSServiceProtocol,
+		hostname: *const c_char,
+		callback: DNSServiceGetAddrInfoReply,
+		context: *mut c_void,
+	) -> DNSServiceErrorType;
+}
+
+pub const PROPERTY_DAEMON_VERSION: &[u8] = b"DaemonVersion\0";
+
+#[cfg(not(dnssd_avahi))]
+extern "C" {
+	pub fn DNSServiceGetProperty(
+		property: *const c_char,
+		result: *mut c_void,
+		size: *mut u32,
+	) -> DNSServiceErrorType;
+}
+
 // TXTRecordRef utils not wrapped - should be easy enough to implement
 // in pure rust
 
-/* Not used so far:
 #[cfg(windows)]
+#[allow(dead_code)]
 mod ffi_windows {
 	use super::DNSServiceErrorType;
 	use std::os::raw::{
@@ -333,11 +458,8 @@ mod ffi_windows {
 			inCode: DNSPropertyCode,
 			outData: *mut c_void,
 		) -> DNSServiceErrorType;
-		pub fn DNSServiceReleaseProperty(
-			inData: *mut c_void,
-		) -> DNSServiceErrorType;
+		pub fn DNSServiceReleaseProperty(inData: *mut c_void) -> DNSServiceErrorType;
 	}
 }
 #[cfg(windows)]
 pub use self::ffi_windows::*;
-*/
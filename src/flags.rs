@@ -0,0 +1,66 @@
+use std::fmt;
+
+/// Display flags as names joined by `|`; unknown bits are shown in hex
+///
+/// Created by [`display_flags`](fn.display_flags.html).
+pub struct DisplayFlags<'a, F>(&'a F);
+
+impl<F> fmt::Display for DisplayFlags<'_, F>
+where
+	F: bitflags::Flags,
+	F::Bits: bitflags::parser::WriteHex,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.0.is_empty() {
+			return f.write_str("(empty)");
+		}
+		bitflags::parser::to_writer(self.0, f)
+	}
+}
+
+impl<F> fmt::Debug for DisplayFlags<'_, F>
+where
+	F: bitflags::Flags,
+	F::Bits: bitflags::parser::WriteHex,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+/// Format flags for diagnostics
+///
+/// Known flags are shown by name, bits this crate doesn't know about (yet)
+/// in hex, e.g. `ADD | MORE_COMING | 0x40000`.  Flags reported by the
+/// daemon keep unknown bits, so new daemon flags remain visible.
+///
+/// To handle the flags one by one use `iter_names()` on the flags type.
+pub fn display_flags<F>(flags: &F) -> DisplayFlags<'_, F>
+where
+	F: bitflags::Flags,
+	F::Bits: bitflags::parser::WriteHex,
+{
+	DisplayFlags(flags)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::BrowsedFlags;
+
+	#[test]
+	fn unknown_bits() {
+		let flags = BrowsedFlags::from_bits_retain(
+			(BrowsedFlags::ADD | BrowsedFlags::MORE_COMING).bits() | 0x4_0000,
+		);
+		assert_eq!(
+			flags.iter_names().map(|(name, _)| name).collect::<Vec<_>>(),
+			["MORE_COMING", "ADD"]
+		);
+		assert_eq!(
+			display_flags(&flags).to_string(),
+			"MORE_COMING | ADD | 0x40000"
+		);
+		assert_eq!(display_flags(&BrowsedFlags::empty()).to_string(), "(empty)");
+	}
+}
@@ -1,4 +1,5 @@
 use futures_core::{
+	stream::FusedStream,
 	Stream,
 	TryStream,
 };
@@ -15,6 +16,8 @@ enum Inner<E, S> {
 	Stream(S),
 }
 
+// yields `None` permanently (and drops the inner stream) after the inner
+// stream ended or failed to start
 pub(crate) struct FusedErrorStream<S: TryStream>(Inner<S::Error, S>);
 
 impl<S: TryStream> From<Result<S, S::Error>> for FusedErrorStream<S> {
@@ -34,7 +37,8 @@ where
 	type Item = S::Item;
 
 	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		match &mut unsafe { self.get_unchecked_mut() }.0 {
+		let this = unsafe { self.get_unchecked_mut() };
+		match &mut this.0 {
 			Inner::Err(e) => {
 				// "error variant" is `Unpin`; extract error and fuse stream
 				match e.take() {
@@ -42,7 +46,51 @@ where
 					None => Poll::Ready(None), // error already returned before
 				}
 			},
-			Inner::Stream(s) => unsafe { Pin::new_unchecked(s) }.poll_next(cx),
+			Inner::Stream(s) => {
+				let item = futures_core::ready!(unsafe { Pin::new_unchecked(s) }.poll_next(cx));
+				if item.is_none() {
+					// drops the stream in place, fine for pinned data
+					this.0 = Inner::Err(None);
+				}
+				Poll::Ready(item)
+			},
 		}
 	}
 }
+
+impl<S, T, E> FusedStream for FusedErrorStream<S>
+where
+	S: Stream<Item = Result<T, E>>,
+	E: Unpin,
+{
+	fn is_terminated(&self) -> bool {
+		matches!(self.0, Inner::Err(None))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures_util::StreamExt;
+	use std::io;
+
+	#[tokio::test]
+	async fn terminates() {
+		let (tx, rx) = futures_channel::mpsc::unbounded::<io::Result<()>>();
+		let mut stream = FusedErrorStream::from(Ok(rx));
+		tx.unbounded_send(Ok(())).unwrap();
+		drop(tx);
+		stream.next().await.unwrap().unwrap();
+		assert!(!stream.is_terminated());
+		assert!(stream.next().await.is_none());
+		assert!(stream.is_terminated());
+		assert!(stream.next().await.is_none());
+
+		let mut stream =
+			FusedErrorStream::<futures_channel::mpsc::UnboundedReceiver<io::Result<()>>>::from(
+				Err(io::ErrorKind::NotFound.into()),
+			);
+		assert!(stream.next().await.unwrap().is_err());
+		assert!(stream.is_terminated());
+	}
+}
@@ -1,4 +1,5 @@
 use futures_channel::oneshot;
+use futures_core::future::FusedFuture;
 use futures_util::FutureExt;
 use std::{
 	future::Future,
@@ -12,7 +13,10 @@ use std::{
 };
 
 use crate::{
-	error::Error,
+	error::{
+		with_context,
+		Error,
+	},
 	ffi,
 	inner::EventedService,
 };
@@ -28,6 +32,8 @@ struct Inner<S: EventedService, T> {
 	service: S,
 	_sender: Box<CallbackContext<T>>,
 	receiver: oneshot::Receiver<io::Result<T>>,
+	// attached to errors
+	operation: String,
 }
 
 #[must_use = "futures do nothing unless polled"]
@@ -46,43 +52,45 @@ impl<S: EventedService, T> ServiceFuture<S, T> {
 		let sender: &mut CallbackContext<T> = &mut *sender;
 		let sender = sender.take().expect("callback must be run only once");
 
-		let data = Error::from(error_code)
+		let data = Error::from(crate::compat::callback_error(error_code))
 			.map_err(io::Error::from)
 			.and_then(|()| f());
 
 		sender.send(data).expect("receiver must still be alive");
 	}
 
-	pub(crate) fn new<F>(f: F) -> io::Result<Self>
+	pub(crate) fn new<F>(operation: String, f: F) -> io::Result<Self>
 	where
 		F: FnOnce(*mut c_void) -> Result<S, Error>,
 	{
 		let (sender, receiver) = oneshot::channel::<io::Result<T>>();
 		let mut sender = Box::new(Some(sender));
 
-		let service = f(box_raw(&mut sender))?;
+		let service = f(box_raw(&mut sender)).map_err(|e| with_context(e.into(), &operation))?;
 
 		Ok(Self(Some(Inner {
 			service,
 			_sender: sender,
 			receiver,
+			operation,
 		})))
 	}
 
-	pub(crate) fn new_with<R, F>(service: S, f: F) -> io::Result<(Self, R)>
+	pub(crate) fn new_with<R, F>(operation: String, service: S, f: F) -> io::Result<(Self, R)>
 	where
 		F: FnOnce(*mut c_void) -> Result<R, Error>,
 	{
 		let (sender, receiver) = oneshot::channel::<io::Result<T>>();
 		let mut sender = Box::new(Some(sender));
 
-		let res = f(box_raw(&mut sender))?;
+		let res = f(box_raw(&mut sender)).map_err(|e| with_context(e.into(), &operation))?;
 
 		Ok((
 			Self(Some(Inner {
 				service,
 				_sender: sender,
 				receiver,
+				operation,
 			})),
 			res,
 		))
@@ -109,9 +117,22 @@ impl<S: EventedService, T> Future for ServiceFuture<S, T> {
 			// can only get ready once.
 			return Poll::Pending;
 		}
-		self.inner_mut().service.poll_service(cx)?;
-		let item = futures_core::ready!(self.inner_mut().receiver.poll_unpin(cx))
-			.expect("send can't die")?;
-		Poll::Ready(Ok((self.0.take().unwrap().service, item)))
+		if let Err(e) = self.inner_mut().service.poll_service(cx) {
+			let inner = self.0.take().unwrap();
+			return Poll::Ready(Err(with_context(e, &inner.operation)));
+		}
+		let item =
+			futures_core::ready!(self.inner_mut().receiver.poll_unpin(cx)).expect("send can't die");
+		let inner = self.0.take().unwrap();
+		Poll::Ready(match item {
+			Ok(item) => Ok((inner.service, item)),
+			Err(e) => Err(with_context(e, &inner.operation)),
+		})
+	}
+}
+
+impl<S: EventedService, T> FusedFuture for ServiceFuture<S, T> {
+	fn is_terminated(&self) -> bool {
+		self.0.is_none()
 	}
 }
@@ -5,13 +5,19 @@ use std::{
 	io,
 	ptr::null_mut,
 	sync::{
+		atomic::{
+			AtomicU32,
+			Ordering,
+		},
 		Arc,
 		Mutex,
+		Weak,
 	},
 	task::{
 		Context,
 		Poll,
 	},
+	time::Duration,
 };
 
 use crate::{
@@ -26,6 +32,12 @@ use crate::{
 		Notified,
 		Notify,
 	},
+	service::{
+		RecordDropPolicy,
+		RecordInfo,
+		RecordState,
+		RegistrationState,
+	},
 };
 
 // More typesafe than raw "ffi", but still not quite done
@@ -89,17 +101,26 @@ impl OwnedService {
 		let bg_fail_notify = Notify::new();
 		let bg_fail_notified = bg_fail_notify.notified();
 		let inner = Arc::new(Mutex::new(SharedInner {
-			handle: self.handle,
+			handle: Some(self.handle),
+			processing: Some(self.processing),
 			bg_error_buf: None,
 			bg_failed: false,
 			bg_fail_notify,
+			linger: Linger::default(),
+			tasks: Vec::new(),
+			records: Vec::new(),
+			state: tokio::sync::watch::Sender::new(RegistrationState::Pending),
 		}));
 		let bg_inner = inner.clone();
-		let mut processing = self.processing;
+		let bg_guard = BgTaskGuard(Arc::downgrade(&inner));
 
 		let bg_task = futures_util::future::poll_fn(move |cx| {
 			let mut inner = bg_inner.lock().unwrap();
-			let raw = inner.handle.as_raw();
+			let inner = &mut *inner;
+			let (raw, processing) = match (&inner.handle, &mut inner.processing) {
+				(Some(handle), Some(processing)) => (handle.as_raw(), processing),
+				_ => return Poll::Ready(()), // closed
+			};
 			let r = processing.process(cx, || {
 				Error::from(unsafe { ffi::DNSServiceProcessResult(raw) })?;
 				Ok(())
@@ -107,6 +128,7 @@ impl OwnedService {
 			match r {
 				Ok(()) => Poll::Pending, // run "forever"
 				Err(e) => {
+					inner.state.send_replace(RegistrationState::from_error(&e));
 					inner.bg_error_buf = Some(e);
 					inner.bg_failed = true;
 					inner.bg_fail_notify.notify_waiters();
@@ -114,28 +136,24 @@ impl OwnedService {
 				},
 			}
 		});
+		let bg_task = tokio::spawn(async move {
+			let _guard = bg_guard;
+			bg_task.await
+		});
+		inner.lock().unwrap().tasks.push(bg_task.abort_handle());
+		let bg_task_handle = Arc::new(AbortHandle {
+			task: Some(bg_task),
+			inner: inner.clone(),
+		});
 		SharedService {
 			inner,
-			_bg_task_handle: Arc::new(AbortHandle(tokio::spawn(bg_task))),
+			bg_task_handle,
 			bg_fail_notified,
 		}
 	}
 
 	// -----
 
-	pub(crate) fn enumerate_domains(
-		flags: ffi::DNSServiceFlags,
-		interface_index: u32,
-		callback: ffi::DNSServiceDomainEnumReply,
-		context: *mut c_void,
-	) -> Result<Self, Error> {
-		let mut sd_ref: ffi::DNSServiceRef = null_mut();
-		Error::from(unsafe {
-			ffi::DNSServiceEnumerateDomains(&mut sd_ref, flags, interface_index, callback, context)
-		})?;
-		Ok(Self::new(sd_ref)?)
-	}
-
 	pub(crate) fn register(
 		flags: ffi::DNSServiceFlags,
 		interface_index: u32,
@@ -148,9 +166,7 @@ impl OwnedService {
 		callback: ffi::DNSServiceRegisterReply,
 		context: *mut c_void,
 	) -> Result<Self, Error> {
-		let txt_len = txt.len();
-		assert!(txt_len < (1 << 16));
-		let txt_len = txt_len as u16;
+		let txt_len = data_len(txt)?;
 		let txt_record = txt.as_ptr();
 
 		let mut sd_ref: ffi::DNSServiceRef = null_mut();
@@ -173,7 +189,159 @@ impl OwnedService {
 		Ok(Self::new(sd_ref)?)
 	}
 
+	// keeps processing its own connection (can't be shared)
+	#[cfg(any(target_os = "macos", target_os = "ios"))]
+	pub(crate) fn sleep_keepalive(
+		flags: ffi::DNSServiceFlags,
+		fd: std::os::raw::c_int,
+		timeout: u32,
+		callback: ffi::DNSServiceSleepKeepaliveReply,
+		context: *mut c_void,
+	) -> Result<Self, Error> {
+		let mut sd_ref: ffi::DNSServiceRef = null_mut();
+		Error::from(unsafe {
+			ffi::DNSServiceSleepKeepalive(&mut sd_ref, flags, fd, timeout, callback, context)
+		})?;
+		Ok(Self::new(sd_ref)?)
+	}
+}
+
+impl EventedService for OwnedService {
+	fn poll_service(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+		let raw = self.handle.as_raw();
+		self.processing.process(cx, || {
+			Error::from(unsafe { ffi::DNSServiceProcessResult(raw) })?;
+			Ok(())
+		})
+	}
+}
+
+// subordinate operation on a shared connection; the shared connection
+// processes the results in its background task
+struct SubordinateRef(ffi::DNSServiceRef);
+
+unsafe impl Send for SubordinateRef {}
+unsafe impl Sync for SubordinateRef {}
+
+pub(crate) struct SubordinateService {
+	parent: SharedService,
+	raw: SubordinateRef,
+}
+
+impl Drop for SubordinateService {
+	fn drop(&mut self) {
+		// must not run concurrently with processing on the parent
+		let inner = self.parent.inner.lock().unwrap();
+		// closing the parent already released the subordinate reference
+		if inner.handle.is_some() {
+			unsafe {
+				ffi::DNSServiceRefDeallocate(self.raw.0);
+			}
+		}
+	}
+}
+
+impl EventedService for SubordinateService {
+	fn poll_service(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+		self.parent.poll_service(cx)
+	}
+}
+
+// lazily created connection shared by all operations (if enabled), alive
+// as long as some operation uses it; replaced after it failed (also when
+// the runtime processing it shut down)
+static SHARED_CONNECTION: Mutex<Option<WeakSharedService>> = Mutex::new(None);
+
+fn shared_connection() -> Result<SharedService, Error> {
+	let mut shared = SHARED_CONNECTION.lock().unwrap();
+	if let Some(service) = shared.as_ref().and_then(WeakSharedService::upgrade) {
+		if !service.has_failed() {
+			return Ok(service);
+		}
+	}
+	let service = SharedService::create_connection()?;
+	*shared = Some(service.downgrade());
+	Ok(service)
+}
+
+/// Service for operations delivering a stream of results; either owns
+/// its connection or uses the shared connection
+pub(crate) enum StreamService {
+	Owned(OwnedService),
+	Subordinate(SubordinateService),
+}
+
+impl EventedService for StreamService {
+	fn poll_service(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+		match self {
+			Self::Owned(service) => service.poll_service(cx),
+			Self::Subordinate(service) => service.poll_service(cx),
+		}
+	}
+}
+
+impl StreamService {
+	// `f` gets a reference to fill and additional flags to pass
+	fn start<F>(f: F) -> Result<Self, Error>
+	where
+		F: FnOnce(&mut ffi::DNSServiceRef, ffi::DNSServiceFlags) -> ffi::DNSServiceErrorType,
+	{
+		Self::start_on(None, f)
+	}
+
+	// run as subordinate operation on `parent` if given (must be created
+	// through "create_connection"), otherwise like `start`
+	fn start_on<F>(parent: Option<&SharedService>, f: F) -> Result<Self, Error>
+	where
+		F: FnOnce(&mut ffi::DNSServiceRef, ffi::DNSServiceFlags) -> ffi::DNSServiceErrorType,
+	{
+		let parent = match parent {
+			Some(parent) => Some(parent.clone()),
+			None if crate::config::shared_connection()
+				&& crate::compat::allow_shared_connection() =>
+			{
+				Some(shared_connection()?)
+			},
+			None => None,
+		};
+		if let Some(parent) = parent {
+			let sd_ref = {
+				let inner = parent.inner.lock().unwrap();
+				// passing the main reference makes it a subordinate operation
+				let mut sd_ref = inner.raw()?;
+				Error::from(f(&mut sd_ref, ffi::FLAGS_SHARE_CONNECTION))?;
+				sd_ref
+			};
+			Ok(Self::Subordinate(SubordinateService {
+				parent,
+				raw: SubordinateRef(sd_ref),
+			}))
+		} else {
+			let mut sd_ref: ffi::DNSServiceRef = null_mut();
+			Error::from(f(&mut sd_ref, 0))?;
+			Ok(Self::Owned(OwnedService::new(sd_ref)?))
+		}
+	}
+
+	pub(crate) fn enumerate_domains(
+		flags: ffi::DNSServiceFlags,
+		interface_index: u32,
+		callback: ffi::DNSServiceDomainEnumReply,
+		context: *mut c_void,
+	) -> Result<Self, Error> {
+		Self::start(|sd_ref, share_flags| unsafe {
+			ffi::DNSServiceEnumerateDomains(
+				sd_ref,
+				flags | share_flags,
+				interface_index,
+				callback,
+				context,
+			)
+		})
+	}
+
 	pub(crate) fn browse(
+		parent: Option<&SharedService>,
 		flags: ffi::DNSServiceFlags,
 		interface_index: u32,
 		reg_type: &cstr::CStr<'_>,
@@ -181,22 +349,21 @@ impl OwnedService {
 		callback: ffi::DNSServiceBrowseReply,
 		context: *mut c_void,
 	) -> Result<Self, Error> {
-		let mut sd_ref: ffi::DNSServiceRef = null_mut();
-		Error::from(unsafe {
+		Self::start_on(parent, |sd_ref, share_flags| unsafe {
 			ffi::DNSServiceBrowse(
-				&mut sd_ref,
-				flags,
+				sd_ref,
+				flags | share_flags,
 				interface_index,
 				reg_type.as_ptr(),
 				domain.as_ptr(),
 				callback,
 				context,
 			)
-		})?;
-		Ok(Self::new(sd_ref)?)
+		})
 	}
 
 	pub(crate) fn resolve(
+		parent: Option<&SharedService>,
 		flags: ffi::DNSServiceFlags,
 		interface_index: u32,
 		name: &cstr::CStr<'_>,
@@ -205,11 +372,10 @@ impl OwnedService {
 		callback: ffi::DNSServiceResolveReply,
 		context: *mut c_void,
 	) -> Result<Self, Error> {
-		let mut sd_ref: ffi::DNSServiceRef = null_mut();
-		Error::from(unsafe {
+		Self::start_on(parent, |sd_ref, share_flags| unsafe {
 			ffi::DNSServiceResolve(
-				&mut sd_ref,
-				flags,
+				sd_ref,
+				flags | share_flags,
 				interface_index,
 				name.as_ptr(),
 				reg_type.as_ptr(),
@@ -217,11 +383,11 @@ impl OwnedService {
 				callback,
 				context,
 			)
-		})?;
-		Ok(Self::new(sd_ref)?)
+		})
 	}
 
 	pub(crate) fn query_record(
+		parent: Option<&SharedService>,
 		flags: ffi::DNSServiceFlags,
 		interface_index: u32,
 		fullname: &cstr::CStr<'_>,
@@ -230,11 +396,10 @@ impl OwnedService {
 		callback: ffi::DNSServiceQueryRecordReply,
 		context: *mut c_void,
 	) -> Result<Self, Error> {
-		let mut sd_ref: ffi::DNSServiceRef = null_mut();
-		Error::from(unsafe {
+		Self::start_on(parent, |sd_ref, share_flags| unsafe {
 			ffi::DNSServiceQueryRecord(
-				&mut sd_ref,
-				flags,
+				sd_ref,
+				flags | share_flags,
 				interface_index,
 				fullname.as_ptr(),
 				rr_type.0,
@@ -242,48 +407,204 @@ impl OwnedService {
 				callback,
 				context,
 			)
-		})?;
-		Ok(Self::new(sd_ref)?)
+		})
 	}
-}
 
-impl EventedService for OwnedService {
-	fn poll_service(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
-		let raw = self.handle.as_raw();
-		self.processing.process(cx, || {
-			Error::from(unsafe { ffi::DNSServiceProcessResult(raw) })?;
-			Ok(())
+	#[cfg(not(dnssd_avahi))]
+	pub(crate) fn get_addr_info(
+		flags: ffi::DNSServiceFlags,
+		interface_index: u32,
+		protocol: ffi::DNSServiceProtocol,
+		hostname: &cstr::CStr<'_>,
+		callback: ffi::DNSServiceGetAddrInfoReply,
+		context: *mut c_void,
+	) -> Result<Self, Error> {
+		Self::start(|sd_ref, share_flags| unsafe {
+			ffi::DNSServiceGetAddrInfo(
+				sd_ref,
+				flags | share_flags,
+				interface_index,
+				protocol,
+				hostname.as_ptr(),
+				callback,
+				context,
+			)
 		})
 	}
 }
 
-struct AbortHandle(tokio::task::JoinHandle<()>);
+// what to do once all users of a `SharedService` are gone
+#[derive(Default)]
+struct Linger {
+	// keep processing (and the service registered) for a while
+	duration: Option<Duration>,
+	// update default TXT record first
+	final_txt: Option<Vec<u8>>,
+}
+
+struct AbortHandle {
+	task: Option<tokio::task::JoinHandle<()>>,
+	inner: Arc<Mutex<SharedInner>>,
+}
 
 impl Drop for AbortHandle {
 	fn drop(&mut self) {
-		self.0.abort();
+		let task = match self.task.take() {
+			Some(task) => task,
+			None => return,
+		};
+		let mut inner = self.inner.lock().unwrap();
+		let linger = std::mem::take(&mut inner.linger);
+		let handle = match &inner.handle {
+			Some(handle) => handle,
+			None => {
+				// closed
+				task.abort();
+				return;
+			},
+		};
+		if let Some(txt) = &linger.final_txt {
+			// no way to report errors anymore
+			let _ = unsafe {
+				ffi::DNSServiceUpdateRecord(
+					handle.as_raw(),
+					null_mut(),
+					0,
+					txt.len() as u16,
+					txt.as_ptr(),
+					0,
+				)
+			};
+		}
+		match (linger.duration, tokio::runtime::Handle::try_current()) {
+			(Some(duration), Ok(runtime)) => {
+				// `close` stops lingering early
+				inner.spawn_task(&runtime, async move {
+					tokio::time::sleep(duration).await;
+					task.abort();
+				});
+			},
+			_ => task.abort(),
+		}
+	}
+}
+
+// fails the service when the background task is dropped before it
+// finished (e.g. because the runtime it was spawned on shut down), so
+// nobody waits for results that never get processed
+struct BgTaskGuard(Weak<Mutex<SharedInner>>);
+
+impl Drop for BgTaskGuard {
+	fn drop(&mut self) {
+		if let Some(inner) = self.0.upgrade() {
+			inner.lock().unwrap().fail(io::Error::new(
+				io::ErrorKind::NotConnected,
+				"runtime processing the service shut down",
+			));
+		}
 	}
 }
 
 struct SharedInner {
-	// protect ffi calls
-	handle: ServiceHandle,
+	// protect ffi calls; `None` after `close`
+	handle: Option<ServiceHandle>,
+	// watching the socket of `handle`
+	processing: Option<crate::evented::ReadProcessor>,
 	// forward error from background task
 	bg_error_buf: Option<io::Error>,
 	// but we can extract error only once, so remember it failed
 	bg_failed: bool,
 	//
 	bg_fail_notify: Notify,
+	linger: Linger,
+	// background processing, lingering and delayed record removal;
+	// aborted on `close`
+	tasks: Vec<tokio::task::AbortHandle>,
+	// records registered through a connection (by `DNSRecordRef` address)
+	records: Vec<(usize, RecordInfo)>,
+	// only updated for registrations
+	state: tokio::sync::watch::Sender<RegistrationState>,
+}
+
+impl Drop for SharedInner {
+	fn drop(&mut self) {
+		// errors stay visible
+		self.state.send_if_modified(|state| match state {
+			RegistrationState::Pending | RegistrationState::Active { .. } => {
+				*state = RegistrationState::Removed;
+				true
+			},
+			_ => false,
+		});
+	}
 }
 
 #[derive(Clone)]
 pub(crate) struct SharedService {
 	inner: Arc<Mutex<SharedInner>>,
 	// make sure we kill the background task once all users are gone
-	_bg_task_handle: Arc<AbortHandle>,
+	bg_task_handle: Arc<AbortHandle>,
 	bg_fail_notified: Notified,
 }
 
+fn closed_error() -> io::Error {
+	io::Error::new(io::ErrorKind::NotConnected, "service closed")
+}
+
+impl SharedInner {
+	fn raw(&self) -> Result<ffi::DNSServiceRef, Error> {
+		match &self.handle {
+			Some(handle) => Ok(handle.as_raw()),
+			None => Err(closed_error().into()),
+		}
+	}
+
+	fn record_info(&mut self, raw: &DNSRecordRef) -> Option<&mut RecordInfo> {
+		let key = raw.0 as usize;
+		self.records
+			.iter_mut()
+			.find(|(k, _)| *k == key)
+			.map(|(_, info)| info)
+	}
+
+	fn spawn_task<F>(&mut self, runtime: &tokio::runtime::Handle, task: F)
+	where
+		F: std::future::Future<Output = ()> + Send + 'static,
+	{
+		if self.handle.is_none() {
+			return; // closed: nothing left to do
+		}
+		self.tasks.retain(|task| !task.is_finished());
+		self.tasks.push(runtime.spawn(task).abort_handle());
+	}
+
+	fn close(&mut self) {
+		// stop watching the socket before it gets closed
+		self.processing = None;
+		self.handle = None;
+		self.records.clear();
+		self.state.send_replace(RegistrationState::Removed);
+		self.fail(closed_error());
+		for task in self.tasks.drain(..) {
+			task.abort();
+		}
+	}
+
+	// report `e` to the users (unless the service failed already)
+	fn fail(&mut self, e: io::Error) {
+		if !self.bg_failed {
+			self.bg_error_buf = Some(e);
+			self.bg_failed = true;
+			self.bg_fail_notify.notify_waiters();
+		}
+	}
+
+	fn forget_record(&mut self, raw: &DNSRecordRef) {
+		let key = raw.0 as usize;
+		self.records.retain(|(k, _)| *k != key);
+	}
+}
+
 impl EventedService for SharedService {
 	fn poll_service(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
 		// service is run in background task; just make sure there wasn't
@@ -301,12 +622,100 @@ impl EventedService for SharedService {
 	}
 }
 
+pub(crate) struct WeakSharedService {
+	inner: Weak<Mutex<SharedInner>>,
+	bg_task_handle: Weak<AbortHandle>,
+}
+
+impl WeakSharedService {
+	pub(crate) fn upgrade(&self) -> Option<SharedService> {
+		let inner = self.inner.upgrade()?;
+		let bg_task_handle = self.bg_task_handle.upgrade()?;
+		let bg_fail_notified = inner.lock().unwrap().bg_fail_notify.notified();
+		Some(SharedService {
+			inner,
+			bg_task_handle,
+			bg_fail_notified,
+		})
+	}
+
+	// like `SharedService::close`; also works while the service lingers
+	// (or removes records later) after all users are gone
+	pub(crate) fn close(&self) {
+		if let Some(inner) = self.inner.upgrade() {
+			inner.lock().unwrap().close();
+		}
+	}
+
+	// whether the service (or some task of it) is still around
+	pub(crate) fn is_alive(&self) -> bool {
+		self.inner.strong_count() > 0
+	}
+}
+
 impl SharedService {
+	pub(crate) fn downgrade(&self) -> WeakSharedService {
+		WeakSharedService {
+			inner: Arc::downgrade(&self.inner),
+			bg_task_handle: Arc::downgrade(&self.bg_task_handle),
+		}
+	}
+
+	// whether the service stopped processing results (after an error,
+	// `close` or when its runtime shut down)
+	pub(crate) fn has_failed(&self) -> bool {
+		self.inner.lock().unwrap().bg_failed
+	}
+
+	// stop processing and release the service (and all records and
+	// subordinate operations); all further operations fail.
+	pub(crate) fn close(&self) {
+		self.inner.lock().unwrap().close();
+	}
+
+	pub(crate) fn records(&self) -> Vec<RecordInfo> {
+		let inner = self.inner.lock().unwrap();
+		if inner.handle.is_none() || inner.bg_failed {
+			return Vec::new();
+		}
+		inner.records.iter().map(|(_, info)| info.clone()).collect()
+	}
+
+	pub(crate) fn record_count(&self) -> usize {
+		let inner = self.inner.lock().unwrap();
+		if inner.handle.is_none() || inner.bg_failed {
+			return 0;
+		}
+		inner.records.len()
+	}
+
+	pub(crate) fn state(&self) -> tokio::sync::watch::Receiver<RegistrationState> {
+		self.inner.lock().unwrap().state.subscribe()
+	}
+
+	pub(crate) fn set_state(&self, state: RegistrationState) {
+		self.inner.lock().unwrap().state.send_replace(state);
+	}
+
+	pub(crate) fn set_linger(&self, duration: Option<Duration>) {
+		self.inner.lock().unwrap().linger.duration = duration;
+	}
+
+	pub(crate) fn set_final_txt(&self, rdata: Option<&[u8]>) -> Result<(), Error> {
+		if let Some(rdata) = rdata {
+			data_len(rdata)?;
+		}
+		self.inner.lock().unwrap().linger.final_txt = rdata.map(<[u8]>::to_vec);
+		Ok(())
+	}
+
 	pub(crate) fn get_default_txt_record(self) -> DNSRecord {
 		DNSRecord {
 			service: self,
 			raw: DNSRecordRef(null_mut()),
 			rr_type: Type::TXT,
+			ttl: AtomicU32::new(0),
+			drop_policy: RecordDropPolicy::RemoveOnDrop,
 		}
 	}
 
@@ -320,9 +729,7 @@ impl SharedService {
 		rdata: &[u8],
 		ttl: u32,
 	) -> Result<DNSRecord, Error> {
-		let rd_len = rdata.len();
-		assert!(rd_len < (1 << 16));
-		let rd_len = rd_len as u16;
+		let rd_len = data_len(rdata)?;
 		let rdata = rdata.as_ptr();
 
 		let inner = self.inner.lock().unwrap();
@@ -330,7 +737,7 @@ impl SharedService {
 		let mut record_ref: ffi::DNSRecordRef = null_mut();
 		Error::from(unsafe {
 			ffi::DNSServiceAddRecord(
-				inner.handle.as_raw(),
+				inner.raw()?,
 				&mut record_ref,
 				flags,
 				rr_type.0,
@@ -346,6 +753,8 @@ impl SharedService {
 			service: self,
 			raw: DNSRecordRef(record_ref),
 			rr_type,
+			ttl: AtomicU32::new(ttl),
+			drop_policy: RecordDropPolicy::RemoveOnDrop,
 		})
 	}
 
@@ -355,6 +764,15 @@ impl SharedService {
 		Ok(OwnedService::new(sd_ref)?.share())
 	}
 
+	#[cfg(any(target_os = "macos", target_os = "ios"))]
+	pub(crate) fn create_delegate_connection(pid: i32, uuid: &[u8; 16]) -> Result<Self, Error> {
+		let mut sd_ref: ffi::DNSServiceRef = null_mut();
+		Error::from(unsafe {
+			ffi::DNSServiceCreateDelegateConnection(&mut sd_ref, pid, uuid.as_ptr())
+		})?;
+		Ok(OwnedService::new(sd_ref)?.share())
+	}
+
 	// only valid when `service` was created through "create_connection"
 	pub(crate) fn register_record(
 		self,
@@ -368,17 +786,15 @@ impl SharedService {
 		callback: ffi::DNSServiceRegisterRecordReply,
 		context: *mut c_void,
 	) -> Result<DNSRecord, Error> {
-		let rd_len = rdata.len();
-		assert!(rd_len < (1 << 16));
-		let rd_len = rd_len as u16;
+		let rd_len = data_len(rdata)?;
 		let rdata = rdata.as_ptr();
 
-		let inner = self.inner.lock().unwrap();
+		let mut inner = self.inner.lock().unwrap();
 
 		let mut record_ref: ffi::DNSRecordRef = null_mut();
 		Error::from(unsafe {
 			ffi::DNSServiceRegisterRecord(
-				inner.handle.as_raw(),
+				inner.raw()?,
 				&mut record_ref,
 				flags,
 				interface_index,
@@ -393,12 +809,25 @@ impl SharedService {
 			)
 		})?;
 
+		inner.records.push((
+			record_ref as usize,
+			RecordInfo {
+				fullname: fullname.to_string_lossy().into_owned(),
+				rr_type,
+				rr_class,
+				ttl,
+				rdata_len: usize::from(rd_len),
+				state: RecordState::Pending,
+			},
+		));
 		drop(inner);
 
 		Ok(DNSRecord {
 			service: self,
 			raw: DNSRecordRef(record_ref),
 			rr_type,
+			ttl: AtomicU32::new(ttl),
+			drop_policy: RecordDropPolicy::RemoveOnDrop,
 		})
 	}
 }
@@ -415,19 +844,69 @@ pub(crate) struct DNSRecord {
 	service: SharedService,
 	raw: DNSRecordRef,
 	rr_type: Type,
+	// last TTL passed to the daemon
+	ttl: AtomicU32,
+	drop_policy: RecordDropPolicy,
+}
+
+// the daemon uses 120 seconds for host records (and 4500 for others)
+const DEFAULT_RECORD_TTL: u32 = 120;
+
+// length of TXT data / rdata for ffi calls (limited to 16 bits)
+fn data_len(data: &[u8]) -> Result<u16, Error> {
+	u16::try_from(data.len()).map_err(|_| Error::KnownError(ffi::DNSServiceError::BadParam))
+}
+
+fn remove_record(service: &SharedService, raw: &DNSRecordRef) -> Result<(), Error> {
+	let mut inner = service.inner.lock().unwrap();
+	inner.forget_record(raw);
+	// closing the service already released the record
+	if let Some(handle) = &inner.handle {
+		Error::from(unsafe {
+			ffi::DNSServiceRemoveRecord(
+				handle.as_raw(),
+				raw.0,
+				0, // no flags
+			)
+		})?;
+	}
+	Ok(())
 }
 
 impl Drop for DNSRecord {
 	fn drop(&mut self) {
-		if !self.raw.0.is_null() {
-			let inner = self.service.inner.lock().unwrap();
-			unsafe {
-				ffi::DNSServiceRemoveRecord(
-					inner.handle.as_raw(),
-					self.raw.0,
-					0, // no flags
-				);
-			}
+		if self.raw.0.is_null() {
+			return;
+		}
+		match self.drop_policy {
+			RecordDropPolicy::RemoveOnDrop => {
+				let _ = remove_record(&self.service, &self.raw);
+			},
+			RecordDropPolicy::KeepUntilConnectionDrop => self.set_state(RecordState::Detached),
+			RecordDropPolicy::KeepForTtl => {
+				self.set_state(RecordState::Detached);
+				let ttl = match self.ttl.load(Ordering::Relaxed) {
+					0 => DEFAULT_RECORD_TTL,
+					ttl => ttl,
+				};
+				match tokio::runtime::Handle::try_current() {
+					Ok(runtime) => {
+						// don't keep the service alive for the record
+						let service = self.service.downgrade();
+						let raw = DNSRecordRef(self.raw.0);
+						let mut inner = self.service.inner.lock().unwrap();
+						inner.spawn_task(&runtime, async move {
+							tokio::time::sleep(Duration::from_secs(ttl.into())).await;
+							if let Some(service) = service.upgrade() {
+								let _ = remove_record(&service, &raw);
+							}
+						});
+					},
+					Err(_) => {
+						let _ = remove_record(&self.service, &self.raw);
+					},
+				}
+			},
 		}
 	}
 }
@@ -439,23 +918,20 @@ impl DNSRecord {
 		rdata: &[u8],
 		ttl: u32,
 	) -> Result<(), Error> {
-		let rd_len = rdata.len();
-		assert!(rd_len < (1 << 16));
-		let rd_len = rd_len as u16;
+		let rd_len = data_len(rdata)?;
 		let rdata = rdata.as_ptr();
 
-		let inner = self.service.inner.lock().unwrap();
+		let mut inner = self.service.inner.lock().unwrap();
 
 		Error::from(unsafe {
-			ffi::DNSServiceUpdateRecord(
-				inner.handle.as_raw(),
-				self.raw.0,
-				flags,
-				rd_len,
-				rdata,
-				ttl,
-			)
-		})
+			ffi::DNSServiceUpdateRecord(inner.raw()?, self.raw.0, flags, rd_len, rdata, ttl)
+		})?;
+		self.ttl.store(ttl, Ordering::Relaxed);
+		if let Some(info) = inner.record_info(&self.raw) {
+			info.ttl = ttl;
+			info.rdata_len = usize::from(rd_len);
+		}
+		Ok(())
 	}
 
 	pub(crate) fn rr_type(&self) -> Type {
@@ -464,8 +940,33 @@ impl DNSRecord {
 
 	// keep "forever" (until service is dropped)
 	pub(crate) fn keep(mut self) {
+		self.set_state(RecordState::Detached);
 		self.raw.0 = null_mut();
 	}
+
+	pub(crate) fn set_state(&self, state: RecordState) {
+		if self.raw.0.is_null() {
+			return;
+		}
+		if let Some(info) = self.service.inner.lock().unwrap().record_info(&self.raw) {
+			info.state = state;
+		}
+	}
+
+	// remove now (regardless of drop policy); nothing to do for the
+	// default TXT record
+	pub(crate) fn remove(mut self) -> Result<(), Error> {
+		if self.raw.0.is_null() {
+			return Ok(());
+		}
+		let result = remove_record(&self.service, &self.raw);
+		self.raw.0 = null_mut();
+		result
+	}
+
+	pub(crate) fn set_drop_policy(&mut self, policy: RecordDropPolicy) {
+		self.drop_policy = policy;
+	}
 }
 
 pub fn reconfirm_record(
@@ -475,13 +976,11 @@ pub fn reconfirm_record(
 	rr_type: Type,
 	rr_class: Class,
 	rdata: &[u8],
-) {
-	let rd_len = rdata.len();
-	assert!(rd_len < (1 << 16));
-	let rd_len = rd_len as u16;
+) -> Result<(), Error> {
+	let rd_len = data_len(rdata)?;
 	let rdata = rdata.as_ptr();
 
-	unsafe {
+	Error::from(unsafe {
 		ffi::DNSServiceReconfirmRecord(
 			flags,
 			interface_index,
@@ -490,6 +989,183 @@ pub fn reconfirm_record(
 			rr_class.0,
 			rd_len,
 			rdata,
+		)
+	})
+}
+
+// connect to the daemon (and disconnect right away)
+#[cfg(not(dnssd_avahi))]
+pub(crate) fn check_daemon() -> Result<(), Error> {
+	let mut sd_ref: ffi::DNSServiceRef = null_mut();
+	Error::from(unsafe { ffi::DNSServiceCreateConnection(&mut sd_ref) })?;
+	unsafe {
+		ffi::DNSServiceRefDeallocate(sd_ref);
+	}
+	Ok(())
+}
+
+// avahi doesn't implement `DNSServiceCreateConnection`; starting a browse
+// connects to the daemon too (the callback never runs as the result is
+// never processed)
+#[cfg(dnssd_avahi)]
+pub(crate) fn check_daemon() -> Result<(), Error> {
+	unsafe extern "C" fn ignore_browse(
+		_sd_ref: ffi::DNSServiceRef,
+		_flags: ffi::DNSServiceFlags,
+		_interface_index: u32,
+		_error_code: ffi::DNSServiceErrorType,
+		_service_name: *const std::os::raw::c_char,
+		_reg_type: *const std::os::raw::c_char,
+		_reply_domain: *const std::os::raw::c_char,
+		_context: *mut c_void,
+	) {
+	}
+
+	let mut sd_ref: ffi::DNSServiceRef = null_mut();
+	let error = unsafe {
+		ffi::DNSServiceBrowse(
+			&mut sd_ref,
+			0,
+			0,
+			c"_services._dns-sd._udp".as_ptr(),
+			std::ptr::null(),
+			Some(ignore_browse),
+			null_mut(),
+		)
+	};
+	Error::from(crate::compat::callback_error(error))?;
+	unsafe {
+		ffi::DNSServiceRefDeallocate(sd_ref);
+	}
+	Ok(())
+}
+
+// `property` must be NUL terminated
+#[cfg(not(dnssd_avahi))]
+pub(crate) fn get_property_u32(property: &[u8]) -> Result<u32, Error> {
+	let mut value: u32 = 0;
+	let mut size = size_of::<u32>() as u32;
+	Error::from(unsafe {
+		ffi::DNSServiceGetProperty(
+			property.as_ptr().cast(),
+			&mut value as *mut u32 as *mut c_void,
+			&mut size,
+		)
+	})?;
+	Ok(value)
+}
+
+#[cfg(dnssd_avahi)]
+pub(crate) fn get_property_u32(_property: &[u8]) -> Result<u32, Error> {
+	Err(Error::KnownError(ffi::DNSServiceError::Unsupported))
+}
+
+#[cfg(windows)]
+pub(crate) fn initialize(flags: ffi::DNSServiceInitializeFlags) -> Result<(), Error> {
+	Error::from(unsafe { ffi::DNSServiceInitialize(flags, 0) })
+}
+
+#[cfg(windows)]
+fn version() -> Result<ffi::DnsPropertyVersion, Error> {
+	let mut version = ffi::DnsPropertyVersion {
+		code: ffi::PROPERTY_CODE_VERSION,
+		client_current_version: 0,
+		client_oldest_server_version: 0,
+		server_current_version: 0,
+		server_oldest_client_version: 0,
+	};
+	let data: *mut ffi::DnsPropertyVersion = &mut version;
+	Error::from(unsafe { ffi::DNSServiceCopyProperty(ffi::PROPERTY_CODE_VERSION, data.cast()) })?;
+	unsafe {
+		ffi::DNSServiceReleaseProperty(data.cast());
+	}
+	Ok(version)
+}
+
+// check client library and daemon are compatible
+#[cfg(windows)]
+pub(crate) fn check_version() -> Result<(), Error> {
+	let version = version()?;
+	if version.server_current_version < version.client_oldest_server_version
+		|| version.client_current_version < version.server_oldest_client_version
+	{
+		return Err(Error::Incompatible {
+			client_version: version.client_current_version,
+			server_version: version.server_current_version,
+		});
+	}
+	Error::from(unsafe { ffi::DNSServiceCheckVersion() })
+}
+
+#[cfg(all(test, unix))]
+impl SharedService {
+	// service without daemon behind it; watches a socket that never gets
+	// readable
+	pub(crate) fn unconnected() -> Self {
+		use std::os::unix::io::IntoRawFd;
+
+		let (socket, peer) = std::os::unix::net::UnixStream::pair().unwrap();
+		// leaked: must stay open as long as the service might watch it
+		std::mem::forget(peer);
+		OwnedService {
+			handle: ServiceHandle::new(null_mut()),
+			processing: crate::evented::ReadProcessor::new(socket.into_raw_fd()).unwrap(),
+		}
+		.share()
+	}
+
+	// record the daemon never saw; must not be removed before `close`
+	pub(crate) fn unregistered_record(&self) -> DNSRecord {
+		DNSRecord {
+			service: self.clone(),
+			raw: DNSRecordRef(std::ptr::NonNull::dangling().as_ptr()),
+			rr_type: Type::TXT,
+			ttl: AtomicU32::new(0),
+			drop_policy: RecordDropPolicy::RemoveOnDrop,
+		}
+	}
+}
+
+#[cfg(all(test, unix))]
+impl WeakSharedService {
+	pub(crate) fn running_tasks(&self) -> usize {
+		self.inner.upgrade().map_or(0, |inner| {
+			let inner = inner.lock().unwrap();
+			inner
+				.tasks
+				.iter()
+				.filter(|task| !task.is_finished())
+				.count()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::data_len;
+
+	#[cfg(unix)]
+	#[test]
+	fn runtime_shutdown_fails_service() {
+		let runtime = tokio::runtime::Builder::new_current_thread()
+			.enable_all()
+			.build()
+			.unwrap();
+		let service = runtime.block_on(async { super::SharedService::unconnected() });
+		assert!(!service.inner.lock().unwrap().bg_failed);
+		drop(runtime);
+		let inner = service.inner.lock().unwrap();
+		assert!(inner.bg_failed);
+		assert_eq!(
+			inner.bg_error_buf.as_ref().map(std::io::Error::kind),
+			Some(std::io::ErrorKind::NotConnected)
 		);
 	}
+
+	#[test]
+	fn data_len_limit() {
+		assert_eq!(data_len(&[]).unwrap(), 0);
+		assert_eq!(data_len(&[0; 0xffff]).unwrap(), 0xffff);
+		assert!(data_len(&[0; 0x10000]).is_err());
+	}
 }
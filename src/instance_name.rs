@@ -0,0 +1,268 @@
+use async_dnssd_types::name::{
+	escape_label,
+	split_labels,
+	unescape_label,
+};
+use std::{
+	error,
+	fmt,
+	io,
+};
+
+/// Name of a service instance (the first label of its full name)
+///
+/// Stores the unescaped name as shown to users; instance names can
+/// contain any UTF-8 text including dots and backslashes, which need to
+/// be escaped in full names (see [`escaped`](#method.escaped)).
+///
+/// Names in results (e.g. [`BrowseResult::service_name`]) and data
+/// structs (e.g. [`RegisterData::name`]) are unescaped already.
+///
+/// [`BrowseResult::service_name`]: struct.BrowseResult.html#structfield.service_name
+/// [`RegisterData::name`]: struct.RegisterData.html#structfield.name
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ServiceInstanceName(String);
+
+impl ServiceInstanceName {
+	/// Maximum length of an instance name in bytes (UTF-8 encoded)
+	pub const MAX_LEN: usize = 63;
+
+	/// Unescaped name
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+
+	/// Whether the name fits into a single label (1 to 63 bytes)
+	pub fn is_valid(&self) -> bool {
+		!self.0.is_empty() && self.0.len() <= Self::MAX_LEN
+	}
+
+	/// Check the name can be registered
+	///
+	/// The length limit applies to the UTF-8 encoded (unescaped) name;
+	/// escaping only happens in presentation format and doesn't count.
+	pub fn check(&self) -> Result<(), InstanceNameError> {
+		if self.0.is_empty() {
+			return Err(InstanceNameError::Empty);
+		}
+		if self.0.len() > Self::MAX_LEN {
+			return Err(InstanceNameError::TooLong { len: self.0.len() });
+		}
+		if let Some(position) = self.0.find(char::is_control) {
+			return Err(InstanceNameError::ControlCharacter { position });
+		}
+		Ok(())
+	}
+
+	/// Unicode NFC normalized name
+	///
+	/// Different normalizations of the same name look identical to
+	/// users but are different names for the daemon.
+	#[cfg(feature = "nfc")]
+	pub fn nfc(&self) -> Self {
+		use unicode_normalization::UnicodeNormalization;
+
+		Self(self.0.nfc().collect())
+	}
+
+	// name as registered: NFC normalized (if enabled) and checked; an
+	// empty name selects the default name (like `None`)
+	pub(crate) fn for_register(name: Option<&str>) -> Result<Option<Self>, InstanceNameError> {
+		let name = match name {
+			None | Some("") => return Ok(None),
+			Some(name) => Self::from(name),
+		};
+		#[cfg(feature = "nfc")]
+		let name = name.nfc();
+		name.check()?;
+		Ok(Some(name))
+	}
+
+	/// Escape name for use as label in a full name
+	///
+	/// Dots and backslashes are escaped with a backslash, control
+	/// characters (and space) as `\DDD`; same as
+	/// `DNSServiceConstructFullName`.
+	pub fn escaped(&self) -> String {
+		escape_label(self.0.as_bytes())
+	}
+
+	/// Parse escaped label (e.g. the first label of a full name)
+	///
+	/// Fails if the label contains unescaped dots, invalid escapes or
+	/// invalid UTF-8.
+	pub fn from_escaped(label: &str) -> io::Result<Self> {
+		let invalid =
+			|| io::Error::new(io::ErrorKind::InvalidInput, "invalid escaped instance name");
+		match split_labels(label).as_deref() {
+			Some([single]) if single.len() == label.len() => (),
+			_ => return Err(invalid()),
+		}
+		let name = unescape_label(label).ok_or_else(invalid)?;
+		Ok(Self(String::from_utf8(name).map_err(|_| invalid())?))
+	}
+
+	/// Unescaped name
+	pub fn into_string(self) -> String {
+		self.0
+	}
+}
+
+impl From<String> for ServiceInstanceName {
+	fn from(name: String) -> Self {
+		Self(name)
+	}
+}
+
+impl From<&str> for ServiceInstanceName {
+	fn from(name: &str) -> Self {
+		Self(name.to_string())
+	}
+}
+
+impl From<ServiceInstanceName> for String {
+	fn from(name: ServiceInstanceName) -> Self {
+		name.0
+	}
+}
+
+impl AsRef<str> for ServiceInstanceName {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+/// Error of [`ServiceInstanceName::check`]
+///
+/// [`ServiceInstanceName::check`]: struct.ServiceInstanceName.html#method.check
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[non_exhaustive]
+pub enum InstanceNameError {
+	/// Name is empty
+	Empty,
+	/// Name is longer than 63 bytes (UTF-8 encoded)
+	TooLong {
+		/// length of the name in bytes
+		len: usize,
+	},
+	/// Name contains a control character
+	ControlCharacter {
+		/// byte offset of the control character
+		position: usize,
+	},
+}
+
+impl fmt::Display for InstanceNameError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Empty => f.write_str("empty service instance name"),
+			Self::TooLong { len } => write!(
+				f,
+				"service instance name too long ({} bytes, at most {})",
+				len,
+				ServiceInstanceName::MAX_LEN
+			),
+			Self::ControlCharacter { position } => write!(
+				f,
+				"control character in service instance name at byte {}",
+				position
+			),
+		}
+	}
+}
+
+impl error::Error for InstanceNameError {}
+
+impl From<InstanceNameError> for io::Error {
+	fn from(e: InstanceNameError) -> Self {
+		io::Error::new(io::ErrorKind::InvalidInput, e)
+	}
+}
+
+/// Turn arbitrary text (e.g. a user provided device name) into a valid
+/// service instance name
+///
+/// Normalizes to NFC (with the `nfc` feature), replaces control
+/// characters (including newlines) with spaces, trims whitespace and
+/// truncates to 63 bytes on a UTF-8 character boundary.  An empty result
+/// isn't valid (see [`ServiceInstanceName::check`]).
+///
+/// [`ServiceInstanceName::check`]: struct.ServiceInstanceName.html#method.check
+pub fn sanitize_instance_name(name: &str) -> ServiceInstanceName {
+	#[cfg(feature = "nfc")]
+	let name = &ServiceInstanceName::from(name).nfc().into_string();
+	let name: String = name
+		.chars()
+		.map(|c| if c.is_control() { ' ' } else { c })
+		.collect();
+	let mut name = name.trim();
+	if name.len() > ServiceInstanceName::MAX_LEN {
+		let mut end = ServiceInstanceName::MAX_LEN;
+		while !name.is_char_boundary(end) {
+			end -= 1;
+		}
+		name = name[..end].trim_end();
+	}
+	ServiceInstanceName::from(name)
+}
+
+/// Shows the unescaped name
+impl fmt::Display for ServiceInstanceName {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl fmt::Debug for ServiceInstanceName {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(&self.0, f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		sanitize_instance_name,
+		InstanceNameError,
+		ServiceInstanceName,
+	};
+
+	#[test]
+	fn escape_roundtrip() {
+		let name = ServiceInstanceName::from("My.Printer\\ 2");
+		assert_eq!(name.escaped(), "My\\.Printer\\\\\\0322");
+		assert_eq!(
+			ServiceInstanceName::from_escaped(&name.escaped()).unwrap(),
+			name
+		);
+		assert!(ServiceInstanceName::from_escaped("a.b").is_err());
+	}
+
+	#[test]
+	fn check_and_sanitize() {
+		let long = "\u{e4}".repeat(32); // 64 bytes
+		assert_eq!(
+			ServiceInstanceName::from(long.as_str()).check(),
+			Err(InstanceNameError::TooLong { len: 64 })
+		);
+		assert_eq!(
+			ServiceInstanceName::from("a\nb").check(),
+			Err(InstanceNameError::ControlCharacter { position: 1 })
+		);
+		assert_eq!(ServiceInstanceName::from("My.Printer").check(), Ok(()));
+		assert_eq!(sanitize_instance_name(&long).as_str(), "\u{e4}".repeat(31));
+		assert_eq!(sanitize_instance_name(" a\tb\n").as_str(), "a b");
+		assert!(sanitize_instance_name("\n").check().is_err());
+	}
+
+	#[test]
+	fn for_register() {
+		assert_eq!(ServiceInstanceName::for_register(None), Ok(None));
+		assert_eq!(ServiceInstanceName::for_register(Some("")), Ok(None));
+		assert_eq!(
+			ServiceInstanceName::for_register(Some("Printer")),
+			Ok(Some(ServiceInstanceName::from("Printer")))
+		);
+		assert!(ServiceInstanceName::for_register(Some("a\nb")).is_err());
+	}
+}
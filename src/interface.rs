@@ -1,4 +1,13 @@
-use std::fmt;
+use std::{
+	fmt,
+	io,
+	net::{
+		IpAddr,
+		SocketAddr,
+		UdpSocket,
+	},
+	num::NonZeroU32,
+};
 
 use crate::ffi;
 
@@ -25,6 +34,119 @@ impl InterfaceIndex {
 	pub fn into_raw(self) -> u32 {
 		self.0
 	}
+
+	/// Lookup interface by name
+	pub fn from_name(name: &str) -> Option<Self> {
+		#[cfg(unix)]
+		{
+			let name = std::ffi::CString::new(name).ok()?;
+			let ndx = unsafe { libc::if_nametoindex(name.as_ptr()) };
+			Self::from_raw(ndx)
+		}
+		#[cfg(not(unix))]
+		{
+			let _ = name;
+			None
+		}
+	}
+
+	/// Find interface owning the given (local) address
+	pub fn from_address(address: IpAddr) -> Option<Self> {
+		#[cfg(unix)]
+		{
+			let addrs = if_addrs().ok()?;
+			addrs
+				.into_iter()
+				.find(|a| a.address == Some(address))
+				.map(|a| a.index)
+		}
+		#[cfg(not(unix))]
+		{
+			let _ = address;
+			None
+		}
+	}
+
+	/// Addresses currently assigned to the interface
+	///
+	/// Fails with `NotFound` if the interface doesn't exist (anymore);
+	/// see [`metadata`](#method.metadata).
+	pub fn addresses(self) -> io::Result<Vec<IpAddr>> {
+		Ok(self.metadata()?.addresses)
+	}
+
+	/// Current addresses, flags and MTU of the interface
+	///
+	/// Use this to decide which interfaces to advertise services on, e.g.
+	/// only where a socket is listening.
+	///
+	/// Fails with `NotFound` if the interface doesn't exist (anymore).
+	pub fn metadata(self) -> io::Result<InterfaceMetadata> {
+		#[cfg(any(unix, windows))]
+		{
+			let mut found = false;
+			let mut metadata = InterfaceMetadata {
+				index: self,
+				name: None,
+				addresses: Vec::new(),
+				up: false,
+				loopback: false,
+				multicast: false,
+				mtu: None,
+			};
+			for entry in if_addrs()? {
+				if entry.index != self {
+					continue;
+				}
+				found = true;
+				metadata.up |= entry.up;
+				metadata.loopback |= entry.loopback;
+				metadata.multicast |= entry.multicast;
+				metadata.addresses.extend(entry.address);
+			}
+			if !found {
+				return Err(io::Error::new(
+					io::ErrorKind::NotFound,
+					format!("interface {} not found", self),
+				));
+			}
+			#[cfg(unix)]
+			{
+				metadata.name = self.name();
+				metadata.mtu = metadata.name.as_deref().and_then(mtu);
+			}
+			#[cfg(windows)]
+			{
+				(metadata.name, metadata.mtu) = adapter_name_mtu(self)?;
+			}
+			Ok(metadata)
+		}
+		#[cfg(not(any(unix, windows)))]
+		{
+			Err(io::Error::new(
+				io::ErrorKind::Unsupported,
+				"interface metadata not supported on this platform",
+			))
+		}
+	}
+
+	/// Name of the interface (if it still exists)
+	pub fn name(self) -> Option<String> {
+		#[cfg(unix)]
+		{
+			let mut buf = [0 as libc::c_char; libc::IF_NAMESIZE];
+			let name = unsafe { libc::if_indextoname(self.0, buf.as_mut_ptr()) };
+			if name.is_null() {
+				return None;
+			}
+			let name = unsafe { std::ffi::CStr::from_ptr(name) };
+			Some(name.to_string_lossy().into_owned())
+		}
+		#[cfg(not(unix))]
+		{
+			None
+		}
+	}
 }
 
 impl From<InterfaceIndex> for u32 {
@@ -39,15 +161,48 @@ impl fmt::Debug for InterfaceIndex {
 	}
 }
 
+/// Shows the interface name, or the index if the name is unknown
+impl fmt::Display for InterfaceIndex {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.name() {
+			Some(name) => f.write_str(&name),
+			None => write!(f, "#{}", self.0),
+		}
+	}
+}
+
+/// Current state of a network interface
+///
+/// See [`InterfaceIndex::metadata`](struct.InterfaceIndex.html#method.metadata).
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct InterfaceMetadata {
+	/// interface index
+	pub index: InterfaceIndex,
+	/// interface name (the "friendly name" of the adapter on Windows)
+	pub name: Option<String>,
+	/// assigned IPv4 and IPv6 addresses
+	pub addresses: Vec<IpAddr>,
+	/// interface is up
+	pub up: bool,
+	/// loopback interface
+	pub loopback: bool,
+	/// interface supports multicast (required for mDNS)
+	pub multicast: bool,
+	/// MTU (only available on Linux and Windows)
+	pub mtu: Option<u32>,
+}
+
 /// Network interface
 ///
 /// Either identifies a single interface (by index) or the special "Any"
 /// or "LocalOnly" interfaces.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
 #[non_exhaustive]
 pub enum Interface {
 	/// Any interface; depending on domain name this means either
 	/// multicast or unicast
+	#[default]
 	Any,
 	/// Single interface
 	Index(InterfaceIndex),
@@ -59,12 +214,6 @@ pub enum Interface {
 	PeerToPeer,
 }
 
-impl Default for Interface {
-	fn default() -> Self {
-		Self::Any
-	}
-}
-
 impl Interface {
 	/// Construct from raw value
 	pub fn from_raw(raw: u32) -> Self {
@@ -88,6 +237,77 @@ impl Interface {
 		}
 	}
 
+	/// Restrict socket to the interface
+	///
+	/// Use this to talk to a service on the interface it was found on;
+	/// required for IPv6 link-local addresses.  Binds the socket to the
+	/// interface (`SO_BINDTOIFINDEX` on Linux, `IP_BOUND_IF` /
+	/// `IPV6_BOUND_IF` on Apple platforms, `IP_BOUND_IF` on Solaris) and for
+	/// IPv6 also selects the interface for outgoing multicast.  On other
+	/// platforms only the multicast interface is set for IPv6.
+	///
+	/// Does nothing for anything but [`Interface::Index`].
+	///
+	/// [`Interface::Index`]: #variant.Index
+	pub fn configure_socket(self, socket: &socket2::Socket) -> io::Result<()> {
+		if self.scope_id() == 0 {
+			return Ok(());
+		}
+		// even unbound sockets report their address family
+		let ipv6 = socket.local_addr()?.is_ipv6();
+		self.configure_socket_family(socket, ipv6)
+	}
+
+	fn configure_socket_family(self, socket: &socket2::Socket, ipv6: bool) -> io::Result<()> {
+		let index = match NonZeroU32::new(self.scope_id()) {
+			Some(index) => index,
+			None => return Ok(()),
+		};
+		#[cfg(any(
+			target_os = "linux",
+			target_os = "android",
+			target_os = "macos",
+			target_os = "ios",
+			target_os = "tvos",
+			target_os = "watchos",
+			target_os = "visionos",
+			target_os = "illumos",
+			target_os = "solaris",
+		))]
+		{
+			if ipv6 {
+				socket.bind_device_by_index_v6(Some(index))?;
+			} else {
+				socket.bind_device_by_index_v4(Some(index))?;
+			}
+		}
+		if ipv6 {
+			socket.set_multicast_if_v6(index.get())?;
+		}
+		Ok(())
+	}
+
+	/// Create UDP socket bound to `addr` and restricted to the interface
+	///
+	/// See [`configure_socket`](#method.configure_socket); also fills in
+	/// the scope id of IPv6 link-local addresses if missing.
+	pub fn bind_udp(self, addr: SocketAddr) -> io::Result<UdpSocket> {
+		let mut addr = addr;
+		if let SocketAddr::V6(addr) = &mut addr {
+			if addr.scope_id() == 0 && addr.ip().is_unicast_link_local() {
+				addr.set_scope_id(self.scope_id());
+			}
+		}
+		let socket = socket2::Socket::new(
+			socket2::Domain::for_address(addr),
+			socket2::Type::DGRAM,
+			Some(socket2::Protocol::UDP),
+		)?;
+		self.configure_socket_family(&socket, addr.is_ipv6())?;
+		socket.bind(&addr.into())?;
+		Ok(socket.into())
+	}
+
 	/// Extract scope id / interface index
 	///
 	/// Returns the interface index (or zero if not a single interface is selected)
@@ -99,8 +319,285 @@ impl Interface {
 	}
 }
 
+/// Restriction of the interfaces operations may use
+///
+/// Applied to all operations started through a
+/// [`DnssdContext`](struct.DnssdContext.html) (see
+/// [`DnssdContext::with_interface_policy`]); operations requesting
+/// [`Interface::Any`] (the default) use the restricted interface, other
+/// interfaces not allowed by the policy fail with `InvalidInput`.
+///
+/// [`DnssdContext::with_interface_policy`]: struct.DnssdContext.html#method.with_interface_policy
+/// [`Interface::Any`]: enum.Interface.html#variant.Any
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[non_exhaustive]
+pub enum InterfacePolicy {
+	/// Use the requested interface
+	#[default]
+	Unrestricted,
+	/// Only use the given interface, e.g. `Index` of a named interface
+	/// or `LocalOnly`
+	Only(Interface),
+	/// Don't use peer-to-peer interfaces: [`Interface::PeerToPeer`] is
+	/// rejected, and flags to include peer-to-peer interfaces (like
+	/// [`BrowseFlags::INCLUDE_P2P`]) are ignored
+	///
+	/// [`Interface::PeerToPeer`]: enum.Interface.html#variant.PeerToPeer
+	/// [`BrowseFlags::INCLUDE_P2P`]: struct.BrowseFlags.html#associatedconstant.INCLUDE_P2P
+	ExcludePeerToPeer,
+}
+
+impl InterfacePolicy {
+	/// Interface to use for an operation requesting `interface`
+	pub fn interface(self, interface: Interface) -> io::Result<Interface> {
+		let allowed = match self {
+			Self::Unrestricted => Some(interface),
+			Self::Only(only) if interface == Interface::Any || interface == only => Some(only),
+			Self::Only(_) => None,
+			Self::ExcludePeerToPeer if interface == Interface::PeerToPeer => None,
+			Self::ExcludePeerToPeer => Some(interface),
+		};
+		allowed.ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("interface {} not allowed by {:?}", interface, self),
+			)
+		})
+	}
+
+	/// Whether peer-to-peer interfaces may be included when using
+	/// [`Interface::Any`](enum.Interface.html#variant.Any)
+	pub fn allows_peer_to_peer(self) -> bool {
+		self != Self::ExcludePeerToPeer
+	}
+}
+
+impl fmt::Display for Interface {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Any => f.write_str("any"),
+			Self::Index(ndx) => fmt::Display::fmt(ndx, f),
+			Self::LocalOnly => f.write_str("local-only"),
+			Self::Unicast => f.write_str("unicast"),
+			Self::PeerToPeer => f.write_str("p2p"),
+		}
+	}
+}
+
 impl From<Interface> for u32 {
 	fn from(i: Interface) -> u32 {
 		i.into_raw()
 	}
 }
+
+#[cfg(target_os = "linux")]
+fn mtu(name: &str) -> Option<u32> {
+	let mtu = std::fs::read_to_string(format!("/sys/class/net/{}/mtu", name)).ok()?;
+	mtu.trim().parse().ok()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn mtu(_name: &str) -> Option<u32> {
+	None
+}
+
+// entry from `getifaddrs` (or `GetAdaptersAddresses` on windows)
+#[cfg(any(unix, windows))]
+pub(crate) struct IfAddr {
+	pub(crate) index: InterfaceIndex,
+	pub(crate) up: bool,
+	pub(crate) loopback: bool,
+	pub(crate) multicast: bool,
+	// `None` for entries of other address families (e.g. link layer)
+	pub(crate) address: Option<IpAddr>,
+}
+
+#[cfg(unix)]
+pub(crate) fn if_addrs() -> io::Result<Vec<IfAddr>> {
+	let mut result = Vec::new();
+	unsafe {
+		let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+		if libc::getifaddrs(&mut addrs) != 0 {
+			return Err(io::Error::last_os_error());
+		}
+		let mut cur = addrs;
+		while !cur.is_null() {
+			let ifa = &*cur;
+			cur = ifa.ifa_next;
+			let index = match InterfaceIndex::from_raw(libc::if_nametoindex(ifa.ifa_name)) {
+				Some(index) => index,
+				None => continue,
+			};
+			let address = if ifa.ifa_addr.is_null() {
+				None
+			} else {
+				match i32::from((*ifa.ifa_addr).sa_family) {
+					libc::AF_INET => {
+						let sin = &*(ifa.ifa_addr as *const libc::sockaddr_in);
+						// `s_addr` is stored in network byte order
+						Some(IpAddr::from(sin.sin_addr.s_addr.to_ne_bytes()))
+					},
+					libc::AF_INET6 => {
+						let sin6 = &*(ifa.ifa_addr as *const libc::sockaddr_in6);
+						Some(IpAddr::from(sin6.sin6_addr.s6_addr))
+					},
+					_ => None,
+				}
+			};
+			result.push(IfAddr {
+				index,
+				up: ifa.ifa_flags & (libc::IFF_UP as libc::c_uint) != 0,
+				loopback: ifa.ifa_flags & (libc::IFF_LOOPBACK as libc::c_uint) != 0,
+				multicast: ifa.ifa_flags & (libc::IFF_MULTICAST as libc::c_uint) != 0,
+				address,
+			});
+		}
+		libc::freeifaddrs(addrs);
+	}
+	Ok(result)
+}
+
+// calls `f` for all adapters
+#[cfg(windows)]
+fn for_each_adapter(
+	mut f: impl FnMut(&winapi::um::iptypes::IP_ADAPTER_ADDRESSES),
+) -> io::Result<()> {
+	use winapi::{
+		shared::{
+			winerror::{
+				ERROR_BUFFER_OVERFLOW,
+				ERROR_NO_DATA,
+				NO_ERROR,
+			},
+			ws2def::AF_UNSPEC,
+		},
+		um::{
+			iphlpapi::GetAdaptersAddresses,
+			iptypes::{
+				GAA_FLAG_SKIP_ANYCAST,
+				GAA_FLAG_SKIP_DNS_SERVER,
+				GAA_FLAG_SKIP_MULTICAST,
+				PIP_ADAPTER_ADDRESSES,
+			},
+		},
+	};
+
+	let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER;
+	// recommended initial size; grows if adapters show up in between
+	let mut size: u32 = 15000;
+	loop {
+		// `u64` elements for the alignment of the structures
+		let mut buf = vec![0u64; (size as usize + 7) / 8];
+		let adapters = buf.as_mut_ptr() as PIP_ADAPTER_ADDRESSES;
+		let r = unsafe {
+			GetAdaptersAddresses(
+				AF_UNSPEC as u32,
+				flags,
+				std::ptr::null_mut(),
+				adapters,
+				&mut size,
+			)
+		};
+		match r {
+			NO_ERROR => (),
+			ERROR_NO_DATA => return Ok(()),
+			ERROR_BUFFER_OVERFLOW => continue,
+			_ => return Err(io::Error::from_raw_os_error(r as i32)),
+		}
+		let mut cur = adapters;
+		while !cur.is_null() {
+			let adapter = unsafe { &*cur };
+			cur = adapter.Next;
+			f(adapter);
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(windows)]
+fn adapter_index(adapter: &winapi::um::iptypes::IP_ADAPTER_ADDRESSES) -> Option<InterfaceIndex> {
+	// `IfIndex` is zero if IPv4 is disabled on the adapter
+	let index = unsafe { adapter.u.s().IfIndex };
+	InterfaceIndex::from_raw(if index != 0 {
+		index
+	} else {
+		adapter.Ipv6IfIndex
+	})
+}
+
+// friendly name and MTU of the adapter
+#[cfg(windows)]
+fn adapter_name_mtu(index: InterfaceIndex) -> io::Result<(Option<String>, Option<u32>)> {
+	let mut result = (None, None);
+	for_each_adapter(|adapter| {
+		if adapter_index(adapter) != Some(index) {
+			return;
+		}
+		let name = adapter.FriendlyName;
+		if !name.is_null() {
+			let len = (0..).take_while(|&i| unsafe { *name.add(i) } != 0).count();
+			let name = unsafe { std::slice::from_raw_parts(name, len) };
+			result.0 = Some(String::from_utf16_lossy(name));
+		}
+		result.1 = Some(adapter.Mtu);
+	})?;
+	Ok(result)
+}
+
+#[cfg(windows)]
+pub(crate) fn if_addrs() -> io::Result<Vec<IfAddr>> {
+	use winapi::{
+		shared::{
+			ifdef::IfOperStatusUp,
+			ipifcons::IF_TYPE_SOFTWARE_LOOPBACK,
+			ws2def::{
+				AF_INET,
+				AF_INET6,
+				SOCKADDR_IN,
+			},
+			ws2ipdef::SOCKADDR_IN6_LH,
+		},
+		um::iptypes::IP_ADAPTER_NO_MULTICAST,
+	};
+
+	let mut result = Vec::new();
+	for_each_adapter(|adapter| {
+		let index = match adapter_index(adapter) {
+			Some(index) => index,
+			None => return,
+		};
+		let entry = |address| IfAddr {
+			index,
+			up: adapter.OperStatus == IfOperStatusUp,
+			loopback: adapter.IfType == IF_TYPE_SOFTWARE_LOOPBACK,
+			multicast: adapter.Flags & IP_ADAPTER_NO_MULTICAST == 0,
+			address,
+		};
+		// like the link layer entry of `getifaddrs`: adapters without
+		// addresses are still listed
+		result.push(entry(None));
+		let mut cur = adapter.FirstUnicastAddress;
+		while !cur.is_null() {
+			let unicast = unsafe { &*cur };
+			cur = unicast.Next;
+			let sa = unicast.Address.lpSockaddr;
+			if sa.is_null() {
+				continue;
+			}
+			let address = match i32::from(unsafe { (*sa).sa_family }) {
+				AF_INET => {
+					let sin = unsafe { &*(sa as *const SOCKADDR_IN) };
+					// `S_addr` is stored in network byte order
+					IpAddr::from(unsafe { sin.sin_addr.S_un.S_addr() }.to_ne_bytes())
+				},
+				AF_INET6 => {
+					let sin6 = unsafe { &*(sa as *const SOCKADDR_IN6_LH) };
+					IpAddr::from(*unsafe { sin6.sin6_addr.u.Byte() })
+				},
+				_ => continue,
+			};
+			result.push(entry(Some(address)));
+		}
+	})?;
+	Ok(result)
+}
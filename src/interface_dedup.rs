@@ -0,0 +1,310 @@
+use futures_core::{
+	Stream,
+	TryStream,
+};
+use std::{
+	collections::{
+		HashMap,
+		VecDeque,
+	},
+	hash::Hash,
+	net::IpAddr,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	dns_consts::{
+		Class,
+		Type,
+	},
+	interface::Interface,
+	service::{
+		AddrInfoFlags,
+		BrowseResult,
+		BrowsedFlags,
+		GetAddrInfoResult,
+		QueriedRecordFlags,
+		QueryRecordResult,
+		ServiceKey,
+	},
+};
+
+/// Results [`InterfaceDedup`](struct.InterfaceDedup.html) can handle
+pub trait InterfaceDedupItem {
+	/// Identity of the result ignoring the interface
+	type Key: Eq + Hash;
+
+	/// Key identifying the result on all interfaces
+	fn dedup_key(&self) -> Self::Key;
+	/// Interface the result was found on
+	fn interface(&self) -> Interface;
+	/// Whether the result is an add (not a removal)
+	fn is_add(&self) -> bool;
+	/// Whether the `MORE_COMING` flag is set
+	fn more_coming(&self) -> bool;
+	/// Set or clear the `MORE_COMING` flag
+	fn set_more_coming(&mut self, more_coming: bool);
+}
+
+impl InterfaceDedupItem for BrowseResult {
+	type Key = ServiceKey;
+
+	fn dedup_key(&self) -> ServiceKey {
+		ServiceKey {
+			interface: Interface::Any,
+			..self.service_key()
+		}
+	}
+
+	fn interface(&self) -> Interface {
+		self.interface
+	}
+
+	fn is_add(&self) -> bool {
+		self.flags.contains(BrowsedFlags::ADD)
+	}
+
+	fn more_coming(&self) -> bool {
+		self.flags.contains(BrowsedFlags::MORE_COMING)
+	}
+
+	fn set_more_coming(&mut self, more_coming: bool) {
+		self.flags.set(BrowsedFlags::MORE_COMING, more_coming);
+	}
+}
+
+impl InterfaceDedupItem for QueryRecordResult {
+	type Key = (String, Type, Class, bytes::Bytes);
+
+	fn dedup_key(&self) -> Self::Key {
+		(
+			self.fullname.clone(),
+			self.rr_type,
+			self.rr_class,
+			self.rdata.clone(),
+		)
+	}
+
+	fn interface(&self) -> Interface {
+		self.interface
+	}
+
+	fn is_add(&self) -> bool {
+		self.flags.contains(QueriedRecordFlags::ADD)
+	}
+
+	fn more_coming(&self) -> bool {
+		self.flags.contains(QueriedRecordFlags::MORE_COMING)
+	}
+
+	fn set_more_coming(&mut self, more_coming: bool) {
+		self.flags.set(QueriedRecordFlags::MORE_COMING, more_coming);
+	}
+}
+
+impl InterfaceDedupItem for GetAddrInfoResult {
+	type Key = (String, IpAddr);
+
+	fn dedup_key(&self) -> Self::Key {
+		(self.hostname.clone(), self.address)
+	}
+
+	fn interface(&self) -> Interface {
+		self.interface
+	}
+
+	fn is_add(&self) -> bool {
+		self.flags.contains(AddrInfoFlags::ADD)
+	}
+
+	fn more_coming(&self) -> bool {
+		self.flags.contains(AddrInfoFlags::MORE_COMING)
+	}
+
+	fn set_more_coming(&mut self, more_coming: bool) {
+		self.flags.set(AddrInfoFlags::MORE_COMING, more_coming);
+	}
+}
+
+/// `Stream` extension to simplify building
+/// [`InterfaceDedup`](struct.InterfaceDedup.html)
+pub trait InterfaceDedupExt: TryStream + Sized
+where
+	Self::Ok: InterfaceDedupItem,
+{
+	/// Create new [`InterfaceDedup`](struct.InterfaceDedup.html)
+	fn dedup_interfaces(self) -> InterfaceDedup<Self>;
+}
+
+impl<S: TryStream> InterfaceDedupExt for S
+where
+	S::Ok: InterfaceDedupItem,
+{
+	fn dedup_interfaces(self) -> InterfaceDedup<Self> {
+		InterfaceDedup::new(self)
+	}
+}
+
+/// Merge results seen on multiple interfaces
+///
+/// Counts on how many interfaces each result (see
+/// [`InterfaceDedupItem::dedup_key`]) is present; only the first add
+/// and the removal from the last interface are forwarded, i.e. the
+/// forwarded results carry the interface they were first (or last)
+/// seen on.
+///
+/// Forwarded results are held back until a result without
+/// `MORE_COMING` arrives (or the underlying stream ends), so the last
+/// forwarded result of each batch doesn't have `MORE_COMING` set even
+/// if the result ending the batch was dropped.
+///
+/// [`InterfaceDedupItem::dedup_key`]: trait.InterfaceDedupItem.html#tymethod.dedup_key
+#[must_use = "streams do nothing unless polled"]
+pub struct InterfaceDedup<S: TryStream>
+where
+	S::Ok: InterfaceDedupItem,
+{
+	stream: S,
+	present: HashMap<<S::Ok as InterfaceDedupItem>::Key, Vec<Interface>>,
+	// forwarded results of the current batch
+	batch: Vec<S::Ok>,
+	ready: VecDeque<S::Ok>,
+	stream_done: bool,
+}
+
+impl<S: TryStream> InterfaceDedup<S>
+where
+	S::Ok: InterfaceDedupItem,
+{
+	/// Create new `InterfaceDedup`.
+	///
+	/// Also see [`InterfaceDedupExt::dedup_interfaces`](trait.InterfaceDedupExt.html#tymethod.dedup_interfaces).
+	pub fn new(stream: S) -> Self {
+		Self {
+			stream,
+			present: HashMap::new(),
+			batch: Vec::new(),
+			ready: VecDeque::new(),
+			stream_done: false,
+		}
+	}
+
+	fn stream(self: Pin<&mut Self>) -> Pin<&mut S> {
+		// `stream` is structurally pinned
+		unsafe { self.map_unchecked_mut(|s| &mut s.stream) }
+	}
+
+	fn fields(self: Pin<&mut Self>) -> &mut Self {
+		// `stream` is the only structurally pinned field; must not move it
+		unsafe { self.get_unchecked_mut() }
+	}
+
+	// whether the result is a logical add or remove transition
+	fn transition(&mut self, item: &S::Ok) -> bool {
+		let key = item.dedup_key();
+		let interface = item.interface();
+		if item.is_add() {
+			let interfaces = self.present.entry(key).or_default();
+			if interfaces.contains(&interface) {
+				return false;
+			}
+			interfaces.push(interface);
+			interfaces.len() == 1
+		} else {
+			let Some(interfaces) = self.present.get_mut(&key) else {
+				return false;
+			};
+			interfaces.retain(|i| *i != interface);
+			if !interfaces.is_empty() {
+				return false;
+			}
+			self.present.remove(&key);
+			true
+		}
+	}
+
+	fn handle(&mut self, item: S::Ok) {
+		let more_coming = item.more_coming();
+		if self.transition(&item) {
+			self.batch.push(item);
+		}
+		if !more_coming {
+			self.finish_batch();
+		}
+	}
+
+	fn finish_batch(&mut self) {
+		if let Some(last) = self.batch.last_mut() {
+			last.set_more_coming(false);
+		}
+		self.ready.extend(self.batch.drain(..));
+	}
+}
+
+impl<S: TryStream> Stream for InterfaceDedup<S>
+where
+	S::Ok: InterfaceDedupItem,
+{
+	type Item = Result<S::Ok, S::Error>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		loop {
+			if let Some(item) = self.as_mut().fields().ready.pop_front() {
+				return Poll::Ready(Some(Ok(item)));
+			}
+			if self.stream_done {
+				return Poll::Ready(None);
+			}
+			match futures_core::ready!(self.as_mut().stream().try_poll_next(cx)) {
+				Some(Ok(item)) => self.as_mut().fields().handle(item),
+				Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+				None => {
+					let this = self.as_mut().fields();
+					this.stream_done = true;
+					this.finish_batch();
+				},
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures_util::StreamExt;
+	use std::io;
+
+	fn result(interface: u32, flags: BrowsedFlags) -> io::Result<BrowseResult> {
+		Ok(BrowseResult {
+			flags,
+			interface: Interface::from_raw(interface),
+			service_name: "printer".to_string(),
+			reg_type: "_ipp._tcp.".to_string(),
+			domain: "local.".to_string(),
+		})
+	}
+
+	#[tokio::test]
+	async fn merges_interfaces() {
+		let more = BrowsedFlags::ADD | BrowsedFlags::MORE_COMING;
+		let (tx, rx) = futures_channel::mpsc::unbounded();
+		let mut dedup = rx.dedup_interfaces();
+
+		// batch ends with a dropped result
+		tx.unbounded_send(result(1, more)).unwrap();
+		tx.unbounded_send(result(2, BrowsedFlags::ADD)).unwrap();
+		tx.unbounded_send(result(1, BrowsedFlags::empty())).unwrap();
+		tx.unbounded_send(result(2, BrowsedFlags::empty())).unwrap();
+		drop(tx);
+		let results = dedup.by_ref().map(|r| r.unwrap()).collect::<Vec<_>>().await;
+		assert_eq!(results.len(), 2);
+		assert_eq!(results[0].flags, BrowsedFlags::ADD);
+		assert_eq!(results[0].interface, Interface::from_raw(1));
+		assert_eq!(results[1].flags, BrowsedFlags::empty());
+		assert_eq!(results[1].interface, Interface::from_raw(2));
+	}
+}
@@ -0,0 +1,622 @@
+use futures_core::Stream;
+use std::{
+	collections::{
+		BTreeMap,
+		BTreeSet,
+		VecDeque,
+	},
+	io,
+	net::IpAddr,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::interface::InterfaceIndex;
+
+/// Change of a network interface
+///
+/// See [`interface_events`](fn.interface_events.html).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum InterfaceEvent {
+	/// Interface appeared or went up
+	Up(InterfaceIndex),
+	/// Interface went down or disappeared
+	Down(InterfaceIndex),
+	/// Address was added to an interface
+	AddressAdded(InterfaceIndex, IpAddr),
+	/// Address was removed from an interface (also reported for all
+	/// addresses of a disappearing interface)
+	AddressRemoved(InterfaceIndex, IpAddr),
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+struct InterfaceState {
+	up: bool,
+	addresses: BTreeSet<IpAddr>,
+}
+
+type Snapshot = BTreeMap<InterfaceIndex, InterfaceState>;
+
+#[cfg(any(unix, windows))]
+fn snapshot() -> io::Result<Snapshot> {
+	let mut snapshot = Snapshot::new();
+	for entry in crate::interface::if_addrs()? {
+		let state = snapshot.entry(entry.index).or_default();
+		state.up |= entry.up;
+		state.addresses.extend(entry.address);
+	}
+	Ok(snapshot)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn snapshot() -> io::Result<Snapshot> {
+	Err(io::Error::new(
+		io::ErrorKind::Unsupported,
+		"interface events not supported on this platform",
+	))
+}
+
+// events to get from `old` to `new`; missing interfaces are down without
+// addresses
+fn diff(old: &Snapshot, new: &Snapshot, events: &mut VecDeque<InterfaceEvent>) {
+	let gone = InterfaceState::default();
+	let indices: BTreeSet<InterfaceIndex> = old.keys().chain(new.keys()).copied().collect();
+	for index in indices {
+		let old = old.get(&index).unwrap_or(&gone);
+		let new = new.get(&index).unwrap_or(&gone);
+		if !old.up && new.up {
+			events.push_back(InterfaceEvent::Up(index));
+		}
+		for &address in old.addresses.difference(&new.addresses) {
+			events.push_back(InterfaceEvent::AddressRemoved(index, address));
+		}
+		for &address in new.addresses.difference(&old.addresses) {
+			events.push_back(InterfaceEvent::AddressAdded(index, address));
+		}
+		if old.up && !new.up {
+			events.push_back(InterfaceEvent::Down(index));
+		}
+	}
+}
+
+// wakes up when interfaces might have changed
+#[cfg(target_os = "linux")]
+struct Trigger(tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>);
+
+#[cfg(target_os = "linux")]
+impl Trigger {
+	fn new() -> io::Result<Self> {
+		use std::os::fd::{
+			AsRawFd,
+			FromRawFd,
+			OwnedFd,
+		};
+
+		unsafe {
+			let fd = libc::socket(
+				libc::AF_NETLINK,
+				libc::SOCK_RAW | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+				libc::NETLINK_ROUTE,
+			);
+			if fd < 0 {
+				return Err(io::Error::last_os_error());
+			}
+			let fd = OwnedFd::from_raw_fd(fd);
+			let mut addr: libc::sockaddr_nl = std::mem::zeroed();
+			addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+			addr.nl_groups =
+				(libc::RTMGRP_LINK | libc::RTMGRP_IPV4_IFADDR | libc::RTMGRP_IPV6_IFADDR) as u32;
+			if libc::bind(
+				fd.as_raw_fd(),
+				&addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+				size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+			) != 0
+			{
+				return Err(io::Error::last_os_error());
+			}
+			Ok(Self(tokio::io::unix::AsyncFd::new(fd)?))
+		}
+	}
+
+	fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		use std::os::fd::AsRawFd;
+
+		// the messages only trigger taking a new snapshot; drain them
+		let mut buf = [0u8; 4096];
+		let mut received = false;
+		while !received {
+			let mut guard = futures_core::ready!(self.0.poll_read_ready(cx))?;
+			loop {
+				let r = unsafe {
+					libc::recv(
+						guard.get_inner().as_raw_fd(),
+						buf.as_mut_ptr() as *mut libc::c_void,
+						buf.len(),
+						libc::MSG_DONTWAIT,
+					)
+				};
+				if r >= 0 {
+					received = true;
+					continue;
+				}
+				let e = io::Error::last_os_error();
+				match e.kind() {
+					io::ErrorKind::WouldBlock => {
+						guard.clear_ready();
+						break;
+					},
+					io::ErrorKind::Interrupted => (),
+					// ENOBUFS: messages got lost, but a new snapshot covers them
+					_ if e.raw_os_error() == Some(libc::ENOBUFS) => received = true,
+					_ => return Poll::Ready(Err(e)),
+				}
+			}
+		}
+		Poll::Ready(Ok(()))
+	}
+}
+
+// set by notification callbacks running on other threads
+#[cfg(any(target_os = "macos", windows))]
+#[derive(Default)]
+struct Changed {
+	changed: std::sync::atomic::AtomicBool,
+	waker: futures_util::task::AtomicWaker,
+}
+
+#[cfg(any(target_os = "macos", windows))]
+impl Changed {
+	fn notify(&self) {
+		self.changed
+			.store(true, std::sync::atomic::Ordering::SeqCst);
+		self.waker.wake();
+	}
+
+	fn poll_changed(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.waker.register(cx.waker());
+		if self
+			.changed
+			.swap(false, std::sync::atomic::Ordering::SeqCst)
+		{
+			Poll::Ready(Ok(()))
+		} else {
+			Poll::Pending
+		}
+	}
+}
+
+#[cfg(target_os = "macos")]
+mod system_configuration {
+	use std::{
+		ffi::CStr,
+		io,
+		os::raw::{
+			c_char,
+			c_int,
+			c_long,
+			c_uchar,
+			c_void,
+		},
+		ptr,
+		sync::Arc,
+		task::{
+			Context,
+			Poll,
+		},
+	};
+
+	use super::Changed;
+
+	type CFIndex = c_long;
+	type CFTypeRef = *const c_void;
+
+	#[repr(C)]
+	struct CFArrayCallBacks {
+		version: CFIndex,
+		retain: *const c_void,
+		release: *const c_void,
+		copy_description: *const c_void,
+		equal: *const c_void,
+	}
+
+	#[repr(C)]
+	struct SCDynamicStoreContext {
+		version: CFIndex,
+		info: *mut c_void,
+		retain: Option<extern "C" fn(*const c_void) -> *const c_void>,
+		release: Option<extern "C" fn(*const c_void)>,
+		copy_description: Option<extern "C" fn(*const c_void) -> CFTypeRef>,
+	}
+
+	type SCDynamicStoreCallBack = extern "C" fn(CFTypeRef, CFTypeRef, *mut c_void);
+
+	const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+	#[link(name = "CoreFoundation", kind = "framework")]
+	extern "C" {
+		#[allow(non_upper_case_globals)]
+		static kCFTypeArrayCallBacks: CFArrayCallBacks;
+
+		fn CFStringCreateWithCString(
+			alloc: CFTypeRef,
+			c_str: *const c_char,
+			encoding: u32,
+		) -> CFTypeRef;
+		fn CFArrayCreate(
+			alloc: CFTypeRef,
+			values: *const CFTypeRef,
+			num_values: CFIndex,
+			callbacks: *const CFArrayCallBacks,
+		) -> CFTypeRef;
+		fn CFRelease(cf: CFTypeRef);
+	}
+
+	#[link(name = "SystemConfiguration", kind = "framework")]
+	extern "C" {
+		fn SCDynamicStoreCreate(
+			alloc: CFTypeRef,
+			name: CFTypeRef,
+			callout: Option<SCDynamicStoreCallBack>,
+			context: *mut SCDynamicStoreContext,
+		) -> CFTypeRef;
+		fn SCDynamicStoreSetNotificationKeys(
+			store: CFTypeRef,
+			keys: CFTypeRef,
+			patterns: CFTypeRef,
+		) -> c_uchar;
+		fn SCDynamicStoreSetDispatchQueue(store: CFTypeRef, queue: *mut c_void) -> c_uchar;
+		fn SCError() -> c_int;
+	}
+
+	// libdispatch is part of libSystem
+	extern "C" {
+		fn dispatch_queue_create(label: *const c_char, attr: *mut c_void) -> *mut c_void;
+		fn dispatch_release(object: *mut c_void);
+	}
+
+	// interface list, link state and addresses of all interfaces
+	const KEYS: &[&CStr] = &[c"State:/Network/Interface"];
+	const PATTERNS: &[&CStr] = &[c"State:/Network/Interface/[^/]+/(Link|IPv4|IPv6)"];
+
+	extern "C" fn retain(info: *const c_void) -> *const c_void {
+		unsafe { Arc::increment_strong_count(info as *const Changed) };
+		info
+	}
+
+	extern "C" fn release(info: *const c_void) {
+		unsafe { Arc::decrement_strong_count(info as *const Changed) };
+	}
+
+	extern "C" fn callout(_store: CFTypeRef, _changed_keys: CFTypeRef, info: *mut c_void) {
+		let changed = unsafe { &*(info as *const Changed) };
+		changed.notify();
+	}
+
+	fn sc_error(function: &str) -> io::Error {
+		let code = unsafe { SCError() };
+		io::Error::other(format!("{} failed: error {}", function, code))
+	}
+
+	unsafe fn string(s: &CStr) -> CFTypeRef {
+		CFStringCreateWithCString(ptr::null(), s.as_ptr(), K_CF_STRING_ENCODING_UTF8)
+	}
+
+	unsafe fn string_array(strings: &[&CStr]) -> CFTypeRef {
+		let values: Vec<CFTypeRef> = strings.iter().map(|s| string(s)).collect();
+		let array = CFArrayCreate(
+			ptr::null(),
+			values.as_ptr(),
+			values.len() as CFIndex,
+			&kCFTypeArrayCallBacks,
+		);
+		// the array retains its values
+		for value in values {
+			CFRelease(value);
+		}
+		array
+	}
+
+	// wakes up when interfaces might have changed (`SCDynamicStore`
+	// notifications on a dispatch queue)
+	pub(super) struct Trigger {
+		store: CFTypeRef,
+		queue: *mut c_void,
+		changed: Arc<Changed>,
+	}
+
+	// the store and queue are only touched to stop the notifications
+	unsafe impl Send for Trigger {}
+
+	impl Trigger {
+		pub(super) fn new() -> io::Result<Self> {
+			let changed = Arc::new(Changed::default());
+			let mut context = SCDynamicStoreContext {
+				version: 0,
+				info: Arc::as_ptr(&changed) as *mut c_void,
+				retain: Some(retain),
+				release: Some(release),
+				copy_description: None,
+			};
+			unsafe {
+				let name = string(c"async-dnssd");
+				let store = SCDynamicStoreCreate(ptr::null(), name, Some(callout), &mut context);
+				CFRelease(name);
+				if store.is_null() {
+					return Err(sc_error("SCDynamicStoreCreate"));
+				}
+				// cleans up on errors below
+				let mut trigger = Self {
+					store,
+					queue: ptr::null_mut(),
+					changed,
+				};
+				let keys = string_array(KEYS);
+				let patterns = string_array(PATTERNS);
+				let ok = SCDynamicStoreSetNotificationKeys(store, keys, patterns);
+				CFRelease(keys);
+				CFRelease(patterns);
+				if ok == 0 {
+					return Err(sc_error("SCDynamicStoreSetNotificationKeys"));
+				}
+				trigger.queue = dispatch_queue_create(
+					c"async-dnssd.interface-events".as_ptr(),
+					ptr::null_mut(),
+				);
+				if SCDynamicStoreSetDispatchQueue(store, trigger.queue) == 0 {
+					return Err(sc_error("SCDynamicStoreSetDispatchQueue"));
+				}
+				Ok(trigger)
+			}
+		}
+
+		pub(super) fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+			self.changed.poll_changed(cx)
+		}
+	}
+
+	impl Drop for Trigger {
+		fn drop(&mut self) {
+			unsafe {
+				if !self.queue.is_null() {
+					SCDynamicStoreSetDispatchQueue(self.store, ptr::null_mut());
+					dispatch_release(self.queue);
+				}
+				CFRelease(self.store);
+			}
+		}
+	}
+}
+
+#[cfg(target_os = "macos")]
+use self::system_configuration::Trigger;
+
+// wakes up when interfaces might have changed (IP interface and unicast
+// address change notifications)
+#[cfg(windows)]
+struct Trigger {
+	handles: Vec<winapi::shared::ntdef::HANDLE>,
+	changed: std::sync::Arc<Changed>,
+}
+
+// the handles are only used to cancel the notifications
+#[cfg(windows)]
+unsafe impl Send for Trigger {}
+
+#[cfg(windows)]
+impl Trigger {
+	fn new() -> io::Result<Self> {
+		use winapi::shared::{
+			netioapi::{
+				NotifyIpInterfaceChange,
+				NotifyUnicastIpAddressChange,
+			},
+			ntdef::FALSE,
+			winerror::NO_ERROR,
+			ws2def::AF_UNSPEC,
+		};
+
+		let changed = std::sync::Arc::new(Changed::default());
+		let context = std::sync::Arc::as_ptr(&changed) as winapi::shared::ntdef::PVOID;
+		// cancels registered notifications on errors below
+		let mut trigger = Self {
+			handles: Vec::new(),
+			changed,
+		};
+		let mut handle = std::ptr::null_mut();
+		let r = unsafe {
+			NotifyIpInterfaceChange(
+				AF_UNSPEC as u16,
+				Some(interface_changed),
+				context,
+				FALSE,
+				&mut handle,
+			)
+		};
+		if r != NO_ERROR {
+			return Err(io::Error::from_raw_os_error(r as i32));
+		}
+		trigger.handles.push(handle);
+		let mut handle = std::ptr::null_mut();
+		let r = unsafe {
+			NotifyUnicastIpAddressChange(
+				AF_UNSPEC as u16,
+				Some(address_changed),
+				context,
+				FALSE,
+				&mut handle,
+			)
+		};
+		if r != NO_ERROR {
+			return Err(io::Error::from_raw_os_error(r as i32));
+		}
+		trigger.handles.push(handle);
+		Ok(trigger)
+	}
+
+	fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.changed.poll_changed(cx)
+	}
+}
+
+#[cfg(windows)]
+impl Drop for Trigger {
+	fn drop(&mut self) {
+		for &handle in &self.handles {
+			// waits for running callbacks; `changed` outlives them
+			unsafe { winapi::shared::netioapi::CancelMibChangeNotify2(handle) };
+		}
+	}
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn interface_changed(
+	context: winapi::shared::ntdef::PVOID,
+	_row: winapi::shared::netioapi::PMIB_IPINTERFACE_ROW,
+	_notification_type: winapi::shared::netioapi::MIB_NOTIFICATION_TYPE,
+) {
+	(*(context as *const Changed)).notify();
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn address_changed(
+	context: winapi::shared::ntdef::PVOID,
+	_row: winapi::shared::netioapi::PMIB_UNICASTIPADDRESS_ROW,
+	_notification_type: winapi::shared::netioapi::MIB_NOTIFICATION_TYPE,
+) {
+	(*(context as *const Changed)).notify();
+}
+
+// no change notifications bound: poll
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+struct Trigger(tokio::time::Interval);
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+impl Trigger {
+	fn new() -> io::Result<Self> {
+		let period = std::time::Duration::from_secs(2);
+		let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		Ok(Self(interval))
+	}
+
+	fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.0.poll_tick(cx).map(|_| Ok(()))
+	}
+}
+
+/// Stream of network interface changes
+///
+/// Created by [`interface_events`](fn.interface_events.html).
+#[must_use = "streams do nothing unless polled"]
+pub struct InterfaceEvents {
+	trigger: Option<Trigger>,
+	// couldn't set up watching
+	failed: bool,
+	known: Snapshot,
+	pending: VecDeque<InterfaceEvent>,
+}
+
+impl InterfaceEvents {
+	fn refresh(&mut self) -> io::Result<()> {
+		let current = snapshot()?;
+		diff(&self.known, &current, &mut self.pending);
+		self.known = current;
+		Ok(())
+	}
+}
+
+impl Stream for InterfaceEvents {
+	type Item = io::Result<InterfaceEvent>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		loop {
+			if let Some(event) = this.pending.pop_front() {
+				return Poll::Ready(Some(Ok(event)));
+			}
+			let trigger = match &mut this.trigger {
+				Some(trigger) => trigger,
+				None if this.failed => return Poll::Ready(None),
+				None => {
+					// first poll: report current state
+					match Trigger::new().and_then(|trigger| {
+						this.trigger = Some(trigger);
+						this.refresh()
+					}) {
+						Ok(()) => continue,
+						Err(e) => {
+							this.trigger = None;
+							this.failed = true;
+							return Poll::Ready(Some(Err(e)));
+						},
+					}
+				},
+			};
+			futures_core::ready!(trigger.poll_changed(cx))?;
+			this.refresh()?;
+		}
+	}
+}
+
+/// Watch network interfaces going up/down and addresses changing
+///
+/// The stream starts with the current state (`Up` for all interfaces
+/// that are up, `AddressAdded` for all addresses), followed by changes;
+/// use it to restart browsing or registrations on changed interfaces.
+///
+/// Uses a netlink socket on Linux, `SCDynamicStore` notifications on
+/// macOS and `NotifyIpInterfaceChange` / `NotifyUnicastIpAddressChange`
+/// on Windows; on other unix platforms the interfaces are checked every
+/// 2 seconds.
+///
+/// Needs to be polled within a tokio runtime.
+pub fn interface_events() -> InterfaceEvents {
+	InterfaceEvents {
+		trigger: None,
+		failed: false,
+		known: Snapshot::new(),
+		pending: VecDeque::new(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn state(up: bool, addresses: &[&str]) -> InterfaceState {
+		InterfaceState {
+			up,
+			addresses: addresses.iter().map(|a| a.parse().unwrap()).collect(),
+		}
+	}
+
+	#[test]
+	fn diff_events() {
+		let eth0 = InterfaceIndex::from_raw(2).unwrap();
+		let eth1 = InterfaceIndex::from_raw(3).unwrap();
+		let old: Snapshot = [
+			(eth0, state(true, &["10.0.0.1", "fe80::1"])),
+			(eth1, state(true, &["10.0.1.1"])),
+		]
+		.into_iter()
+		.collect();
+		let new: Snapshot = [(eth0, state(true, &["10.0.0.2", "fe80::1"]))]
+			.into_iter()
+			.collect();
+		let mut events = VecDeque::new();
+		diff(&old, &new, &mut events);
+		let addr = |a: &str| a.parse::<IpAddr>().unwrap();
+		assert_eq!(
+			Vec::from(events),
+			[
+				InterfaceEvent::AddressRemoved(eth0, addr("10.0.0.1")),
+				InterfaceEvent::AddressAdded(eth0, addr("10.0.0.2")),
+				InterfaceEvent::AddressRemoved(eth1, addr("10.0.1.1")),
+				InterfaceEvent::Down(eth1),
+			]
+		);
+	}
+}
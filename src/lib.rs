@@ -1,4 +1,4 @@
-#![doc(html_root_url = "https://docs.rs/async-dnssd/0.5.1")]
+#![doc(html_root_url = "https://docs.rs/async-dnssd/0.6.0")]
 #![warn(missing_docs)]
 #![warn(rust_2018_idioms)]
 #![warn(unused_extern_crates, unused_qualifications)]
@@ -9,7 +9,9 @@
 //! * [Browse for available services][`browse`]
 //! * [Create Connection to register records with][`connect`]
 //! * [Enumerate domains that are recommended for registration or browsing][`enumerate_domains`]
+//! * [Enumerate service types advertised in a domain][`enumerate_service_types`]
 //! * [Query for an arbitrary DNS record][`query_record`]
+//! * [Query all records of a service][`query_service_records`]
 //! * [Register a service][`register`]
 //! * [Add a record to a registered service][`Registration::add_record`]
 //! * [Register record][`Connection::register_record`]
@@ -20,6 +22,17 @@
 //! * [Purge record from cache][`reconfirm_record`]
 //! * [Construct full name][`FullName::construct`]
 //! * [Stream timeouts][`TimeoutStream`]
+//! * [Suppress flapping browse results][`BrowseDebounce`]
+//! * [Track the current set of browsed services][`DiscoveryCache`]
+//! * [Merge results seen on multiple interfaces][`InterfaceDedup`]
+//! * [Browse surviving daemon restarts][`browse_reconnecting`]
+//! * [Retry operations failing with transient errors][`RetryPolicy`]
+//! * [Handle names that aren't valid UTF-8][`set_name_decoding`]
+//! * [Monitor record changes][`monitor_record`]
+//! * [Shut down connections, registrations and streams together][`DnssdContext`]
+//! * [Watch network interface changes][`interface_events`]
+//! * [Advertise services from a declarative list][`ServiceRegistry`]
+//! * [Parse and encode record data][`Rdata`]
 //!
 //! ## Porting from dnssd C API
 //!
@@ -31,7 +44,7 @@
 //! | [`DNSServiceCreateConnection`]  | [`connect`]                                                  |
 //! | [`DNSServiceEnumerateDomains`]  | [`enumerate_domains`]                                        |
 //! | [`DNSServiceQueryRecord`]       | [`query_record`]                                             |
-//! | [`DNSServiceReconfirmRecord`]   | [`reconfirm_record`]                                         |
+//! | [`DNSServiceReconfirmRecord`]   | [`reconfirm_record`], [`reconfirm_record_with_flags`]        |
 //! | [`DNSServiceRegister`]          | [`register`]                                                 |
 //! | [`DNSServiceRegisterRecord`]    | [`Connection::register_record`]                              |
 //! | [`DNSServiceResolve`]           | [`resolve`]                                                  |
@@ -68,26 +81,123 @@
 //! [`FullName::construct`]: struct.FullName.html#method.construct
 //! [`connect`]: fn.connect.html
 //! [`enumerate_domains`]: fn.enumerate_domains.html
+//! [`enumerate_service_types`]: fn.enumerate_service_types.html
 //! [`query_record`]: fn.query_record.html
+//! [`query_service_records`]: fn.query_service_records.html
 //! [`reconfirm_record`]: fn.reconfirm_record.html
+//! [`reconfirm_record_with_flags`]: fn.reconfirm_record_with_flags.html
 //! [`register`]: fn.register.html
 //! [`Connection::register_record`]: struct.Connection.html#method.register_record
 //! [`resolve`]: fn.resolve.html
 //! [`Record::update_record`]: struct.Record.html#method.update_record
 //! [`RegisterRecord::update_record`]: struct.RegisterRecord.html#method.update_record
 //! [`TimeoutStream`]: struct.TimeoutStream.html
+//! [`BrowseDebounce`]: struct.BrowseDebounce.html
+//! [`DiscoveryCache`]: struct.DiscoveryCache.html
+//! [`InterfaceDedup`]: struct.InterfaceDedup.html
+//! [`browse_reconnecting`]: fn.browse_reconnecting.html
+//! [`RetryPolicy`]: struct.RetryPolicy.html
+//! [`set_name_decoding`]: fn.set_name_decoding.html
+//! [`monitor_record`]: fn.monitor_record.html
+//! [`DnssdContext`]: struct.DnssdContext.html
+//! [`ServiceRegistry`]: struct.ServiceRegistry.html
+//! [`Rdata`]: enum.Rdata.html
 //! [`TxtRecord`]: struct.TxtRecord.html
 
+use async_dnssd_types::{
+	dns_consts,
+	rdata,
+	txt_record,
+};
+
 pub use self::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
+	config::{
+		default_domain_cache_duration,
+		name_decoding,
+		normalize_daemon_behavior,
+		set_default_domain_cache_duration,
+		set_name_decoding,
+		set_normalize_daemon_behavior,
+		set_shared_connection,
+		shared_connection,
+		NameDecoding,
+	},
+	context::DnssdContext,
+	debounce::{
+		BrowseDebounce,
+		BrowseDebounceExt,
+	},
+	discovery_cache::{
+		CacheEvent,
+		DiscoveryCache,
+		DiscoveryCacheExt,
+	},
 	dns_consts::{
 		Class,
+		ParseMnemonicError,
 		Type,
 	},
-	error::Error,
+	domain_name::DomainName,
+	error::{
+		Error,
+		ErrorCategory,
+		OperationError,
+	},
 	ffi::MAX_DOMAIN_NAME,
+	flags::{
+		display_flags,
+		DisplayFlags,
+	},
+	instance_name::{
+		sanitize_instance_name,
+		InstanceNameError,
+		ServiceInstanceName,
+	},
 	interface::{
 		Interface,
 		InterfaceIndex,
+		InterfaceMetadata,
+		InterfacePolicy,
+	},
+	interface_dedup::{
+		InterfaceDedup,
+		InterfaceDedupExt,
+		InterfaceDedupItem,
+	},
+	interface_events::{
+		interface_events,
+		InterfaceEvent,
+		InterfaceEvents,
+	},
+	rdata::{
+		Hinfo,
+		Nsec,
+		Rdata,
+		RdataError,
+		Srv,
+	},
+	reconnecting_browse::{
+		browse_reconnecting,
+		browse_reconnecting_with_clock,
+		ReconnectData,
+		ReconnectingBrowse,
+	},
+	recv::Recv,
+	registry::{
+		RegistryEvent,
+		ServiceRegistry,
+		ServiceSpec,
+		ServiceStatus,
+	},
+	retry::{
+		register_with_retry,
+		register_with_retry_with_clock,
+		RegisterWithRetry,
+		RetryPolicy,
 	},
 	service::*,
 	timeout_stream::{
@@ -95,29 +205,80 @@ pub use self::{
 		TimeoutStream,
 	},
 	txt_record::{
+		MergePolicy,
+		TxtChange,
+		TxtDiff,
 		TxtRecord,
 		TxtRecordError,
 		TxtRecordIter,
+		TxtRecordRef,
+		TxtRecordStrings,
 	},
 };
 
+#[cfg(windows)]
+pub use self::config::{
+	init_options,
+	set_init_options,
+	InitOptions,
+};
+
+mod clock;
+mod compat;
+mod config;
+mod context;
 mod cstr;
-mod dns_consts;
+mod deadline;
+mod debounce;
+mod discovery_cache;
+mod dns_name;
+mod domain_name;
 mod error;
 mod evented;
 mod ffi;
+mod flags;
 mod fused_err_stream;
 mod future;
 mod inner;
+mod instance_name;
 mod interface;
+mod interface_dedup;
+mod interface_events;
+mod limit;
 mod non_exhaustive_struct;
 mod notify;
+mod quiescence;
+mod reconnecting_browse;
+mod recv;
+mod registry;
+mod retry;
 mod service;
 mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod timeout_stream;
-mod txt_record;
 
 fn init() {
+	#[cfg(windows)]
+	{
+		use std::sync::Once;
+
+		static INIT: Once = Once::new();
+		INIT.call_once(|| {
+			let options = init_options();
+			let result = inner::initialize(options.bits()).and_then(|()| {
+				if options.contains(InitOptions::NO_SERVER_CHECK) {
+					Ok(())
+				} else {
+					inner::check_version()
+				}
+			});
+			if let Err(e) = result {
+				log::warn!("DNS-SD client library unusable: {}", e);
+			}
+		});
+	}
+
 	#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
 	{
 		use std::sync::Once;
@@ -0,0 +1,90 @@
+use futures_core::{
+	stream::FusedStream,
+	Stream,
+};
+use futures_util::StreamExt;
+use std::{
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+/// Ends a stream after a number of (counted) items
+///
+/// The wrapped stream (i.e. the underlying `DNSServiceRef`) gets dropped
+/// right away when the last item is delivered.
+pub(crate) struct Limit<S: Stream> {
+	stream: Option<S>,
+	remaining: Option<usize>,
+	// which items count towards the limit
+	counts: fn(&S::Item) -> bool,
+}
+
+impl<S: Stream> Limit<S> {
+	pub(crate) fn new(stream: S, max: Option<usize>, counts: fn(&S::Item) -> bool) -> Self {
+		Self {
+			// nothing to wait for
+			stream: if max == Some(0) { None } else { Some(stream) },
+			remaining: max,
+			counts,
+		}
+	}
+}
+
+impl<S: Stream + Unpin> Stream for Limit<S> {
+	type Item = S::Item;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		let stream = match &mut this.stream {
+			Some(stream) => stream,
+			None => return Poll::Ready(None),
+		};
+		let item = futures_core::ready!(stream.poll_next_unpin(cx));
+		match &item {
+			None => this.stream = None,
+			Some(item) if (this.counts)(item) => {
+				if let Some(remaining) = &mut this.remaining {
+					*remaining -= 1;
+					if *remaining == 0 {
+						this.stream = None;
+					}
+				}
+			},
+			Some(_) => (),
+		}
+		Poll::Ready(item)
+	}
+}
+
+impl<S: FusedStream + Unpin> FusedStream for Limit<S> {
+	fn is_terminated(&self) -> bool {
+		self.stream.as_ref().is_none_or(S::is_terminated)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io;
+
+	#[tokio::test]
+	async fn drops_stream() {
+		let (tx, rx) = futures_channel::mpsc::unbounded::<io::Result<u32>>();
+		let mut stream = Limit::new(rx, Some(2), |item| item.is_ok());
+
+		tx.unbounded_send(Ok(1)).unwrap();
+		tx.unbounded_send(Err(io::Error::other("not counted")))
+			.unwrap();
+		tx.unbounded_send(Ok(2)).unwrap();
+		tx.unbounded_send(Ok(3)).unwrap();
+		assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+		assert!(stream.next().await.unwrap().is_err());
+		assert_eq!(stream.next().await.unwrap().unwrap(), 2);
+		assert!(stream.next().await.is_none());
+		// receiver got dropped
+		assert!(tx.is_closed());
+	}
+}
@@ -11,7 +11,7 @@ use std::{
 	},
 };
 
-type NotifiedBox<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+type NotifiedBox<'a> = Pin<Box<dyn Future<Output = ()> + Send + Sync + 'a>>;
 
 pub struct Notify {
 	notify: Arc<tokio::sync::Notify>,
@@ -0,0 +1,124 @@
+use std::{
+	future::Future,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+
+use crate::clock::Clock;
+
+/// Collects results until they stop coming in
+///
+/// Done once no new results arrived for `quiet_period` (not counting
+/// the time `MORE_COMING` is set), or after `max_wait`; both timers
+/// start with the first poll.
+pub(crate) struct Quiescence<T, C: Clock> {
+	items: Vec<T>,
+	clock: C,
+	quiet_period: Duration,
+	max_wait: Duration,
+	// not armed while `MORE_COMING` is set
+	quiet_timer: Option<Pin<Box<C::Sleep>>>,
+	// started on first poll
+	max_timer: Option<Pin<Box<C::Sleep>>>,
+}
+
+impl<T, C: Clock> Quiescence<T, C> {
+	pub(crate) fn new(quiet_period: Duration, max_wait: Duration, clock: C) -> Self {
+		Self {
+			items: Vec::new(),
+			clock,
+			quiet_period,
+			max_wait,
+			quiet_timer: None,
+			max_timer: None,
+		}
+	}
+
+	fn sleep(&self, duration: Duration) -> Pin<Box<C::Sleep>> {
+		Box::pin(self.clock.sleep_until(self.clock.now() + duration))
+	}
+
+	fn start(&mut self) {
+		if self.max_timer.is_none() {
+			self.max_timer = Some(self.sleep(self.max_wait));
+			self.quiet_timer = Some(self.sleep(self.quiet_period));
+		}
+	}
+
+	/// Restart the quiet period after a result (even if it didn't
+	/// change the collected items)
+	pub(crate) fn seen(&mut self, more_coming: bool) {
+		self.start();
+		self.quiet_timer = if more_coming {
+			None
+		} else {
+			Some(self.sleep(self.quiet_period))
+		};
+	}
+
+	/// Replace the first item `same` as `item`, or append it if there
+	/// is none
+	pub(crate) fn add(&mut self, item: T, same: impl Fn(&T, &T) -> bool) {
+		match self.items.iter().position(|i| same(i, &item)) {
+			Some(pos) => self.items[pos] = item,
+			None => self.items.push(item),
+		}
+	}
+
+	/// Remove the first item `same` as `item`
+	pub(crate) fn remove(&mut self, item: &T, same: impl Fn(&T, &T) -> bool) {
+		if let Some(pos) = self.items.iter().position(|i| same(i, item)) {
+			self.items.remove(pos);
+		}
+	}
+
+	/// Ready once the results stopped coming in
+	pub(crate) fn poll_done(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+		self.start();
+		if let Some(timer) = &mut self.max_timer {
+			if timer.as_mut().poll(cx).is_ready() {
+				return Poll::Ready(());
+			}
+		}
+		if let Some(timer) = &mut self.quiet_timer {
+			return timer.as_mut().poll(cx);
+		}
+		Poll::Pending
+	}
+
+	/// Collected items; also stops the timers
+	pub(crate) fn finish(&mut self) -> Vec<T> {
+		self.quiet_timer = None;
+		self.max_timer = None;
+		std::mem::take(&mut self.items)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Quiescence;
+	use crate::clock::TokioClock;
+	use std::{
+		future::poll_fn,
+		time::Duration,
+	};
+
+	#[tokio::test(start_paused = true)]
+	async fn max_wait_caps_more_coming() {
+		let mut collector =
+			Quiescence::new(Duration::from_secs(1), Duration::from_secs(10), TokioClock);
+		let start = tokio::time::Instant::now();
+		collector.add(1, |a, b| a == b);
+		collector.add(2, |a, b| a == b);
+		collector.remove(&1, |a, b| a == b);
+		// `MORE_COMING` never cleared: only the cap ends collecting
+		collector.seen(true);
+		poll_fn(|cx| collector.poll_done(cx)).await;
+		assert_eq!(start.elapsed(), Duration::from_secs(10));
+		assert_eq!(collector.finish(), vec![2]);
+	}
+}
@@ -0,0 +1,490 @@
+use crate::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
+	error::is_daemon_gone,
+	interface::Interface,
+	service::{
+		browse_extended,
+		Browse,
+		BrowseData,
+		BrowseFlags,
+		BrowseResult,
+		BrowsedFlags,
+		ServiceKey,
+	},
+};
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+	collections::{
+		HashSet,
+		VecDeque,
+	},
+	future::Future,
+	io,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+
+/// Optional data when browsing with
+/// [`browse_reconnecting`](fn.browse_reconnecting.html); either use its
+/// default value or customize it like:
+///
+/// ```
+/// # use async_dnssd::ReconnectData;
+/// # use std::time::Duration;
+/// ReconnectData {
+///     settle: Duration::from_secs(10),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ReconnectData {
+	/// time to wait before trying to restart the browse after the
+	/// connection to the daemon broke (default: 1 second)
+	pub retry_interval: Duration,
+	/// time to wait for services to show up again after the browse
+	/// was restarted; services that weren't found again by then are
+	/// reported as removed (default: 3 seconds)
+	pub settle: Duration,
+	#[doc(hidden)]
+	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+}
+
+impl Default for ReconnectData {
+	fn default() -> Self {
+		Self {
+			retry_interval: Duration::from_secs(1),
+			settle: Duration::from_secs(3),
+			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+		}
+	}
+}
+
+struct BrowseArgs {
+	reg_type: String,
+	flags: BrowseFlags,
+	interface: Interface,
+	domain: Option<String>,
+}
+
+impl BrowseArgs {
+	// `timeout` and `max_results` are applied across restarts instead
+	fn start(&self) -> Browse {
+		browse_extended(
+			&self.reg_type,
+			BrowseData {
+				flags: self.flags,
+				interface: self.interface,
+				domain: self.domain.as_deref(),
+				..Default::default()
+			},
+		)
+	}
+}
+
+type BrowseStream = Pin<Box<dyn Stream<Item = io::Result<BrowseResult>> + Send>>;
+
+// (re)starts the underlying browse; tests use scenario streams
+type StartBrowse = Box<dyn FnMut() -> BrowseStream + Send>;
+
+struct Resync<T> {
+	// services found again since the restart
+	seen: HashSet<ServiceKey>,
+	// only running while a browse is active
+	timer: Option<Pin<Box<T>>>,
+}
+
+/// Browse that survives restarts of the daemon
+///
+/// Created by [`browse_reconnecting`](fn.browse_reconnecting.html).
+#[must_use = "streams do nothing unless polled"]
+pub struct ReconnectingBrowse<C: Clock = TokioClock> {
+	reg_type: String,
+	start: StartBrowse,
+	reconnect: ReconnectData,
+	clock: C,
+	browse: Option<BrowseStream>,
+	retry_timer: Pin<Box<C::Sleep>>,
+	// services reported as added
+	known: HashSet<ServiceKey>,
+	resync: Option<Resync<C::Sleep>>,
+	// synthetic removals
+	queue: VecDeque<BrowseResult>,
+	// `BrowseData::timeout` (since creation)
+	deadline: Option<Pin<Box<C::Sleep>>>,
+	// `BrowseData::max_results` minus the reported adds
+	remaining_results: Option<usize>,
+	ended: bool,
+}
+
+impl<C: Clock> ReconnectingBrowse<C> {
+	fn new(
+		reg_type: &str,
+		mut start: StartBrowse,
+		data: &BrowseData<'_>,
+		reconnect: ReconnectData,
+		clock: C,
+	) -> Self {
+		let ended = data.max_results == Some(0);
+		// nothing to wait for
+		let browse = if ended { None } else { Some(start()) };
+		Self {
+			reg_type: reg_type.to_string(),
+			start,
+			reconnect,
+			retry_timer: Box::pin(clock.sleep_until(clock.now())),
+			deadline: data
+				.timeout
+				.map(|timeout| Box::pin(clock.sleep_until(clock.now() + timeout))),
+			clock,
+			browse,
+			known: HashSet::new(),
+			resync: None,
+			queue: VecDeque::new(),
+			remaining_results: data.max_results,
+			ended,
+		}
+	}
+
+	// stop the operation; the stream ends
+	fn end(&mut self) {
+		self.ended = true;
+		self.browse = None;
+		self.resync = None;
+		self.queue.clear();
+		self.deadline = None;
+	}
+
+	fn count_result(&mut self, result: &BrowseResult) {
+		if !result.flags.contains(BrowsedFlags::ADD) {
+			return;
+		}
+		if let Some(remaining) = &mut self.remaining_results {
+			*remaining -= 1;
+			if *remaining == 0 {
+				self.end();
+			}
+		}
+	}
+
+	fn connection_lost(&mut self) {
+		self.browse = None;
+		let retry = self.clock.now() + self.reconnect.retry_interval;
+		self.retry_timer = Box::pin(self.clock.sleep_until(retry));
+		match &mut self.resync {
+			Some(resync) => {
+				// restart failed; start settle timer again after next restart
+				resync.timer = None;
+			},
+			None => {
+				self.resync = Some(Resync {
+					seen: HashSet::new(),
+					timer: None,
+				});
+			},
+		}
+	}
+
+	fn handle_result(&mut self, result: BrowseResult) -> Option<BrowseResult> {
+		let key = result.service_key();
+		if result.flags.contains(BrowsedFlags::ADD) {
+			if let Some(resync) = &mut self.resync {
+				resync.seen.insert(key.clone());
+			}
+			if self.known.insert(key) {
+				Some(result)
+			} else {
+				// service came back after restart
+				None
+			}
+		} else {
+			if let Some(resync) = &mut self.resync {
+				resync.seen.remove(&key);
+			}
+			if self.known.remove(&key) {
+				Some(result)
+			} else {
+				None
+			}
+		}
+	}
+
+	fn finish_resync(&mut self) {
+		let resync = self.resync.take().expect("resync active");
+		let gone: Vec<ServiceKey> = self.known.difference(&resync.seen).cloned().collect();
+		for key in gone {
+			self.known.remove(&key);
+			self.queue.push_back(BrowseResult {
+				flags: BrowsedFlags::empty(),
+				interface: key.interface,
+				service_name: key.service_name,
+				reg_type: key.reg_type,
+				domain: key.domain,
+			});
+		}
+	}
+}
+
+impl<C: Clock + Unpin> Stream for ReconnectingBrowse<C> {
+	type Item = io::Result<BrowseResult>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		if this.ended {
+			return Poll::Ready(None);
+		}
+		if let Some(deadline) = &mut this.deadline {
+			if deadline.as_mut().poll(cx).is_ready() {
+				this.end();
+				return Poll::Ready(None);
+			}
+		}
+		loop {
+			if let Some(removed) = this.queue.pop_front() {
+				return Poll::Ready(Some(Ok(removed)));
+			}
+
+			let browse = match &mut this.browse {
+				Some(browse) => browse,
+				None => {
+					futures_core::ready!(this.retry_timer.as_mut().poll(cx));
+					if let Some(resync) = &mut this.resync {
+						let settle = this.clock.now() + this.reconnect.settle;
+						resync.timer = Some(Box::pin(this.clock.sleep_until(settle)));
+					}
+					this.browse.insert((this.start)())
+				},
+			};
+
+			match browse.poll_next_unpin(cx) {
+				Poll::Ready(Some(Ok(result))) => {
+					if let Some(result) = this.handle_result(result) {
+						this.count_result(&result);
+						return Poll::Ready(Some(Ok(result)));
+					}
+				},
+				Poll::Ready(Some(Err(e))) if is_daemon_gone(&e) => {
+					log::debug!("browse for {} lost daemon: {}", this.reg_type, e);
+					this.connection_lost();
+				},
+				Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+				Poll::Ready(None) => return Poll::Ready(None),
+				Poll::Pending => {
+					let resync_timer = this.resync.as_mut().and_then(|r| r.timer.as_mut());
+					match resync_timer {
+						Some(timer) => {
+							futures_core::ready!(timer.as_mut().poll(cx));
+							this.finish_resync();
+						},
+						None => return Poll::Pending,
+					}
+				},
+			}
+		}
+	}
+}
+
+/// Browse for available services, restarting the browse when the
+/// connection to the daemon breaks
+///
+/// After a restart (e.g. the daemon got restarted) services that show up
+/// again aren't reported a second time; services that don't come back
+/// within [`ReconnectData::settle`] are reported as removed.
+///
+/// Only `ServiceNotRunning` and `DefunctConnection` errors trigger a
+/// restart; other errors are forwarded.
+///
+/// [`BrowseData::timeout`] and [`BrowseData::max_results`] apply to the
+/// whole stream across restarts: the timeout counts from the call, and
+/// services showing up again after a restart don't count as results
+/// (as they aren't reported again).
+///
+/// [`ReconnectData::settle`]: struct.ReconnectData.html#structfield.settle
+/// [`BrowseData::timeout`]: struct.BrowseData.html#structfield.timeout
+/// [`BrowseData::max_results`]: struct.BrowseData.html#structfield.max_results
+pub fn browse_reconnecting(
+	reg_type: &str,
+	data: BrowseData<'_>,
+	reconnect: ReconnectData,
+) -> ReconnectingBrowse {
+	browse_reconnecting_with_clock(reg_type, data, reconnect, TokioClock)
+}
+
+/// Browse for available services, restarting the browse when the
+/// connection to the daemon breaks
+///
+/// Same as [`browse_reconnecting`](fn.browse_reconnecting.html), but uses
+/// the given [`Clock`](trait.Clock.html) for the timers.
+pub fn browse_reconnecting_with_clock<C: Clock>(
+	reg_type: &str,
+	data: BrowseData<'_>,
+	reconnect: ReconnectData,
+	clock: C,
+) -> ReconnectingBrowse<C> {
+	let args = BrowseArgs {
+		reg_type: reg_type.to_string(),
+		flags: data.flags,
+		interface: data.interface,
+		domain: data.domain.map(str::to_string),
+	};
+	let start: StartBrowse = Box::new(move || Box::pin(args.start()));
+	ReconnectingBrowse::new(reg_type, start, &data, reconnect, clock)
+}
+
+// the fake services come from the `testing` feature
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+	use super::*;
+	use crate::testing::{
+		FakeService,
+		Scenario,
+	};
+	use std::time::Duration;
+
+	const REG_TYPE: &str = "_ipp._tcp.";
+
+	fn printer(name: &str) -> FakeService {
+		FakeService::new(name, REG_TYPE, "printer.local.", 631)
+	}
+
+	fn browse(scenario: Scenario, data: BrowseData<'_>) -> ReconnectingBrowse {
+		let start: StartBrowse = Box::new(move || Box::pin(scenario.browse(REG_TYPE)));
+		ReconnectingBrowse::new(REG_TYPE, start, &data, ReconnectData::default(), TokioClock)
+	}
+
+	// browse restarts with the next session
+	fn restarting(sessions: Vec<Scenario>) -> ReconnectingBrowse {
+		let mut sessions = sessions.into_iter();
+		let start: StartBrowse = Box::new(move || {
+			Box::pin(
+				sessions
+					.next()
+					.expect("unexpected restart")
+					.browse(REG_TYPE),
+			)
+		});
+		ReconnectingBrowse::new(
+			REG_TYPE,
+			start,
+			&BrowseData::default(),
+			ReconnectData::default(),
+			TokioClock,
+		)
+	}
+
+	// "+name" / "-name" with the (virtual) time in seconds
+	async fn events(browse: ReconnectingBrowse) -> Vec<(u64, String)> {
+		let start = tokio::time::Instant::now();
+		browse
+			.map(|result| {
+				let result = result.unwrap();
+				let sign = if result.flags.contains(BrowsedFlags::ADD) {
+					'+'
+				} else {
+					'-'
+				};
+				let event = format!("{}{}", sign, result.service_name);
+				(start.elapsed().as_secs(), event)
+			})
+			.collect()
+			.await
+	}
+
+	fn expected(events: &[(u64, &str)]) -> Vec<(u64, String)> {
+		events
+			.iter()
+			.map(|&(at, event)| (at, event.to_string()))
+			.collect()
+	}
+
+	async fn names(browse: ReconnectingBrowse) -> Vec<String> {
+		browse
+			.map(|result| result.unwrap().service_name)
+			.collect()
+			.await
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn timeout() {
+		let scenario = Scenario::new()
+			.service(printer("a"))
+			.add_at(Duration::from_secs(2), printer("b"))
+			.add_at(Duration::from_secs(10), printer("c"));
+		let start = tokio::time::Instant::now();
+		let browse = browse(
+			scenario,
+			BrowseData {
+				timeout: Some(Duration::from_secs(5)),
+				..Default::default()
+			},
+		);
+		assert_eq!(names(browse).await, ["a", "b"]);
+		assert_eq!(start.elapsed(), Duration::from_secs(5));
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn max_results() {
+		let scenario = Scenario::new()
+			.service(printer("a"))
+			.remove_at(Duration::from_secs(1), "a", REG_TYPE)
+			.add_at(Duration::from_secs(2), printer("b"))
+			.add_at(Duration::from_secs(3), printer("c"));
+		let browse = browse(
+			scenario,
+			BrowseData {
+				max_results: Some(2),
+				..Default::default()
+			},
+		);
+		// removals don't count
+		assert_eq!(names(browse).await, ["a", "a", "b"]);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn restart_suppresses_duplicates() {
+		let sessions = vec![
+			Scenario::new()
+				.service(printer("a"))
+				.service(printer("b"))
+				.daemon_gone_at(Duration::from_secs(5)),
+			// restarted after the retry interval (at 6s); "b" is gone
+			Scenario::new()
+				.service(printer("a"))
+				.add_at(Duration::from_secs(1), printer("c"))
+				.remove_at(Duration::from_secs(10), "c", REG_TYPE),
+		];
+		assert_eq!(
+			events(restarting(sessions)).await,
+			expected(&[(0, "+a"), (0, "+b"), (7, "+c"), (9, "-b"), (16, "-c")]),
+		);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn retry_backoff() {
+		let sessions = vec![
+			Scenario::new()
+				.service(printer("a"))
+				.daemon_gone_at(Duration::from_secs(2)),
+			// restarts at 3s and 4s fail right away
+			Scenario::new().daemon_gone_at(Duration::ZERO),
+			Scenario::new().daemon_gone_at(Duration::ZERO),
+			// restarted at 5s; settle time counts from here, so "a"
+			// coming back at 7s isn't reported as removed
+			Scenario::new()
+				.service(printer("b"))
+				.add_at(Duration::from_secs(2), printer("a"))
+				.remove_at(Duration::from_secs(5), "b", REG_TYPE),
+		];
+		assert_eq!(
+			events(restarting(sessions)).await,
+			expected(&[(0, "+a"), (5, "+b"), (10, "-b")]),
+		);
+	}
+}
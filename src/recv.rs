@@ -0,0 +1,38 @@
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+	future::Future,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+/// Pending next item of a stream
+///
+/// Created by `recv` methods like [`Browse::recv`]; completes with
+/// `None` once the stream ended.
+///
+/// Cancel safe: dropping it before it completes doesn't lose an item;
+/// the next `recv` call (or polling the stream) delivers it.
+///
+/// [`Browse::recv`]: struct.Browse.html#method.recv
+#[must_use = "futures do nothing unless polled"]
+pub struct Recv<'a, S> {
+	stream: &'a mut S,
+}
+
+impl<'a, S> Recv<'a, S> {
+	pub(crate) fn new(stream: &'a mut S) -> Self {
+		Self { stream }
+	}
+}
+
+impl<S: Stream + Unpin> Future for Recv<'_, S> {
+	type Output = Option<S::Item>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
@@ -0,0 +1,613 @@
+use futures_core::Stream;
+use futures_util::FutureExt;
+use std::{
+	collections::BTreeMap,
+	future::Future,
+	io,
+	pin::Pin,
+	sync::Arc,
+	task::{
+		Context,
+		Poll,
+		Waker,
+	},
+	time::Duration,
+};
+
+use crate::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
+	interface::Interface,
+	service::{
+		register_extended,
+		Register,
+		RegisterData,
+		RegisterResult,
+		Registration,
+		ServiceKey,
+	},
+	txt_record::TxtRecord,
+};
+
+/// Declarative description of a service to advertise
+///
+/// Services are identified by `interface`, `name`, `reg_type` and
+/// `domain` (see [`ServiceSpec::key`](#method.key)).
+#[derive(Clone)]
+pub struct ServiceSpec {
+	/// service name
+	pub name: String,
+	/// service type and protocol (for example "_http._tcp")
+	pub reg_type: String,
+	/// port (in native byte order)
+	pub port: u16,
+	/// TXT record (can be updated without registering the service again)
+	pub txt: TxtRecord,
+	/// interface to register service on
+	pub interface: Interface,
+	/// domain on which to advertise the service
+	pub domain: Option<String>,
+	/// the SRV target host name, defaults to local hostname(s)
+	pub host: Option<String>,
+	#[doc(hidden)]
+	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+}
+
+impl ServiceSpec {
+	/// New service with empty TXT record on the default interface and
+	/// domain
+	pub fn new(name: &str, reg_type: &str, port: u16) -> Self {
+		Self {
+			name: name.to_string(),
+			reg_type: reg_type.to_string(),
+			port,
+			txt: TxtRecord::new(),
+			interface: Interface::default(),
+			domain: None,
+			host: None,
+			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+		}
+	}
+
+	/// Key identifying the service
+	///
+	/// The `domain` of the key is empty for the default domain.
+	pub fn key(&self) -> ServiceKey {
+		ServiceKey {
+			interface: self.interface,
+			service_name: self.name.clone(),
+			reg_type: self.reg_type.clone(),
+			domain: self.domain.clone().unwrap_or_default(),
+		}
+	}
+
+	// whether a registration for `self` can be reused for `other`
+	// (possibly by updating the TXT record)
+	fn same_registration(&self, other: &Self) -> bool {
+		self.name == other.name
+			&& self.reg_type == other.reg_type
+			&& self.port == other.port
+			&& self.interface == other.interface
+			&& self.domain == other.domain
+			&& self.host == other.host
+	}
+
+	fn register(&self) -> io::Result<Register> {
+		register_extended(
+			&self.reg_type,
+			self.port,
+			RegisterData {
+				interface: self.interface,
+				name: Some(&self.name),
+				domain: self.domain.as_deref(),
+				host: self.host.as_deref(),
+				txt: self.txt.rdata(),
+				..Default::default()
+			},
+		)
+	}
+}
+
+/// Status of a service in a [`ServiceRegistry`](struct.ServiceRegistry.html)
+#[derive(Debug)]
+pub enum ServiceStatus<'a> {
+	/// Waiting for the daemon to confirm the registration
+	Pending,
+	/// Service is registered
+	Registered(&'a RegisterResult),
+	/// Registration failed and won't be retried (e.g. name conflict)
+	Failed(&'a io::Error),
+	/// Lost the connection to the daemon; registering again soon
+	Recovering,
+}
+
+/// Event of a [`ServiceRegistry`](struct.ServiceRegistry.html) stream
+#[derive(Debug)]
+pub enum RegistryEvent {
+	/// Service got registered (also after recovering)
+	Registered(ServiceKey, RegisterResult),
+	/// Registration failed and won't be retried (e.g. name conflict)
+	///
+	/// The error is shared with [`ServiceStatus::Failed`].
+	///
+	/// [`ServiceStatus::Failed`]: enum.ServiceStatus.html#variant.Failed
+	Failed(ServiceKey, Arc<io::Error>),
+	/// Lost the connection to the daemon (e.g. it got restarted); the
+	/// service gets registered again
+	Lost(ServiceKey, io::Error),
+}
+
+// starts a registration; replaced in tests
+type RegisterFn = fn(&ServiceSpec) -> io::Result<Register>;
+
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(32);
+
+enum State<C: Clock> {
+	Pending(Register),
+	Registered(Registration, RegisterResult),
+	Failed(Arc<io::Error>),
+	Retry(Pin<Box<C::Sleep>>),
+}
+
+struct Entry<C: Clock> {
+	spec: ServiceSpec,
+	register: RegisterFn,
+	state: State<C>,
+	// delay before the next attempt to register again
+	retry_delay: Duration,
+}
+
+impl<C: Clock> Entry<C> {
+	fn new(spec: ServiceSpec, register: RegisterFn) -> io::Result<Self> {
+		let state = State::Pending(register(&spec)?);
+		Ok(Self {
+			spec,
+			register,
+			state,
+			retry_delay: MIN_RETRY_DELAY,
+		})
+	}
+
+	fn retry(&mut self, clock: &C) {
+		self.state = State::Retry(Box::pin(clock.sleep_until(clock.now() + self.retry_delay)));
+		self.retry_delay = std::cmp::min(self.retry_delay * 2, MAX_RETRY_DELAY);
+	}
+
+	fn poll(&mut self, key: &ServiceKey, clock: &C, cx: &mut Context<'_>) -> Poll<RegistryEvent> {
+		loop {
+			match &mut self.state {
+				State::Pending(register) => match futures_core::ready!(register.poll_unpin(cx)) {
+					Ok((registration, result)) => {
+						self.retry_delay = MIN_RETRY_DELAY;
+						self.state = State::Registered(registration, result.clone());
+						return Poll::Ready(RegistryEvent::Registered(key.clone(), result));
+					},
+					Err(e) if crate::error::is_daemon_gone(&e) => {
+						self.retry(clock);
+						return Poll::Ready(RegistryEvent::Lost(key.clone(), e));
+					},
+					Err(e) => {
+						let e = Arc::new(e);
+						self.state = State::Failed(e.clone());
+						return Poll::Ready(RegistryEvent::Failed(key.clone(), e));
+					},
+				},
+				State::Registered(registration, _) => {
+					let e = futures_core::ready!(registration.poll_error(cx));
+					self.retry(clock);
+					return Poll::Ready(RegistryEvent::Lost(key.clone(), e));
+				},
+				State::Failed(_) => return Poll::Pending,
+				State::Retry(sleep) => {
+					futures_core::ready!(sleep.as_mut().poll(cx));
+					match (self.register)(&self.spec) {
+						Ok(register) => self.state = State::Pending(register),
+						Err(_) => self.retry(clock), // daemon probably still down
+					}
+				},
+			}
+		}
+	}
+
+	fn status(&self) -> ServiceStatus<'_> {
+		match &self.state {
+			State::Pending(_) => ServiceStatus::Pending,
+			State::Registered(_, result) => ServiceStatus::Registered(result),
+			State::Failed(e) => ServiceStatus::Failed(e),
+			State::Retry(_) => ServiceStatus::Recovering,
+		}
+	}
+
+	fn update_txt(&mut self, txt: &TxtRecord) -> io::Result<()> {
+		let record = match &self.state {
+			State::Pending(register) => Some(register.get_default_txt_record()),
+			State::Registered(registration, _) => Some(registration.get_default_txt_record()),
+			State::Failed(e) => {
+				return Err(io::Error::new(
+					e.kind(),
+					format!("registration failed: {}", e),
+				));
+			},
+			// next registration uses the new TXT record
+			State::Retry(_) => None,
+		};
+		if let Some(record) = record {
+			record.update_record(txt.rdata(), 0)?;
+		}
+		self.spec.txt = txt.clone();
+		Ok(())
+	}
+}
+
+/// Set of service registrations keyed by [`ServiceKey`]
+///
+/// Services can be added, updated and removed at runtime, or managed
+/// through a declarative list with [`reconcile`].
+///
+/// The registrations are processed in the background (on the tokio
+/// runtime used to create them); dropping the registry removes all
+/// services.  Polling the registry as a `Stream` reports status changes
+/// and registers services again after the connection to the daemon
+/// broke (e.g. because it got restarted); the stream never ends.  The
+/// delay before registering again is timed by a [`Clock`] (see
+/// [`with_clock`]).
+///
+/// [`ServiceKey`]: struct.ServiceKey.html
+/// [`reconcile`]: #method.reconcile
+/// [`Clock`]: trait.Clock.html
+/// [`with_clock`]: #method.with_clock
+#[must_use = "streams do nothing unless polled"]
+pub struct ServiceRegistry<C: Clock = TokioClock> {
+	services: BTreeMap<ServiceKey, Entry<C>>,
+	register: RegisterFn,
+	clock: C,
+	waker: Option<Waker>,
+}
+
+impl Default for ServiceRegistry {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ServiceRegistry {
+	/// Create empty registry
+	pub fn new() -> Self {
+		Self::with_clock(TokioClock)
+	}
+}
+
+impl<C: Clock> ServiceRegistry<C> {
+	/// Create empty registry using the given [`Clock`](trait.Clock.html)
+	pub fn with_clock(clock: C) -> Self {
+		Self::with_register(ServiceSpec::register, clock)
+	}
+
+	fn with_register(register: RegisterFn, clock: C) -> Self {
+		Self {
+			services: BTreeMap::new(),
+			register,
+			clock,
+			waker: None,
+		}
+	}
+
+	/// Number of managed services
+	pub fn len(&self) -> usize {
+		self.services.len()
+	}
+
+	/// Whether no services are managed
+	pub fn is_empty(&self) -> bool {
+		self.services.is_empty()
+	}
+
+	/// Specs of all managed services
+	pub fn specs(&self) -> impl Iterator<Item = &ServiceSpec> {
+		self.services.values().map(|entry| &entry.spec)
+	}
+
+	/// Status of a service
+	///
+	/// Changes are only picked up while polling the registry as
+	/// `Stream`, i.e. this is the status reported by the last event of
+	/// the service.
+	pub fn status(&self, key: &ServiceKey) -> Option<ServiceStatus<'_>> {
+		Some(self.services.get(key)?.status())
+	}
+
+	fn wake(&mut self) {
+		if let Some(waker) = self.waker.take() {
+			waker.wake();
+		}
+	}
+
+	// register or update service; removes it on errors
+	fn apply(&mut self, spec: &ServiceSpec) -> io::Result<()> {
+		let key = spec.key();
+		let entry = match self.services.remove(&key) {
+			Some(mut entry)
+				if entry.spec.same_registration(spec)
+					&& !matches!(entry.state, State::Failed(_)) =>
+			{
+				if entry.spec.txt.rdata() == spec.txt.rdata() {
+					Ok(entry)
+				} else {
+					entry.update_txt(&spec.txt).map(|()| entry)
+				}
+			},
+			Some(entry) => {
+				// deregister first; the new registration might conflict
+				drop(entry);
+				Entry::new(spec.clone(), self.register)
+			},
+			None => Entry::new(spec.clone(), self.register),
+		}?;
+		self.services.insert(key, entry);
+		self.wake();
+		Ok(())
+	}
+
+	/// Register a new service
+	///
+	/// Fails with `AlreadyExists` if a service with the same key is
+	/// already managed.
+	pub fn add(&mut self, spec: ServiceSpec) -> io::Result<ServiceKey> {
+		let key = spec.key();
+		if self.services.contains_key(&key) {
+			return Err(io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				"service already registered",
+			));
+		}
+		self.apply(&spec)?;
+		Ok(key)
+	}
+
+	/// Change an existing service
+	///
+	/// Only updates the TXT record if nothing else changed (and the
+	/// service didn't fail); otherwise the service is registered again.
+	/// Fails with `NotFound` if no service with the key of `spec` is
+	/// managed.  On other errors the service is removed.
+	pub fn update(&mut self, spec: ServiceSpec) -> io::Result<()> {
+		if !self.services.contains_key(&spec.key()) {
+			return Err(io::Error::new(
+				io::ErrorKind::NotFound,
+				"service not registered",
+			));
+		}
+		self.apply(&spec)
+	}
+
+	/// Remove (deregister) a service; returns whether it was managed
+	pub fn remove(&mut self, key: &ServiceKey) -> bool {
+		self.services.remove(key).is_some()
+	}
+
+	/// Make registered services match `desired`
+	///
+	/// - services not in `desired` are removed,
+	/// - new services are registered,
+	/// - services with a changed TXT record get their TXT record updated,
+	/// - services with other changes (or failed services) are registered
+	///   again.
+	///
+	/// For duplicate keys in `desired` the last one wins.  All changes
+	/// are attempted; the first error is returned (failed services are
+	/// not managed afterwards).
+	pub fn reconcile(&mut self, desired: &[ServiceSpec]) -> io::Result<()> {
+		let mut wanted: BTreeMap<ServiceKey, &ServiceSpec> = BTreeMap::new();
+		for spec in desired {
+			wanted.insert(spec.key(), spec);
+		}
+		self.services.retain(|key, _| wanted.contains_key(key));
+
+		let mut result = Ok(());
+		for spec in wanted.into_values() {
+			if let Err(e) = self.apply(spec) {
+				if result.is_ok() {
+					result = Err(e);
+				}
+			}
+		}
+		result
+	}
+}
+
+impl<C: Clock + Unpin> Stream for ServiceRegistry<C> {
+	type Item = RegistryEvent;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		this.waker = Some(cx.waker().clone());
+		for (key, entry) in &mut this.services {
+			if let Poll::Ready(event) = entry.poll(key, &this.clock, cx) {
+				return Poll::Ready(Some(event));
+			}
+		}
+		Poll::Pending
+	}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use crate::{
+		error::Error,
+		ffi,
+		RegisteredFlags,
+		RegistrationState,
+	};
+	use futures_util::StreamExt;
+	use std::cell::RefCell;
+	use tokio::sync::watch;
+
+	thread_local! {
+		// states of the registrations started by `fake_register`
+		static STATES: RefCell<Vec<watch::Receiver<RegistrationState>>> = const { RefCell::new(Vec::new()) };
+	}
+
+	// services named "conflict" fail with a name conflict, "restart"
+	// loses the daemon on the first attempt
+	fn fake_register(spec: &ServiceSpec) -> io::Result<Register> {
+		let attempt = STATES.with(|states| states.borrow().len());
+		let result = if spec.name == "conflict" {
+			Err(Error::KnownError(ffi::DNSServiceError::NameConflict).into())
+		} else if spec.name == "restart" && attempt == 0 {
+			Err(Error::KnownError(ffi::DNSServiceError::ServiceNotRunning).into())
+		} else {
+			Ok(RegisterResult {
+				flags: RegisteredFlags::ADD,
+				name: spec.name.clone(),
+				reg_type: format!("{}.", spec.reg_type),
+				domain: "local.".to_string(),
+			})
+		};
+		let register = Register::unconnected(result);
+		STATES.with(|states| states.borrow_mut().push(register.state()));
+		Ok(register)
+	}
+
+	fn registration_state(ndx: usize) -> watch::Receiver<RegistrationState> {
+		STATES.with(|states| states.borrow()[ndx].clone())
+	}
+
+	async fn removed(ndx: usize) -> bool {
+		let mut state = registration_state(ndx);
+		let removed = async move {
+			state
+				.wait_for(|state| matches!(state, RegistrationState::Removed))
+				.await
+				.is_ok()
+		};
+		tokio::time::timeout(Duration::from_secs(1), removed)
+			.await
+			.unwrap_or(false)
+	}
+
+	#[tokio::test]
+	async fn register_update_remove() {
+		let mut registry = ServiceRegistry::with_register(fake_register, TokioClock);
+		let key = registry
+			.add(ServiceSpec::new("printer", "_ipp._tcp", 631))
+			.unwrap();
+		let e = registry
+			.add(ServiceSpec::new("printer", "_ipp._tcp", 631))
+			.unwrap_err();
+		assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+		// same name and type in another domain is a different service
+		let mut other = ServiceSpec::new("printer", "_ipp._tcp", 631);
+		other.domain = Some("example.com.".to_string());
+		let other_key = registry.add(other).unwrap();
+		assert_ne!(other_key, key);
+		assert!(registry.remove(&other_key));
+		assert!(removed(1).await);
+		assert!(matches!(
+			registry.status(&key),
+			Some(ServiceStatus::Pending)
+		));
+		match registry.next().await.unwrap() {
+			RegistryEvent::Registered(k, result) => {
+				assert_eq!(k, key);
+				assert_eq!(result.name, "printer");
+			},
+			e => panic!("unexpected {:?}", e),
+		}
+		assert!(matches!(
+			registry.status(&key),
+			Some(ServiceStatus::Registered(result)) if result.domain == "local."
+		));
+
+		// a new port needs a new registration
+		registry
+			.update(ServiceSpec::new("printer", "_ipp._tcp", 632))
+			.unwrap();
+		assert!(removed(0).await);
+		assert!(matches!(
+			registry.status(&key),
+			Some(ServiceStatus::Pending)
+		));
+		assert!(matches!(
+			registry.next().await.unwrap(),
+			RegistryEvent::Registered(..)
+		));
+		assert_eq!(registry.specs().next().unwrap().port, 632);
+
+		assert!(registry.remove(&key));
+		assert!(removed(2).await);
+		assert!(!registry.remove(&key));
+		assert!(registry.status(&key).is_none());
+		let e = registry
+			.update(ServiceSpec::new("printer", "_ipp._tcp", 632))
+			.unwrap_err();
+		assert_eq!(e.kind(), io::ErrorKind::NotFound);
+	}
+
+	#[tokio::test]
+	async fn failed_registration() {
+		let mut registry = ServiceRegistry::with_register(fake_register, TokioClock);
+		let spec = ServiceSpec::new("conflict", "_ipp._tcp", 631);
+		let key = registry.add(spec.clone()).unwrap();
+		match registry.next().await.unwrap() {
+			RegistryEvent::Failed(k, e) => {
+				assert_eq!(k, key);
+				// the original error is kept
+				assert!(Error::from_io_error(&e).unwrap().is_name_conflict());
+			},
+			e => panic!("unexpected {:?}", e),
+		}
+		assert!(matches!(
+			registry.status(&key),
+			Some(ServiceStatus::Failed(e)) if Error::from_io_error(e).unwrap().is_name_conflict()
+		));
+
+		// failed services are registered again on update
+		registry.update(spec).unwrap();
+		assert!(matches!(
+			registry.status(&key),
+			Some(ServiceStatus::Pending)
+		));
+		assert!(matches!(
+			registry.next().await.unwrap(),
+			RegistryEvent::Failed(..)
+		));
+		assert_eq!(STATES.with(|states| states.borrow().len()), 2);
+	}
+
+	// clock whose timers fire right away
+	struct Immediate;
+
+	impl Clock for Immediate {
+		type Sleep = futures_util::future::Ready<()>;
+
+		fn now(&self) -> tokio::time::Instant {
+			tokio::time::Instant::now()
+		}
+
+		fn sleep_until(&self, _deadline: tokio::time::Instant) -> Self::Sleep {
+			futures_util::future::ready(())
+		}
+	}
+
+	#[tokio::test]
+	async fn recovers_through_clock() {
+		let mut registry = ServiceRegistry::with_register(fake_register, Immediate);
+		let key = registry
+			.add(ServiceSpec::new("restart", "_ipp._tcp", 631))
+			.unwrap();
+		assert!(matches!(
+			registry.next().await.unwrap(),
+			RegistryEvent::Lost(k, _) if k == key
+		));
+		// the retry timer doesn't need the tokio timer to fire
+		assert!(matches!(
+			registry.next().await.unwrap(),
+			RegistryEvent::Registered(k, _) if k == key
+		));
+		assert_eq!(STATES.with(|states| states.borrow().len()), 2);
+	}
+}
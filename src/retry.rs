@@ -0,0 +1,215 @@
+use std::{
+	collections::hash_map::RandomState,
+	future::Future,
+	hash::{
+		BuildHasher,
+		Hasher,
+	},
+	io,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+
+use crate::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
+	error::is_daemon_transient,
+	service::{
+		register_extended,
+		RegisterData,
+		RegisterResult,
+		Registration,
+	},
+};
+
+/// When and how often to retry operations failing with transient errors
+///
+/// Used by [`register_with_retry`]; either use its default value or
+/// customize it like:
+///
+/// ```
+/// # use async_dnssd::RetryPolicy;
+/// RetryPolicy {
+///     max_attempts: None,
+///     ..Default::default()
+/// };
+/// ```
+///
+/// To restart browses after the daemon went away use
+/// [`browse_reconnecting`](fn.browse_reconnecting.html).
+///
+/// [`register_with_retry`]: fn.register_with_retry.html
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+	/// maximum number of attempts including the first one; `None`
+	/// retries forever (default: 5)
+	pub max_attempts: Option<u32>,
+	/// delay before the first retry (default: 100 milliseconds)
+	pub initial_backoff: Duration,
+	/// upper limit for the delay, which doubles with each retry
+	/// (default: 10 seconds)
+	pub max_backoff: Duration,
+	/// wait a random time between half and the full delay, so clients
+	/// don't retry in lockstep after a daemon restart (default: true)
+	pub jitter: bool,
+	/// which errors to retry (default:
+	/// [`is_transient_daemon_error`](#method.is_transient_daemon_error))
+	pub retryable: fn(&io::Error) -> bool,
+	#[doc(hidden)]
+	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: Some(5),
+			initial_backoff: Duration::from_millis(100),
+			max_backoff: Duration::from_secs(10),
+			jitter: true,
+			retryable: Self::is_transient_daemon_error,
+			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Whether the error is `ServiceNotRunning`, `Transient` or
+	/// `DefunctConnection` (e.g. the daemon got restarted)
+	pub fn is_transient_daemon_error(e: &io::Error) -> bool {
+		is_daemon_transient(e)
+	}
+
+	/// Delay before retry number `retry` (starting at 0)
+	pub fn backoff(&self, retry: u32) -> Duration {
+		let factor = 1u32.checked_shl(retry).unwrap_or(u32::MAX);
+		let delay = self
+			.initial_backoff
+			.checked_mul(factor)
+			.map_or(self.max_backoff, |delay| delay.min(self.max_backoff));
+		if !self.jitter {
+			return delay;
+		}
+		let half = delay / 2;
+		let random = RandomState::new().build_hasher().finish();
+		let range = u64::try_from(half.as_nanos()).unwrap_or(u64::MAX);
+		half + Duration::from_nanos(random % range.saturating_add(1))
+	}
+
+	// whether to retry after `retries` retries failed with `e`
+	fn should_retry(&self, retries: u32, e: &io::Error) -> bool {
+		(self.retryable)(e)
+			&& self
+				.max_attempts
+				.is_none_or(|max| retries.saturating_add(1) < max)
+	}
+}
+
+type RegisterOutput = io::Result<(Registration, RegisterResult)>;
+
+/// Pending registration with retries
+///
+/// Created by [`register_with_retry`](fn.register_with_retry.html).
+#[must_use = "futures do nothing unless polled"]
+pub struct RegisterWithRetry<'a> {
+	inner: Pin<Box<dyn Future<Output = RegisterOutput> + Send + 'a>>,
+}
+
+impl Future for RegisterWithRetry<'_> {
+	type Output = RegisterOutput;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.inner.as_mut().poll(cx)
+	}
+}
+
+/// Register a service, retrying transient failures
+///
+/// Like [`register_extended`](fn.register_extended.html), but starts a
+/// new registration after failures accepted by `policy`.  Errors
+/// showing up after the registration completed aren't handled.
+#[doc(alias = "DNSServiceRegister")]
+pub fn register_with_retry<'a>(
+	reg_type: &'a str,
+	port: u16,
+	data: RegisterData<'a>,
+	policy: RetryPolicy,
+) -> RegisterWithRetry<'a> {
+	register_with_retry_with_clock(reg_type, port, data, policy, TokioClock)
+}
+
+/// Register a service, retrying transient failures
+///
+/// Like [`register_with_retry`](fn.register_with_retry.html), but uses
+/// the given [`Clock`](trait.Clock.html) to wait between attempts.
+#[doc(alias = "DNSServiceRegister")]
+pub fn register_with_retry_with_clock<'a, C>(
+	reg_type: &'a str,
+	port: u16,
+	data: RegisterData<'a>,
+	policy: RetryPolicy,
+	clock: C,
+) -> RegisterWithRetry<'a>
+where
+	C: Clock + Send + 'a,
+	C::Sleep: Send,
+{
+	RegisterWithRetry {
+		inner: Box::pin(async move {
+			let mut retries = 0;
+			loop {
+				let result = match register_extended(reg_type, port, data) {
+					Ok(register) => register.await,
+					Err(e) => Err(e),
+				};
+				match result {
+					Err(e) if policy.should_retry(retries, &e) => {
+						log::debug!("register {} failed, retrying: {}", reg_type, e);
+						let backoff = policy.backoff(retries);
+						clock.sleep_until(clock.now() + backoff).await;
+						retries += 1;
+					},
+					result => return result,
+				}
+			}
+		}),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn backoff() {
+		let policy = RetryPolicy {
+			initial_backoff: Duration::from_millis(100),
+			max_backoff: Duration::from_secs(1),
+			jitter: false,
+			..Default::default()
+		};
+		assert_eq!(policy.backoff(0), Duration::from_millis(100));
+		assert_eq!(policy.backoff(2), Duration::from_millis(400));
+		assert_eq!(policy.backoff(4), Duration::from_secs(1));
+		assert_eq!(policy.backoff(40), Duration::from_secs(1));
+
+		let policy = RetryPolicy {
+			jitter: true,
+			..policy
+		};
+		let delay = policy.backoff(1);
+		assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+
+		let e = io::Error::from(crate::Error::KnownError(
+			crate::ffi::DNSServiceError::ServiceNotRunning,
+		));
+		assert!(policy.should_retry(3, &e));
+		assert!(!policy.should_retry(4, &e));
+		assert!(!policy.should_retry(0, &io::Error::other("permanent")));
+	}
+}
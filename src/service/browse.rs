@@ -1,4 +1,8 @@
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
 use std::{
+	fmt,
+	future::Future,
 	io,
 	os::raw::{
 		c_char,
@@ -9,20 +13,65 @@ use std::{
 		Context,
 		Poll,
 	},
+	time::Duration,
 };
 
 use crate::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
 	cstr,
+	domain_name::DomainName,
 	ffi,
 	inner,
 	interface::Interface,
+	quiescence::Quiescence,
 };
 
-type CallbackStream = crate::stream::ServiceStream<inner::OwnedService, BrowseResult>;
+type CallbackStream = crate::stream::ServiceStream<inner::StreamService, BrowseResult>;
+
+bitflags::bitflags! {
+	/// Flags used to browse for services
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct BrowseFlags: ffi::DNSServiceFlags {
+		/// Also browse over peer-to-peer interfaces (when browsing on
+		/// all interfaces).
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsIncludeP2P`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsincludep2p).
+		const INCLUDE_P2P = ffi::FLAGS_INCLUDE_P2P;
+
+		/// Also browse over Apple Wireless Direct Link (when browsing on
+		/// all interfaces).
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsIncludeAWDL`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsincludeawdl).
+		const INCLUDE_AWDL = ffi::FLAGS_INCLUDE_AWDL;
+
+		/// Don't use cellular interfaces for unicast DNS (only on macOS
+		/// and iOS).
+		///
+		/// See [`kDNSServiceFlagsDenyCellular`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsdenycellular).
+		#[cfg(any(target_os = "macos", target_os = "ios"))]
+		const DENY_CELLULAR = ffi::FLAGS_DENY_CELLULAR;
+
+		/// Don't use expensive interfaces (e.g. cellular, personal
+		/// hotspot) for unicast DNS (only on macOS and iOS).
+		///
+		/// See [`kDNSServiceFlagsDenyExpensive`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsdenyexpensive).
+		#[cfg(any(target_os = "macos", target_os = "ios"))]
+		const DENY_EXPENSIVE = ffi::FLAGS_DENY_EXPENSIVE;
+	}
+}
 
 bitflags::bitflags! {
 	/// Flags for [`BrowseResult`](struct.BrowseResult.html)
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct BrowsedFlags: ffi::DNSServiceFlags {
 		/// Indicates at least one more result is pending in the queue.  If
 		/// not set there still might be more results coming in the future.
@@ -43,18 +92,111 @@ bitflags::bitflags! {
 /// Results are delivered through `Stream`.
 #[must_use = "streams do nothing unless polled"]
 pub struct Browse {
-	stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>,
+	stream: crate::deadline::Deadline<
+		crate::limit::Limit<crate::fused_err_stream::FusedErrorStream<CallbackStream>>,
+	>,
 }
 
 impl Browse {
-	pin_utils::unsafe_pinned!(stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>);
+	/// Wait for the next result; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+
+	/// Collect the services found until results stop coming in, then
+	/// stop browsing
+	///
+	/// Completes once no new results arrived for `quiet_period` (not
+	/// counting the time `MORE_COMING` is set), or after `max_wait`
+	/// (both starting with the first poll).  Services removed in the
+	/// meantime aren't included; the results have `MORE_COMING`
+	/// cleared.
+	pub fn collect_snapshot(self, quiet_period: Duration, max_wait: Duration) -> BrowseSnapshot {
+		self.collect_snapshot_with_clock(quiet_period, max_wait, TokioClock)
+	}
+
+	/// Same as [`collect_snapshot`](#method.collect_snapshot), using the
+	/// given [`Clock`](trait.Clock.html)
+	pub fn collect_snapshot_with_clock<C: Clock>(
+		self,
+		quiet_period: Duration,
+		max_wait: Duration,
+		clock: C,
+	) -> BrowseSnapshot<C> {
+		BrowseSnapshot {
+			browse: Some(self),
+			collector: Quiescence::new(quiet_period, max_wait, clock),
+		}
+	}
+}
+
+/// Pending collection of the currently available services
+///
+/// Created by [`Browse::collect_snapshot`](struct.Browse.html#method.collect_snapshot).
+#[must_use = "futures do nothing unless polled"]
+pub struct BrowseSnapshot<C: Clock = TokioClock> {
+	browse: Option<Browse>,
+	collector: Quiescence<BrowseResult, C>,
+}
+
+fn same_service(a: &BrowseResult, b: &BrowseResult) -> bool {
+	a.service_key() == b.service_key()
+}
+
+impl<C: Clock + Unpin> Future for BrowseSnapshot<C> {
+	type Output = io::Result<Vec<BrowseResult>>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = &mut *self;
+		let browse = this
+			.browse
+			.as_mut()
+			.expect("cannot poll BrowseSnapshot twice");
+		loop {
+			let mut result = match browse.poll_next_unpin(cx) {
+				Poll::Ready(Some(Ok(result))) => result,
+				Poll::Ready(Some(Err(e))) => {
+					this.browse = None;
+					this.collector.finish();
+					return Poll::Ready(Err(e));
+				},
+				// timeout from `BrowseData`: return what we have
+				Poll::Ready(None) => break,
+				Poll::Pending => {
+					futures_core::ready!(this.collector.poll_done(cx));
+					break;
+				},
+			};
+			let more_coming = result.flags.contains(BrowsedFlags::MORE_COMING);
+			if result.flags.contains(BrowsedFlags::ADD) {
+				result.flags.remove(BrowsedFlags::MORE_COMING);
+				this.collector.add(result, same_service);
+			} else {
+				this.collector.remove(&result, same_service);
+			}
+			this.collector.seen(more_coming);
+		}
+		this.browse = None;
+		Poll::Ready(Ok(this.collector.finish()))
+	}
 }
 
 impl futures_core::Stream for Browse {
 	type Item = io::Result<BrowseResult>;
 
-	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		self.stream().poll_next(cx)
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for Browse {
+	fn is_terminated(&self) -> bool {
+		self.stream.is_terminated()
 	}
 }
 
@@ -76,7 +218,54 @@ pub struct BrowseResult {
 	pub domain: String,
 }
 
+/// Identifies a service instance found by browsing
+///
+/// Add and remove results for the same instance share the same key.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ServiceKey {
+	/// Interface the service was found on.
+	pub interface: Interface,
+	/// Name of the service.
+	pub service_name: String,
+	/// Type of the service
+	pub reg_type: String,
+	/// Domain the service was found in
+	pub domain: String,
+}
+
 impl BrowseResult {
+	/// Key identifying the browsed service instance
+	pub fn service_key(&self) -> ServiceKey {
+		ServiceKey {
+			interface: self.interface,
+			service_name: self.service_name.clone(),
+			reg_type: self.reg_type.clone(),
+			domain: self.domain.clone(),
+		}
+	}
+
+	/// Name of the service as [`ServiceInstanceName`](struct.ServiceInstanceName.html)
+	pub fn instance_name(&self) -> crate::ServiceInstanceName {
+		crate::ServiceInstanceName::from(self.service_name.as_str())
+	}
+
+	/// Escaped full name of the service (e.g. for [`query_record`] or
+	/// [`reconfirm_record`])
+	///
+	/// See [`FullName::construct`].
+	///
+	/// [`query_record`]: fn.query_record.html
+	/// [`reconfirm_record`]: fn.reconfirm_record.html
+	/// [`FullName::construct`]: struct.FullName.html#method.construct
+	pub fn full_name(&self) -> io::Result<String> {
+		crate::service::FullName {
+			service: Some(&self.service_name),
+			reg_type: &self.reg_type,
+			domain: &self.domain,
+		}
+		.construct()
+	}
+
 	/// Resolve browse result.
 	///
 	/// Should check before whether result has the `Add` flag, as
@@ -91,6 +280,26 @@ impl BrowseResult {
 	}
 }
 
+/// One line summary like `+ "Printer" _ipp._tcp. local. (en0)`
+impl fmt::Display for BrowseResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sign = if self.flags.contains(BrowsedFlags::ADD) {
+			'+'
+		} else {
+			'-'
+		};
+		write!(
+			f,
+			"{} {:?} {} {}",
+			sign, self.service_name, self.reg_type, self.domain
+		)?;
+		if self.interface != Interface::Any {
+			write!(f, " ({})", self.interface)?;
+		}
+		Ok(())
+	}
+}
+
 unsafe extern "C" fn browse_callback(
 	_sd_ref: ffi::DNSServiceRef,
 	flags: ffi::DNSServiceFlags,
@@ -102,12 +311,12 @@ unsafe extern "C" fn browse_callback(
 	context: *mut c_void,
 ) {
 	CallbackStream::run_callback(context, error_code, || {
-		let service_name = cstr::from_cstr(service_name)?;
-		let reg_type = cstr::from_cstr(reg_type)?;
-		let reply_domain = cstr::from_cstr(reply_domain)?;
+		let service_name = cstr::from_cstr_name(service_name)?;
+		let reg_type = cstr::from_cstr_protocol_name(reg_type)?;
+		let reply_domain = cstr::from_cstr_protocol_name(reply_domain)?;
 
 		Ok(BrowseResult {
-			flags: BrowsedFlags::from_bits_truncate(flags),
+			flags: BrowsedFlags::from_bits_retain(flags),
 			interface: Interface::from_raw(interface_index),
 			service_name: service_name.to_string(),
 			reg_type: reg_type.to_string(),
@@ -128,10 +337,25 @@ unsafe extern "C" fn browse_callback(
 /// ```
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct BrowseData<'a> {
+	/// flags for browsing
+	pub flags: BrowseFlags,
 	/// interface to query records on
 	pub interface: Interface,
 	/// domain on which to search for the service
+	///
+	/// Checked and normalized like [`DomainName`](struct.DomainName.html).
 	pub domain: Option<&'a str>,
+	/// end the operation after the given time (default: never)
+	///
+	/// The stream simply ends (without an error) and the operation is
+	/// stopped right away.
+	pub timeout: Option<Duration>,
+	/// end the operation after the given number of found services
+	/// (default: no limit)
+	///
+	/// Only results with `ADD` set count; the stream ends right after
+	/// the last one and the operation is stopped.
+	pub max_results: Option<usize>,
 	#[doc(hidden)]
 	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 }
@@ -139,22 +363,39 @@ pub struct BrowseData<'a> {
 impl<'a> Default for BrowseData<'a> {
 	fn default() -> Self {
 		Self {
+			flags: BrowseFlags::default(),
 			interface: Interface::default(),
 			domain: None,
+			timeout: None,
+			max_results: None,
 			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 		}
 	}
 }
 
-fn _browse_extended(reg_type: &str, data: BrowseData<'_>) -> io::Result<Browse> {
+fn counts_browse_result(item: &io::Result<BrowseResult>) -> bool {
+	matches!(item, Ok(result) if result.flags.contains(BrowsedFlags::ADD))
+}
+
+fn _browse_extended(
+	connection: Option<&inner::SharedService>,
+	reg_type: &str,
+	data: BrowseData<'_>,
+) -> io::Result<Browse> {
 	crate::init();
 
+	let operation = match data.domain {
+		Some(domain) => format!("browse {} domain={}", reg_type, domain),
+		None => format!("browse {}", reg_type),
+	};
 	let reg_type = cstr::CStr::from(&reg_type)?;
-	let domain = cstr::NullableCStr::from(&data.domain)?;
+	let domain = data.domain.map(DomainName::new).transpose()?;
+	let domain = cstr::NullableCStr::from(&domain)?;
 
-	let stream = CallbackStream::new(move |sender| {
-		inner::OwnedService::browse(
-			0, // no flags
+	let stream = CallbackStream::new(operation, move |sender| {
+		inner::StreamService::browse(
+			connection,
+			crate::compat::filter_flags("DNSServiceBrowse", data.flags.bits(), 0),
 			data.interface.into_raw(),
 			&reg_type,
 			&domain,
@@ -164,7 +405,29 @@ fn _browse_extended(reg_type: &str, data: BrowseData<'_>) -> io::Result<Browse>
 	})
 	.into();
 
-	Ok(Browse { stream })
+	Ok(Browse {
+		stream: crate::deadline::Deadline::new(
+			crate::limit::Limit::new(stream, data.max_results, counts_browse_result),
+			data.timeout,
+		),
+	})
+}
+
+// browse on the given connection (or the default one)
+pub(crate) fn browse_on(
+	connection: Option<&inner::SharedService>,
+	reg_type: &str,
+	data: BrowseData<'_>,
+) -> Browse {
+	match _browse_extended(connection, reg_type, data) {
+		Ok(r) => r,
+		Err(e) => Browse {
+			stream: crate::deadline::Deadline::new(
+				crate::limit::Limit::new(Err(e).into(), None, counts_browse_result),
+				None,
+			),
+		},
+	}
 }
 
 /// Browse for available services
@@ -174,12 +437,7 @@ fn _browse_extended(reg_type: &str, data: BrowseData<'_>) -> io::Result<Browse>
 /// See [`DNSServiceBrowse`](https://developer.apple.com/documentation/dnssd/1804742-dnsservicebrowse).
 #[doc(alias = "DNSServiceBrowse")]
 pub fn browse_extended(reg_type: &str, data: BrowseData<'_>) -> Browse {
-	match _browse_extended(reg_type, data) {
-		Ok(r) => r,
-		Err(e) => Browse {
-			stream: Err(e).into(),
-		},
-	}
+	browse_on(None, reg_type, data)
 }
 
 /// Browse for available services
@@ -0,0 +1,117 @@
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+	collections::{
+		hash_map::Entry,
+		HashMap,
+		VecDeque,
+	},
+	io,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::service::{
+	BrowseResult,
+	BrowsedFlags,
+	ServiceKey,
+};
+
+// follow-up operations started for each service found by a browse
+pub(crate) trait Follow {
+	type State;
+	type Event;
+
+	// service found that isn't followed yet; `None` doesn't follow it
+	fn start(&mut self, result: &BrowseResult) -> Option<Self::State>;
+
+	// event for a browse result (after following the service started or
+	// stopped); `stopped` is the state of a removed service
+	fn browsed(
+		&mut self,
+		result: BrowseResult,
+		stopped: Option<Self::State>,
+	) -> Option<Self::Event>;
+
+	// poll follow-up operations of a service; `false` stops following it
+	fn poll(
+		&mut self,
+		key: &ServiceKey,
+		state: &mut Self::State,
+		events: &mut VecDeque<Self::Event>,
+		cx: &mut Context<'_>,
+	) -> bool;
+}
+
+// browse running follow-up operations per found service (stopped when
+// the service is removed); ends with the browse
+pub(crate) struct BrowseFollow<S, F: Follow> {
+	browse: S,
+	follow: F,
+	services: HashMap<ServiceKey, F::State>,
+	pending: VecDeque<F::Event>,
+}
+
+impl<S, F> BrowseFollow<S, F>
+where
+	S: Stream<Item = io::Result<BrowseResult>> + Unpin,
+	F: Follow,
+{
+	pub(crate) fn new(browse: S, follow: F) -> Self {
+		Self {
+			browse,
+			follow,
+			services: HashMap::new(),
+			pending: VecDeque::new(),
+		}
+	}
+
+	fn handle_browse(&mut self, result: BrowseResult) {
+		let key = result.service_key();
+		let stopped = if result.flags.contains(BrowsedFlags::ADD) {
+			if let Entry::Vacant(entry) = self.services.entry(key) {
+				if let Some(state) = self.follow.start(&result) {
+					entry.insert(state);
+				}
+			}
+			None
+		} else {
+			self.services.remove(&key)
+		};
+		self.pending.extend(self.follow.browsed(result, stopped));
+	}
+
+	fn poll_services(&mut self, cx: &mut Context<'_>) {
+		let follow = &mut self.follow;
+		let pending = &mut self.pending;
+		self.services
+			.retain(|key, state| follow.poll(key, state, pending, cx));
+	}
+
+	pub(crate) fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<F::Event>>> {
+		loop {
+			if let Some(event) = self.pending.pop_front() {
+				return Poll::Ready(Some(Ok(event)));
+			}
+			match self.browse.poll_next_unpin(cx) {
+				Poll::Ready(Some(Ok(result))) => {
+					self.handle_browse(result);
+					continue;
+				},
+				Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+				Poll::Ready(None) => {
+					// the whole stream ends with the browse
+					self.services.clear();
+					return Poll::Ready(None);
+				},
+				Poll::Pending => (),
+			}
+			self.poll_services(cx);
+			if self.pending.is_empty() {
+				return Poll::Pending;
+			}
+		}
+	}
+}
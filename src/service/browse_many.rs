@@ -0,0 +1,157 @@
+use futures_core::Stream;
+use futures_util::{
+	stream::SelectAll,
+	StreamExt,
+};
+use std::{
+	io,
+	pin::Pin,
+	sync::Arc,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::service::{
+	browse_extended,
+	Browse,
+	BrowseData,
+	BrowseResult,
+};
+
+// browse yielding results tagged with the requested type
+struct TaggedBrowse<S = Browse> {
+	reg_type: Arc<str>,
+	browse: S,
+}
+
+impl<S: Stream<Item = io::Result<BrowseResult>> + Unpin> Stream for TaggedBrowse<S> {
+	type Item = (Arc<str>, io::Result<BrowseResult>);
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let item = futures_core::ready!(self.browse.poll_next_unpin(cx));
+		Poll::Ready(item.map(|item| (self.reg_type.clone(), item)))
+	}
+}
+
+/// Pending browse for multiple service types
+///
+/// Yields the results of all browses tagged with the service type (as
+/// passed to [`browse_many`](fn.browse_many.html)) they were found
+/// for.  An error only ends the browse it was reported for; the stream
+/// ends once all browses ended.
+#[must_use = "streams do nothing unless polled"]
+pub struct MultiBrowse {
+	browses: SelectAll<TaggedBrowse>,
+}
+
+impl MultiBrowse {
+	/// Wait for the next result; `None` once all browses ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+}
+
+impl Stream for MultiBrowse {
+	type Item = (Arc<str>, io::Result<BrowseResult>);
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.browses.poll_next_unpin(cx)
+	}
+}
+
+/// Browse for multiple service types at once
+///
+/// Starts one browse per entry of `reg_types` (e.g. `"_http._tcp"` and
+/// `"_https._tcp"`), all using `data` (limits like `max_results` apply
+/// to each browse separately), and merges the results.
+///
+/// See [`DNSServiceBrowse`](https://developer.apple.com/documentation/dnssd/1804742-dnsservicebrowse).
+#[doc(alias = "DNSServiceBrowse")]
+pub fn browse_many_extended(reg_types: &[&str], data: BrowseData<'_>) -> MultiBrowse {
+	MultiBrowse {
+		browses: merge(reg_types, |reg_type| browse_extended(reg_type, data)),
+	}
+}
+
+// browses of all types (scenario streams in tests)
+fn merge<S, F>(reg_types: &[&str], mut browse: F) -> SelectAll<TaggedBrowse<S>>
+where
+	S: Stream<Item = io::Result<BrowseResult>> + Unpin,
+	F: FnMut(&str) -> S,
+{
+	reg_types
+		.iter()
+		.map(|&reg_type| TaggedBrowse {
+			reg_type: Arc::from(reg_type),
+			browse: browse(reg_type),
+		})
+		.collect()
+}
+
+/// Browse for multiple service types at once
+///
+/// Uses [`browse_many_extended`] with default [`BrowseData`].
+///
+/// See [`DNSServiceBrowse`](https://developer.apple.com/documentation/dnssd/1804742-dnsservicebrowse).
+///
+/// [`browse_many_extended`]: fn.browse_many_extended.html
+/// [`BrowseData`]: struct.BrowseData.html
+#[doc(alias = "DNSServiceBrowse")]
+pub fn browse_many(reg_types: &[&str]) -> MultiBrowse {
+	browse_many_extended(reg_types, BrowseData::default())
+}
+
+// the fake services come from the `testing` feature
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+	use super::*;
+	use crate::testing::{
+		FakeService,
+		Scenario,
+	};
+	use std::time::Duration;
+
+	#[tokio::test(start_paused = true)]
+	async fn tagged_results() {
+		let scenario = Scenario::new()
+			.service(FakeService::new("web", "_http._tcp.", "web.local.", 80))
+			.service(FakeService::new(
+				"printer",
+				"_ipp._tcp.",
+				"printer.local.",
+				631,
+			))
+			.add_at(
+				Duration::from_secs(1),
+				FakeService::new("secure", "_https._tcp.", "web.local.", 443),
+			)
+			.daemon_gone_at(Duration::from_secs(2));
+		let browses = merge(&["_http._tcp", "_https._tcp"], |reg_type| {
+			// scenario types are fully qualified
+			scenario.browse(&format!("{}.", reg_type))
+		});
+		let mut results: Vec<_> = browses
+			.map(|(reg_type, result)| match result {
+				Ok(result) => format!("{} {}", reg_type, result.service_name),
+				Err(_) => format!("{} failed", reg_type),
+			})
+			.collect()
+			.await;
+		// each browse ends after its own error
+		results.sort();
+		assert_eq!(
+			results,
+			[
+				"_http._tcp failed",
+				"_http._tcp web",
+				"_https._tcp failed",
+				"_https._tcp secure",
+			]
+		);
+	}
+}
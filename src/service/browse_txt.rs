@@ -0,0 +1,276 @@
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+	collections::VecDeque,
+	io,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	dns_consts::Type,
+	service::{
+		browse_extended,
+		browse_follow::{
+			BrowseFollow,
+			Follow,
+		},
+		monitor_record_extended,
+		Browse,
+		BrowseData,
+		BrowseResult,
+		BrowsedFlags,
+		MonitorRecord,
+		QueryRecordData,
+		RecordChange,
+		ServiceKey,
+	},
+	txt_record::{
+		TxtChange,
+		TxtRecord,
+	},
+};
+
+/// Event of [`BrowseWithTxt`](struct.BrowseWithTxt.html)
+#[derive(Clone)]
+pub enum BrowseTxtEvent {
+	/// Service was found (`ADD` set), TXT monitoring started
+	Added(BrowseResult),
+	/// TXT record of a found service changed (the first TXT record is
+	/// reported with all entries added)
+	TxtChanged {
+		/// service the TXT record belongs to
+		service: ServiceKey,
+		/// current TXT record
+		txt: TxtRecord,
+		/// changed entries
+		changes: Vec<TxtChange>,
+	},
+	/// Service is gone (`ADD` not set), TXT monitoring stopped
+	Removed(BrowseResult),
+}
+
+struct TxtWatch<M> {
+	monitor: M,
+	txt: TxtRecord,
+}
+
+// starts the TXT monitors (scenario streams in tests)
+struct TxtMonitor<FM> {
+	monitor: FM,
+}
+
+fn monitor_txt(result: &BrowseResult) -> io::Result<MonitorRecord> {
+	let data = QueryRecordData {
+		interface: result.interface,
+		..Default::default()
+	};
+	Ok(monitor_record_extended(
+		&result.full_name()?,
+		Type::TXT,
+		data,
+	))
+}
+
+type DaemonTxtMonitor = TxtMonitor<fn(&BrowseResult) -> io::Result<MonitorRecord>>;
+
+impl<M, FM> Follow for TxtMonitor<FM>
+where
+	M: Stream<Item = io::Result<RecordChange>> + Unpin,
+	FM: FnMut(&BrowseResult) -> io::Result<M>,
+{
+	type Event = BrowseTxtEvent;
+	type State = TxtWatch<M>;
+
+	fn start(&mut self, result: &BrowseResult) -> Option<Self::State> {
+		match (self.monitor)(result) {
+			Ok(monitor) => Some(TxtWatch {
+				monitor,
+				txt: TxtRecord::new(),
+			}),
+			Err(e) => {
+				log::warn!("can't monitor TXT record of {}: {}", result, e);
+				None
+			},
+		}
+	}
+
+	fn browsed(
+		&mut self,
+		result: BrowseResult,
+		_stopped: Option<Self::State>,
+	) -> Option<BrowseTxtEvent> {
+		if result.flags.contains(BrowsedFlags::ADD) {
+			Some(BrowseTxtEvent::Added(result))
+		} else {
+			Some(BrowseTxtEvent::Removed(result))
+		}
+	}
+
+	fn poll(
+		&mut self,
+		key: &ServiceKey,
+		watch: &mut Self::State,
+		events: &mut VecDeque<BrowseTxtEvent>,
+		cx: &mut Context<'_>,
+	) -> bool {
+		while let Poll::Ready(item) = watch.monitor.poll_next_unpin(cx) {
+			let change = match item {
+				Some(Ok(change)) => change,
+				Some(Err(e)) => {
+					log::warn!("monitoring TXT record of {:?} failed: {}", key, e);
+					return false;
+				},
+				None => return false,
+			};
+			let txt = match change {
+				RecordChange::Added(record) | RecordChange::Updated { new: record, .. } => {
+					match TxtRecord::parse(&record.rdata) {
+						Some(txt) => txt,
+						None => {
+							log::debug!("ignoring invalid TXT record of {:?}", key);
+							continue;
+						},
+					}
+				},
+				RecordChange::Removed(_) => TxtRecord::new(),
+			};
+			let changes = watch.txt.diff(&txt).into_changes();
+			watch.txt = txt.clone();
+			if !changes.is_empty() {
+				events.push_back(BrowseTxtEvent::TxtChanged {
+					service: key.clone(),
+					txt,
+					changes,
+				});
+			}
+		}
+		true
+	}
+}
+
+/// Pending browse with TXT monitoring
+///
+/// Results are delivered through `Stream`.
+#[must_use = "streams do nothing unless polled"]
+pub struct BrowseWithTxt {
+	inner: BrowseFollow<Browse, DaemonTxtMonitor>,
+}
+
+impl Stream for BrowseWithTxt {
+	type Item = io::Result<BrowseTxtEvent>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.inner.poll_next(cx)
+	}
+}
+
+/// Browse for services and monitor the TXT records of found services
+///
+/// Starts a TXT query (on the interface the service was found on) for
+/// each found service, and stops it when the service is removed.  TXT
+/// changes are reported per entry, e.g. to watch device state published
+/// in TXT records.
+///
+/// TXT queries that fail are logged and stopped; the stream ends with
+/// the browse operation.
+#[doc(alias = "DNSServiceBrowse")]
+pub fn browse_with_txt_extended(reg_type: &str, data: BrowseData<'_>) -> BrowseWithTxt {
+	let monitor = TxtMonitor {
+		monitor: monitor_txt as fn(&BrowseResult) -> io::Result<MonitorRecord>,
+	};
+	BrowseWithTxt {
+		inner: BrowseFollow::new(browse_extended(reg_type, data), monitor),
+	}
+}
+
+/// Browse for services and monitor the TXT records of found services
+///
+/// Uses [`browse_with_txt_extended`] with default [`BrowseData`].
+///
+/// [`browse_with_txt_extended`]: fn.browse_with_txt_extended.html
+/// [`BrowseData`]: struct.BrowseData.html
+#[doc(alias = "DNSServiceBrowse")]
+pub fn browse_with_txt(reg_type: &str) -> BrowseWithTxt {
+	browse_with_txt_extended(reg_type, BrowseData::default())
+}
+
+// the fake services come from the `testing` feature
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+	use super::*;
+	use crate::{
+		dns_consts::Class,
+		service::{
+			QueriedRecordFlags,
+			QueryRecordResult,
+		},
+		testing::{
+			FakeService,
+			Scenario,
+		},
+	};
+	use std::time::Duration;
+
+	const REG_TYPE: &str = "_ipp._tcp.";
+
+	fn txt(value: &[u8]) -> TxtRecord {
+		let mut txt = TxtRecord::new();
+		txt.set_value(b"rp", value).unwrap();
+		txt
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn txt_changes() {
+		let scenario = Scenario::new()
+			.service(FakeService::new("a", REG_TYPE, "a.local.", 631).txt(txt(b"ipp/print")))
+			.update_txt_at(Duration::from_secs(2), "a", REG_TYPE, txt(b"ipp/fax"))
+			.remove_at(Duration::from_secs(4), "a", REG_TYPE);
+		let resolve = scenario.clone();
+		// resolve results stand in for the TXT record changes
+		let monitor = TxtMonitor {
+			monitor: move |r: &BrowseResult| {
+				Ok(resolve
+					.resolve(&r.service_name, &r.reg_type)
+					.map(|resolved| {
+						let resolved = resolved?;
+						Ok(RecordChange::Added(QueryRecordResult {
+							flags: QueriedRecordFlags::ADD,
+							interface: resolved.interface,
+							fullname: resolved.fullname,
+							rr_type: Type::TXT,
+							rr_class: Class::IN,
+							rdata: resolved.txt,
+							ttl: 4500,
+							negative: false,
+						}))
+					}))
+			},
+		};
+		let mut browse = BrowseFollow::new(scenario.browse(REG_TYPE), monitor);
+		let events: Vec<_> = futures_util::stream::poll_fn(|cx| browse.poll_next(cx))
+			.map(|event| match event.unwrap() {
+				BrowseTxtEvent::Added(result) => format!("+ {}", result.service_name),
+				BrowseTxtEvent::TxtChanged {
+					service,
+					txt,
+					changes,
+				} => {
+					assert_eq!(changes.len(), 1);
+					let value = txt.get(b"rp").flatten().unwrap_or_default();
+					format!(
+						"{} rp={}",
+						service.service_name,
+						String::from_utf8_lossy(value)
+					)
+				},
+				BrowseTxtEvent::Removed(result) => format!("- {}", result.service_name),
+			})
+			.collect()
+			.await;
+		assert_eq!(events, ["+ a", "a rp=ipp/print", "a rp=ipp/fax", "- a"]);
+	}
+}
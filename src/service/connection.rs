@@ -16,15 +16,58 @@ use crate::{
 		Class,
 		Type,
 	},
+	domain_name::DomainName,
 	ffi,
 	inner,
 	interface::Interface,
+	service::{
+		browse_on,
+		query_record_on,
+		record_set::{
+			diff_records,
+			RecordChange,
+			RecordKey,
+		},
+		resolve_on,
+		ApplyRecordSet,
+		Browse,
+		BrowseData,
+		QueryRecord,
+		QueryRecordData,
+		RecordDropPolicy,
+		RecordInfo,
+		RecordSet,
+		RecordSetChanges,
+		Resolve,
+		ResolveData,
+		UpdateRecordFlags,
+	},
 };
 
 type CallbackFuture = crate::future::ServiceFuture<inner::SharedService, RegisterRecordResult>;
 
 /// Connection to register records with
-pub struct Connection(inner::SharedService);
+pub struct Connection {
+	service: inner::SharedService,
+	// records registered through `apply`
+	managed: tokio::sync::Mutex<Vec<ManagedRecord>>,
+}
+
+struct ManagedRecord {
+	key: RecordKey,
+	rdata: Vec<u8>,
+	ttl: u32,
+	record: crate::Record,
+}
+
+impl Connection {
+	fn new(service: inner::SharedService) -> Self {
+		Self {
+			service,
+			managed: tokio::sync::Mutex::new(Vec::new()),
+		}
+	}
+}
 
 /// Create [`Connection`](struct.Connection.html) to register records
 /// with
@@ -34,12 +77,31 @@ pub struct Connection(inner::SharedService);
 pub fn connect() -> io::Result<Connection> {
 	crate::init();
 
-	Ok(Connection(inner::SharedService::create_connection()?))
+	Ok(Connection::new(inner::SharedService::create_connection()?))
+}
+
+/// Create [`Connection`](struct.Connection.html) to register records
+/// with on behalf of another process
+///
+/// The process is identified by `pid` if non-zero, otherwise by its
+/// `uuid`.  Requires the calling process to have the corresponding
+/// entitlement; only available on macOS and iOS.
+///
+/// See `DNSServiceCreateDelegateConnection` in `dns_sd.h`.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[doc(alias = "DNSServiceCreateDelegateConnection")]
+pub fn connect_delegate(pid: i32, uuid: [u8; 16]) -> io::Result<Connection> {
+	crate::init();
+
+	Ok(Connection::new(
+		inner::SharedService::create_delegate_connection(pid, &uuid)?,
+	))
 }
 
 bitflags::bitflags! {
 	/// Flags used to register a record
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct RegisterRecordFlags: ffi::DNSServiceFlags {
 		/// Indicates there might me multiple records with the given name, type and class.
 		///
@@ -50,6 +112,14 @@ bitflags::bitflags! {
 		///
 		/// See [`kDNSServiceFlagsUnique`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsunique).
 		const UNIQUE = ffi::FLAGS_UNIQUE;
+
+		/// Answer queries for the record from outside the local link.
+		///
+		/// The avahi compat layer doesn't support registering single
+		/// records at all.
+		///
+		/// See [`kDNSServiceFlagsAllowRemoteQuery`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsallowremotequery).
+		const ALLOW_REMOTE_QUERY = ffi::FLAGS_ALLOW_REMOTE_QUERY;
 	}
 }
 
@@ -65,18 +135,14 @@ pub struct RegisterRecord {
 	record: Option<crate::Record>,
 }
 
-impl RegisterRecord {
-	pin_utils::unsafe_pinned!(future: CallbackFuture);
-
-	pin_utils::unsafe_unpinned!(record: Option<crate::Record>);
-}
-
 impl Future for RegisterRecord {
 	type Output = io::Result<crate::Record>;
 
 	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-		futures_core::ready!(self.as_mut().future().poll(cx))?;
-		Poll::Ready(Ok(self.record().take().unwrap()))
+		futures_core::ready!(self.future.poll_unpin(cx))?;
+		let record = self.record.take().unwrap();
+		record.set_registered();
+		Poll::Ready(Ok(record))
 	}
 }
 
@@ -114,6 +180,9 @@ pub struct RegisterRecordData {
 	/// time to live of the resource record in seconds (passing 0 will
 	/// select a sensible default)
 	pub ttl: u32,
+	/// what happens when dropping the [`Record`](struct.Record.html)
+	/// handle (default: remove it)
+	pub drop_policy: RecordDropPolicy,
 	#[doc(hidden)]
 	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 }
@@ -125,12 +194,31 @@ impl Default for RegisterRecordData {
 			interface: Interface::default(),
 			rr_class: Class::IN,
 			ttl: 0,
+			drop_policy: RecordDropPolicy::RemoveOnDrop,
 			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 		}
 	}
 }
 
 impl Connection {
+	pub(crate) fn service(&self) -> &inner::SharedService {
+		&self.service
+	}
+
+	/// Records currently registered through this connection (in
+	/// registration order)
+	///
+	/// Includes pending registrations and records kept after dropping
+	/// their handle; empty after the connection failed.
+	pub fn records(&self) -> Vec<RecordInfo> {
+		self.service.records()
+	}
+
+	/// Number of records currently registered through this connection
+	pub fn record_count(&self) -> usize {
+		self.service.record_count()
+	}
+
 	/// Register record on interface with given name, type, class, rdata
 	/// and ttl
 	///
@@ -143,21 +231,25 @@ impl Connection {
 		rdata: &[u8],
 		data: RegisterRecordData,
 	) -> io::Result<RegisterRecord> {
+		let operation = format!("register_record {} {}", fullname, rr_type);
 		let fullname = cstr::CStr::from(&fullname)?;
 
-		let (future, record) = CallbackFuture::new_with(self.0.clone(), move |sender| {
-			self.0.clone().register_record(
-				data.flags.bits(),
-				data.interface.into_raw(),
-				&fullname,
-				rr_type,
-				data.rr_class,
-				rdata,
-				data.ttl,
-				Some(register_record_callback),
-				sender,
-			)
-		})?;
+		let (future, mut record) =
+			CallbackFuture::new_with(operation, self.service.clone(), move |sender| {
+				self.service.clone().register_record(
+					data.flags.bits(),
+					data.interface.into_raw(),
+					&fullname,
+					rr_type,
+					data.rr_class,
+					rdata,
+					data.ttl,
+					Some(register_record_callback),
+					sender,
+				)
+			})?;
+
+		record.set_drop_policy(data.drop_policy);
 
 		Ok(RegisterRecord {
 			future,
@@ -165,6 +257,101 @@ impl Connection {
 		})
 	}
 
+	/// Make the records registered through `apply` match `records`
+	///
+	/// Compares `records` with the result of the previous calls and
+	/// only registers, updates (in place, using
+	/// `DNSServiceUpdateRecord`) and removes what changed.  Removals
+	/// happen first, so unique records can move between names.
+	///
+	/// Records registered with
+	/// [`register_record`](#method.register_record) aren't touched.
+	/// Concurrent calls are serialized.  If registering a new record
+	/// fails the other changes stay applied and the first error is
+	/// returned; calling `apply` again retries the missing records.
+	pub fn apply<'a>(&'a self, records: &'a RecordSet) -> ApplyRecordSet<'a> {
+		ApplyRecordSet {
+			inner: Box::pin(self.apply_record_set(records)),
+		}
+	}
+
+	async fn apply_record_set(&self, records: &RecordSet) -> io::Result<RecordSetChanges> {
+		let mut managed = self.managed.lock().await;
+		let entries = records.entries();
+		let existing: Vec<_> = managed
+			.iter()
+			.map(|m| (m.key.clone(), &m.rdata[..], m.ttl))
+			.collect();
+		let desired: Vec<_> = entries
+			.iter()
+			.map(|e| (e.key(), &e.rdata[..], e.data.ttl))
+			.collect();
+		let changes = diff_records(&existing, &desired);
+
+		let mut summary = RecordSetChanges::default();
+		let mut remove = vec![false; managed.len()];
+		let mut add = Vec::new();
+		for change in changes {
+			match change {
+				RecordChange::Keep { .. } => summary.unchanged += 1,
+				RecordChange::Update { existing, desired } => {
+					let entry = &entries[desired];
+					let m = &mut managed[existing];
+					m.record.update_record(&entry.rdata, entry.data.ttl)?;
+					m.rdata.clone_from(&entry.rdata);
+					m.ttl = entry.data.ttl;
+					summary.updated += 1;
+				},
+				RecordChange::Remove { existing } => remove[existing] = true,
+				RecordChange::Add { desired } => add.push(&entries[desired]),
+			}
+		}
+
+		let mut index = 0;
+		managed.retain_mut(|m| {
+			let keep = !remove[index];
+			index += 1;
+			if !keep {
+				m.record.set_drop_policy(RecordDropPolicy::RemoveOnDrop);
+				summary.removed += 1;
+			}
+			keep
+		});
+
+		let mut pending = Vec::new();
+		for entry in &add {
+			pending.push(self.register_record_extended(
+				&entry.fullname,
+				entry.rr_type,
+				&entry.rdata,
+				entry.data,
+			)?);
+		}
+		let mut result = Ok(());
+		for (entry, registered) in add
+			.into_iter()
+			.zip(futures_util::future::join_all(pending).await)
+		{
+			match registered {
+				Ok(record) => {
+					managed.push(ManagedRecord {
+						key: entry.key(),
+						rdata: entry.rdata.clone(),
+						ttl: entry.data.ttl,
+						record,
+					});
+					summary.added += 1;
+				},
+				Err(e) => {
+					if result.is_ok() {
+						result = Err(e);
+					}
+				},
+			}
+		}
+		result.map(|()| summary)
+	}
+
 	/// Register record on interface with given name, type, class, rdata
 	/// and ttl
 	///
@@ -183,6 +370,94 @@ impl Connection {
 	) -> io::Result<RegisterRecord> {
 		self.register_record_extended(fullname, rr_type, rdata, RegisterRecordData::default())
 	}
+
+	/// Browse for available services on this connection
+	///
+	/// Like [`browse_extended`], but runs as subordinate operation
+	/// ([`kDNSServiceFlagsShareConnection`]) on this connection instead
+	/// of using a socket of its own; results are processed by the
+	/// background task of the connection.
+	///
+	/// [`browse_extended`]: fn.browse_extended.html
+	/// [`kDNSServiceFlagsShareConnection`]: https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsshareconnection
+	#[doc(alias = "DNSServiceBrowse")]
+	pub fn browse_extended(&self, reg_type: &str, data: BrowseData<'_>) -> Browse {
+		browse_on(Some(&self.service), reg_type, data)
+	}
+
+	/// Browse for available services on this connection
+	///
+	/// Uses [`browse_extended`](#method.browse_extended) with default
+	/// [`BrowseData`](struct.BrowseData.html).
+	#[doc(alias = "DNSServiceBrowse")]
+	pub fn browse(&self, reg_type: &str) -> Browse {
+		self.browse_extended(reg_type, BrowseData::default())
+	}
+
+	/// Find hostname and port (and more) for a service on this
+	/// connection
+	///
+	/// Like [`resolve_extended`](fn.resolve_extended.html), but runs as
+	/// subordinate operation on this connection (see
+	/// [`browse_extended`](#method.browse_extended)).
+	#[doc(alias = "DNSServiceResolve")]
+	pub fn resolve_extended(
+		&self,
+		interface: Interface,
+		name: &str,
+		reg_type: &str,
+		domain: impl Into<DomainName>,
+		data: ResolveData,
+	) -> Resolve {
+		resolve_on(
+			Some(&self.service),
+			interface,
+			name,
+			reg_type,
+			&domain.into(),
+			data,
+		)
+	}
+
+	/// Find hostname and port (and more) for a service on this
+	/// connection
+	///
+	/// Uses [`resolve_extended`](#method.resolve_extended) with default
+	/// [`ResolveData`](struct.ResolveData.html).
+	#[doc(alias = "DNSServiceResolve")]
+	pub fn resolve(
+		&self,
+		interface: Interface,
+		name: &str,
+		reg_type: &str,
+		domain: impl Into<DomainName>,
+	) -> Resolve {
+		self.resolve_extended(interface, name, reg_type, domain, ResolveData::default())
+	}
+
+	/// Query for an arbitrary DNS record on this connection
+	///
+	/// Like [`query_record_extended`](fn.query_record_extended.html), but
+	/// runs as subordinate operation on this connection (see
+	/// [`browse_extended`](#method.browse_extended)).
+	#[doc(alias = "DNSServiceQueryRecord")]
+	pub fn query_record_extended(
+		&self,
+		fullname: &str,
+		rr_type: Type,
+		data: QueryRecordData,
+	) -> QueryRecord {
+		query_record_on(Some(&self.service), fullname, rr_type, data)
+	}
+
+	/// Query for an arbitrary DNS record on this connection
+	///
+	/// Uses [`query_record_extended`](#method.query_record_extended)
+	/// with default [`QueryRecordData`](struct.QueryRecordData.html).
+	#[doc(alias = "DNSServiceQueryRecord")]
+	pub fn query_record(&self, fullname: &str, rr_type: Type) -> QueryRecord {
+		self.query_record_extended(fullname, rr_type, QueryRecordData::default())
+	}
 }
 
 impl RegisterRecord {
@@ -215,6 +490,24 @@ impl RegisterRecord {
 		self.inner_record().update_record(rdata, ttl)
 	}
 
+	/// Update record with flags (see
+	/// [`Record::update_record_extended`](struct.Record.html#method.update_record_extended))
+	///
+	/// # Panics
+	///
+	/// Panics after the future completed.  Use the returned
+	/// [`Record`](struct.Record.html) instead.
+	#[doc(alias = "DNSServiceUpdateRecord")]
+	pub fn update_record_extended(
+		&self,
+		flags: UpdateRecordFlags,
+		rdata: &[u8],
+		ttl: u32,
+	) -> io::Result<()> {
+		self.inner_record()
+			.update_record_extended(flags, rdata, ttl)
+	}
+
 	/// Keep record for as long as the underlying connection lives.
 	///
 	/// Keep the a handle to the underlying connection (either the
@@ -0,0 +1,322 @@
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+	collections::VecDeque,
+	io,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	service::{
+		browse_extended,
+		browse_follow::{
+			BrowseFollow,
+			Follow,
+		},
+		Browse,
+		BrowseData,
+		BrowseResult,
+		Resolve,
+		ResolveHost,
+		ResolveHostResult,
+		ResolveResult,
+		ResolvedHostFlags,
+		ScopedSocketAddr,
+		ServiceKey,
+	},
+	txt_record::{
+		TxtRecord,
+		TxtRecordRef,
+	},
+};
+
+/// Service found by [`discover`](fn.discover.html), resolved to its
+/// addresses
+#[derive(Clone, Debug)]
+pub struct ResolvedService {
+	/// browse key of the service
+	pub key: ServiceKey,
+	/// full name of the service
+	pub fullname: String,
+	/// hostname the service is provided on
+	pub host_target: String,
+	/// port the service is provided on (native endian)
+	pub port: u16,
+	/// TXT RDATA describing service parameters
+	///
+	/// Parse with [`txt_record`] or [`txt_ref`].
+	///
+	/// [`txt_record`]: #method.txt_record
+	/// [`txt_ref`]: #method.txt_ref
+	pub txt: bytes::Bytes,
+	/// addresses of `host_target` (with `port`)
+	pub addresses: Vec<ScopedSocketAddr>,
+}
+
+impl ResolvedService {
+	/// Parsed TXT record (copy of `txt`); `None` if invalid
+	pub fn txt_record(&self) -> Option<TxtRecord> {
+		TxtRecord::parse(&self.txt)
+	}
+
+	/// Parsed TXT record borrowing `txt`; `None` if invalid
+	pub fn txt_ref(&self) -> Option<TxtRecordRef<'_>> {
+		TxtRecordRef::parse(&self.txt)
+	}
+}
+
+/// Event of [`Discover`](struct.Discover.html)
+#[derive(Clone, Debug)]
+pub enum ServiceEvent {
+	/// Service was found and resolved
+	Added(ResolvedService),
+	/// Previously added service is gone
+	Removed(ServiceKey),
+}
+
+struct AddressLookup<H> {
+	resolved: ResolveResult,
+	query: H,
+	addresses: Vec<ScopedSocketAddr>,
+}
+
+enum ServiceState<R, H> {
+	Resolving(R),
+	Addresses(Box<AddressLookup<H>>),
+	// `Added` was reported
+	Done,
+}
+
+// starts the resolve and address queries (scenario streams in tests)
+struct Resolver<FR, FH> {
+	resolve: FR,
+	resolve_host: FH,
+}
+
+type DaemonResolver = Resolver<fn(&BrowseResult) -> Resolve, fn(&ResolveResult) -> ResolveHost>;
+
+impl<R, H, FR, FH> Follow for Resolver<FR, FH>
+where
+	R: Stream<Item = io::Result<ResolveResult>> + Unpin,
+	H: Stream<Item = io::Result<ResolveHostResult>> + Unpin,
+	FR: FnMut(&BrowseResult) -> R,
+	FH: FnMut(&ResolveResult) -> H,
+{
+	type Event = ServiceEvent;
+	type State = ServiceState<R, H>;
+
+	fn start(&mut self, result: &BrowseResult) -> Option<Self::State> {
+		Some(ServiceState::Resolving((self.resolve)(result)))
+	}
+
+	fn browsed(
+		&mut self,
+		result: BrowseResult,
+		stopped: Option<Self::State>,
+	) -> Option<ServiceEvent> {
+		match stopped {
+			Some(ServiceState::Done) => Some(ServiceEvent::Removed(result.service_key())),
+			_ => None,
+		}
+	}
+
+	fn poll(
+		&mut self,
+		key: &ServiceKey,
+		state: &mut Self::State,
+		events: &mut VecDeque<ServiceEvent>,
+		cx: &mut Context<'_>,
+	) -> bool {
+		match self.poll_service(key, state, events, cx) {
+			Ok(()) => true,
+			Err(e) => {
+				log::warn!("resolving {:?} failed: {}", key, e);
+				false
+			},
+		}
+	}
+}
+
+fn ended() -> io::Error {
+	io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended")
+}
+
+impl<R, H, FR, FH> Resolver<FR, FH>
+where
+	R: Stream<Item = io::Result<ResolveResult>> + Unpin,
+	H: Stream<Item = io::Result<ResolveHostResult>> + Unpin,
+	FR: FnMut(&BrowseResult) -> R,
+	FH: FnMut(&ResolveResult) -> H,
+{
+	fn poll_service(
+		&mut self,
+		key: &ServiceKey,
+		state: &mut ServiceState<R, H>,
+		pending: &mut VecDeque<ServiceEvent>,
+		cx: &mut Context<'_>,
+	) -> io::Result<()> {
+		if let ServiceState::Resolving(resolve) = state {
+			match resolve.poll_next_unpin(cx) {
+				Poll::Ready(Some(resolved)) => {
+					let resolved = resolved?;
+					*state = ServiceState::Addresses(Box::new(AddressLookup {
+						query: (self.resolve_host)(&resolved),
+						resolved,
+						addresses: Vec::new(),
+					}));
+				},
+				Poll::Ready(None) => return Err(ended()),
+				Poll::Pending => return Ok(()),
+			}
+		}
+		if let ServiceState::Addresses(lookup) = state {
+			let AddressLookup {
+				resolved,
+				query,
+				addresses,
+			} = &mut **lookup;
+			while let Poll::Ready(item) = query.poll_next_unpin(cx) {
+				let result = item.ok_or_else(ended)??;
+				if result.flags.contains(ResolvedHostFlags::ADD) {
+					if !addresses.contains(&result.address) {
+						addresses.push(result.address);
+					}
+				} else {
+					addresses.retain(|a| *a != result.address);
+				}
+				// report once a batch of addresses is complete
+				if !result.more_coming && !addresses.is_empty() {
+					pending.push_back(ServiceEvent::Added(ResolvedService {
+						key: key.clone(),
+						fullname: resolved.fullname.clone(),
+						host_target: resolved.host_target.clone(),
+						port: resolved.port,
+						txt: resolved.txt.clone(),
+						addresses: std::mem::take(addresses),
+					}));
+					*state = ServiceState::Done;
+					break;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Pending discovery (browse, resolve and address lookup)
+///
+/// Results are delivered through `Stream`.
+#[must_use = "streams do nothing unless polled"]
+pub struct Discover {
+	inner: BrowseFollow<Browse, DaemonResolver>,
+}
+
+impl Discover {
+	/// Wait for the next event; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+}
+
+impl Stream for Discover {
+	type Item = io::Result<ServiceEvent>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.inner.poll_next(cx)
+	}
+}
+
+/// Browse for services, resolve found services and look up their
+/// addresses
+///
+/// Each found service is resolved (first result only) and reported as
+/// [`ServiceEvent::Added`] once a batch of addresses of its host is
+/// known; the resolve and address queries are stopped then.  Services
+/// that disappear after being reported are reported as
+/// [`ServiceEvent::Removed`].
+///
+/// Services that fail to resolve are logged and dropped; the stream ends
+/// with the browse operation.
+///
+/// [`ServiceEvent::Added`]: enum.ServiceEvent.html#variant.Added
+/// [`ServiceEvent::Removed`]: enum.ServiceEvent.html#variant.Removed
+#[doc(alias = "DNSServiceBrowse")]
+#[doc(alias = "DNSServiceResolve")]
+pub fn discover_extended(reg_type: &str, data: BrowseData<'_>) -> Discover {
+	let resolver = Resolver {
+		resolve: BrowseResult::resolve as fn(&BrowseResult) -> Resolve,
+		resolve_host: ResolveResult::resolve_socket_address as fn(&ResolveResult) -> ResolveHost,
+	};
+	Discover {
+		inner: BrowseFollow::new(browse_extended(reg_type, data), resolver),
+	}
+}
+
+/// Browse for services, resolve found services and look up their
+/// addresses
+///
+/// Uses [`discover_extended`] with default [`BrowseData`].
+///
+/// [`discover_extended`]: fn.discover_extended.html
+/// [`BrowseData`]: struct.BrowseData.html
+#[doc(alias = "DNSServiceBrowse")]
+#[doc(alias = "DNSServiceResolve")]
+pub fn discover(reg_type: &str) -> Discover {
+	discover_extended(reg_type, BrowseData::default())
+}
+
+// the fake services come from the `testing` feature
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+	use super::*;
+	use crate::testing::{
+		FakeService,
+		Scenario,
+	};
+	use std::time::Duration;
+
+	const REG_TYPE: &str = "_ipp._tcp.";
+
+	#[tokio::test(start_paused = true)]
+	async fn resolve_and_remove() {
+		let scenario = Scenario::new()
+			.service(
+				FakeService::new("a", REG_TYPE, "a.local.", 631)
+					.address("10.0.0.1".parse().unwrap()),
+			)
+			// no addresses: never reported
+			.service(FakeService::new("b", REG_TYPE, "b.local.", 631))
+			.remove_at(Duration::from_secs(3), "b", REG_TYPE)
+			.remove_at(Duration::from_secs(5), "a", REG_TYPE);
+		let (s1, s2) = (scenario.clone(), scenario.clone());
+		let resolver = Resolver {
+			resolve: move |r: &BrowseResult| s1.resolve(&r.service_name, &r.reg_type),
+			resolve_host: move |r: &ResolveResult| s2.resolve_host(&r.host_target, r.port),
+		};
+		let mut discover = BrowseFollow::new(scenario.browse(REG_TYPE), resolver);
+		let events: Vec<_> = futures_util::stream::poll_fn(|cx| discover.poll_next(cx))
+			.map(|event| match event.unwrap() {
+				ServiceEvent::Added(service) => {
+					let addresses: Vec<_> =
+						service.addresses.iter().map(ToString::to_string).collect();
+					format!(
+						"+ {} {} {}",
+						service.key.service_name,
+						service.host_target,
+						addresses.join(",")
+					)
+				},
+				ServiceEvent::Removed(key) => format!("- {}", key.service_name),
+			})
+			.collect()
+			.await;
+		assert_eq!(events, ["+ a a.local. 10.0.0.1:631", "- a"]);
+	}
+}
@@ -1,24 +1,39 @@
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
 use std::{
+	collections::HashMap,
+	fmt,
+	future::Future,
 	io,
 	os::raw::{
 		c_char,
 		c_void,
 	},
 	pin::Pin,
+	sync::{
+		Arc,
+		Mutex,
+		Weak,
+	},
 	task::{
 		Context,
 		Poll,
 	},
+	time::{
+		Duration,
+		Instant,
+	},
 };
 
 use crate::{
 	cstr,
+	domain_name::DomainName,
 	ffi,
 	inner,
 	interface::Interface,
 };
 
-type CallbackStream = crate::stream::ServiceStream<inner::OwnedService, EnumerateResult>;
+type CallbackStream = crate::stream::ServiceStream<inner::StreamService, EnumerateResult>;
 
 /// Whether to enumerate domains which are browsed or domains for which
 /// registrations can be made.
@@ -41,7 +56,8 @@ impl From<Enumerate> for ffi::DNSServiceFlags {
 
 bitflags::bitflags! {
 	/// Flags for [`EnumerateDomains`](struct.EnumerateDomains.html)
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct EnumeratedFlags: ffi::DNSServiceFlags {
 		/// Indicates at least one more result is pending in the queue.  If
 		/// not set there still might be more results coming in the future.
@@ -68,15 +84,19 @@ pub struct EnumerateDomains {
 	stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>,
 }
 
-impl EnumerateDomains {
-	pin_utils::unsafe_pinned!(stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>);
-}
-
 impl futures_core::Stream for EnumerateDomains {
 	type Item = io::Result<EnumerateResult>;
 
-	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		self.stream().poll_next(cx)
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for EnumerateDomains {
+	fn is_terminated(&self) -> bool {
+		self.stream.is_terminated()
 	}
 }
 
@@ -93,6 +113,25 @@ pub struct EnumerateResult {
 	pub domain: String,
 }
 
+/// One line summary like `+ local. (default) (en0)`
+impl fmt::Display for EnumerateResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sign = if self.flags.contains(EnumeratedFlags::ADD) {
+			'+'
+		} else {
+			'-'
+		};
+		write!(f, "{} {}", sign, self.domain)?;
+		if self.flags.contains(EnumeratedFlags::DEFAULT) {
+			f.write_str(" (default)")?;
+		}
+		if self.interface != Interface::Any {
+			write!(f, " ({})", self.interface)?;
+		}
+		Ok(())
+	}
+}
+
 unsafe extern "C" fn enumerate_callback(
 	_sd_ref: ffi::DNSServiceRef,
 	flags: ffi::DNSServiceFlags,
@@ -102,10 +141,10 @@ unsafe extern "C" fn enumerate_callback(
 	context: *mut c_void,
 ) {
 	CallbackStream::run_callback(context, error_code, || {
-		let reply_domain = cstr::from_cstr(reply_domain)?;
+		let reply_domain = cstr::from_cstr_protocol_name(reply_domain)?;
 
 		Ok(EnumerateResult {
-			flags: EnumeratedFlags::from_bits_truncate(flags),
+			flags: EnumeratedFlags::from_bits_retain(flags),
 			interface: Interface::from_raw(interface_index),
 			domain: reply_domain.to_string(),
 		})
@@ -119,8 +158,9 @@ unsafe extern "C" fn enumerate_callback(
 pub fn enumerate_domains(enumerate: Enumerate, interface: Interface) -> EnumerateDomains {
 	crate::init();
 
-	let stream = CallbackStream::new(move |sender| {
-		inner::OwnedService::enumerate_domains(
+	let operation = format!("enumerate_domains {:?}", enumerate);
+	let stream = CallbackStream::new(operation, move |sender| {
+		inner::StreamService::enumerate_domains(
 			enumerate.into(),
 			interface.into_raw(),
 			Some(enumerate_callback),
@@ -131,3 +171,194 @@ pub fn enumerate_domains(enumerate: Enumerate, interface: Interface) -> Enumerat
 
 	EnumerateDomains { stream }
 }
+
+/// Pending lookup of the default registration domain
+///
+/// Created by [`pick_registration_domain`](fn.pick_registration_domain.html).
+#[must_use = "futures do nothing unless polled"]
+pub struct PickRegistrationDomain {
+	enumerate: EnumerateDomains,
+}
+
+// domain flagged as default in the initial results (until `MORE_COMING`
+// is cleared)
+fn poll_default_domain(
+	enumerate: &mut EnumerateDomains,
+	cx: &mut Context<'_>,
+) -> Poll<io::Result<Option<String>>> {
+	loop {
+		match futures_core::ready!(enumerate.poll_next_unpin(cx)) {
+			Some(Ok(result)) => {
+				if result
+					.flags
+					.contains(EnumeratedFlags::ADD | EnumeratedFlags::DEFAULT)
+				{
+					return Poll::Ready(Ok(Some(result.domain)));
+				}
+				if !result.flags.contains(EnumeratedFlags::MORE_COMING) {
+					// initial results didn't contain a default domain
+					return Poll::Ready(Ok(None));
+				}
+			},
+			Some(Err(e)) => return Poll::Ready(Err(e)),
+			None => return Poll::Ready(Ok(None)),
+		}
+	}
+}
+
+impl Future for PickRegistrationDomain {
+	type Output = io::Result<Option<String>>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		poll_default_domain(&mut self.enumerate, cx)
+	}
+}
+
+/// Find the default domain to register services on
+///
+/// Enumerates registration domains and returns the domain flagged as
+/// default (e.g. a wide-area domain configured on the host), suitable
+/// for [`RegisterData::domain`].  Completes with `None` if the initial
+/// results (until `MORE_COMING` is cleared) don't contain a default
+/// domain.
+///
+/// [`RegisterData::domain`]: struct.RegisterData.html#structfield.domain
+#[doc(alias = "DNSServiceEnumerateDomains")]
+pub fn pick_registration_domain(interface: Interface) -> PickRegistrationDomain {
+	PickRegistrationDomain {
+		enumerate: enumerate_domains(Enumerate::RegistrationDomains, interface),
+	}
+}
+
+// found default domains by (enumeration, raw interface)
+pub(crate) type DefaultDomainCache =
+	Mutex<Option<HashMap<(Enumerate, u32), (Instant, DomainName)>>>;
+static DEFAULT_DOMAINS: DefaultDomainCache = Mutex::new(None);
+
+// process-wide cache or the one of a `DnssdContext` (gone with it)
+#[derive(Clone)]
+pub(crate) enum DomainCacheRef {
+	Global,
+	Context(Weak<DefaultDomainCache>),
+}
+
+impl DomainCacheRef {
+	pub(crate) fn context(cache: &Arc<DefaultDomainCache>) -> Self {
+		Self::Context(Arc::downgrade(cache))
+	}
+
+	fn with<R>(&self, f: impl FnOnce(&DefaultDomainCache) -> R) -> Option<R> {
+		match self {
+			Self::Global => Some(f(&DEFAULT_DOMAINS)),
+			Self::Context(cache) => cache.upgrade().map(|cache| f(&cache)),
+		}
+	}
+}
+
+// how long to wait for the initial enumeration results
+const DEFAULT_DOMAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pending lookup of a default domain
+///
+/// Created by [`default_browse_domain`] and
+/// [`default_registration_domain`].
+///
+/// [`default_browse_domain`]: fn.default_browse_domain.html
+/// [`default_registration_domain`]: fn.default_registration_domain.html
+#[must_use = "futures do nothing unless polled"]
+pub struct DefaultDomain {
+	inner: Pin<Box<dyn Future<Output = DomainName> + Send>>,
+}
+
+impl Future for DefaultDomain {
+	type Output = DomainName;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.inner.as_mut().poll(cx)
+	}
+}
+
+async fn find_default_domain(enumerate: Enumerate, interface: Interface) -> Option<DomainName> {
+	let mut stream = enumerate_domains(enumerate, interface);
+	match futures_util::future::poll_fn(|cx| poll_default_domain(&mut stream, cx)).await {
+		Ok(domain) => domain.and_then(|domain| DomainName::new(&domain).ok()),
+		Err(e) => {
+			log::warn!("enumerating {:?} failed: {}", enumerate, e);
+			None
+		},
+	}
+}
+
+pub(crate) fn default_domain(
+	enumerate: Enumerate,
+	interface: Interface,
+	cache: DomainCacheRef,
+) -> DefaultDomain {
+	let key = (enumerate, interface.into_raw());
+	let max_age = crate::default_domain_cache_duration();
+	let cached = cache.with(|cache| {
+		let cache = cache.lock().unwrap();
+		let (found, domain) = cache.as_ref()?.get(&key)?;
+		(found.elapsed() < max_age).then(|| domain.clone())
+	});
+	if let Some(domain) = cached.flatten() {
+		return DefaultDomain {
+			inner: Box::pin(async move { domain }),
+		};
+	}
+	DefaultDomain {
+		inner: Box::pin(async move {
+			let found = tokio::time::timeout(
+				DEFAULT_DOMAIN_TIMEOUT,
+				find_default_domain(enumerate, interface),
+			)
+			.await;
+			match found {
+				Ok(Some(domain)) => {
+					cache.with(|cache| {
+						cache
+							.lock()
+							.unwrap()
+							.get_or_insert_with(HashMap::new)
+							.insert(key, (Instant::now(), domain.clone()));
+					});
+					domain
+				},
+				// not cached: might work next time
+				Ok(None) | Err(_) => DomainName::from("local."),
+			}
+		}),
+	}
+}
+
+/// Find the default domain to browse for services in
+///
+/// Enumerates browse domains (on all interfaces) and picks the domain
+/// flagged as default, falling back to `local.` if there is none (or the
+/// enumeration fails or takes too long).  Found domains are cached (see
+/// [`set_default_domain_cache_duration`]).
+///
+/// [`set_default_domain_cache_duration`]: fn.set_default_domain_cache_duration.html
+#[doc(alias = "DNSServiceEnumerateDomains")]
+pub fn default_browse_domain() -> DefaultDomain {
+	default_domain(
+		Enumerate::BrowseDomains,
+		Interface::Any,
+		DomainCacheRef::Global,
+	)
+}
+
+/// Find the default domain to register services on
+///
+/// Like [`default_browse_domain`](fn.default_browse_domain.html) for
+/// registration domains; also see
+/// [`pick_registration_domain`](fn.pick_registration_domain.html) to
+/// tell "no default domain" apart.
+#[doc(alias = "DNSServiceEnumerateDomains")]
+pub fn default_registration_domain() -> DefaultDomain {
+	default_domain(
+		Enumerate::RegistrationDomains,
+		Interface::Any,
+		DomainCacheRef::Global,
+	)
+}
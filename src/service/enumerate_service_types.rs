@@ -0,0 +1,167 @@
+use async_dnssd_types::name::split_labels;
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
+use std::{
+	io,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	interface::Interface,
+	service::{
+		browse_extended,
+		Browse,
+		BrowseData,
+		BrowseResult,
+		BrowsedFlags,
+	},
+};
+
+// DNS-SD meta-query listing service types (RFC 6763 section 9)
+const SERVICE_TYPES: &str = "_services._dns-sd._udp";
+
+/// Service type found by
+/// [`enumerate_service_types`](fn.enumerate_service_types.html)
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ServiceTypeEnumeration {
+	/// service type (e.g. `_http._tcp.`)
+	pub reg_type: String,
+	/// domain the service type was found in
+	pub domain: String,
+	/// interface the service type was found on
+	pub interface: Interface,
+	/// whether the type was added (or removed)
+	pub added: bool,
+	/// `MORE_COMING` was set on the browse result
+	pub more_coming: bool,
+}
+
+impl ServiceTypeEnumeration {
+	// meta-query results have the first label of the type as service
+	// name and the protocol label in front of the domain in `reg_type`:
+	// "_http" + "_tcp.local." (the domain field might just be ".")
+	fn from_browse(result: &BrowseResult) -> Option<Self> {
+		let labels = split_labels(&result.reg_type)?;
+		let (proto, domain) = labels.split_first()?;
+		if !result.service_name.starts_with('_') || !proto.starts_with('_') {
+			return None;
+		}
+		let domain = if domain.is_empty() {
+			result.domain.clone()
+		} else {
+			let mut domain = domain.join(".");
+			domain.push('.');
+			domain
+		};
+		Some(Self {
+			reg_type: format!("{}.{}.", result.service_name, proto),
+			domain,
+			interface: result.interface,
+			added: result.flags.contains(BrowsedFlags::ADD),
+			more_coming: result.flags.contains(BrowsedFlags::MORE_COMING),
+		})
+	}
+}
+
+/// Pending service type enumeration
+#[must_use = "streams do nothing unless polled"]
+pub struct EnumerateServiceTypes {
+	browse: Browse,
+}
+
+impl EnumerateServiceTypes {
+	/// Wait for the next result; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+}
+
+impl futures_core::Stream for EnumerateServiceTypes {
+	type Item = io::Result<ServiceTypeEnumeration>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		loop {
+			let result = match futures_core::ready!(self.browse.poll_next_unpin(cx)) {
+				Some(Ok(result)) => result,
+				Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+				None => return Poll::Ready(None),
+			};
+			match ServiceTypeEnumeration::from_browse(&result) {
+				Some(service_type) => return Poll::Ready(Some(Ok(service_type))),
+				None => log::warn!("ignoring invalid service type result: {:?}", result),
+			}
+		}
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for EnumerateServiceTypes {
+	fn is_terminated(&self) -> bool {
+		self.browse.is_terminated()
+	}
+}
+
+/// Enumerate the service types advertised in a domain
+///
+/// Browses for `_services._dns-sd._udp` and reassembles the service
+/// types from the results (malformed results are logged and skipped);
+/// `data` is passed to the browse.
+///
+/// See [RFC 6763, section 9](https://www.rfc-editor.org/rfc/rfc6763#section-9).
+#[doc(alias = "DNSServiceBrowse")]
+pub fn enumerate_service_types_extended(data: BrowseData<'_>) -> EnumerateServiceTypes {
+	EnumerateServiceTypes {
+		browse: browse_extended(SERVICE_TYPES, data),
+	}
+}
+
+/// Enumerate the service types advertised in a domain
+///
+/// Uses [`enumerate_service_types_extended`] with default
+/// [`BrowseData`] for the given domain (e.g. `"local"`).
+///
+/// [`enumerate_service_types_extended`]: fn.enumerate_service_types_extended.html
+/// [`BrowseData`]: struct.BrowseData.html
+#[doc(alias = "DNSServiceBrowse")]
+pub fn enumerate_service_types(domain: &str) -> EnumerateServiceTypes {
+	enumerate_service_types_extended(BrowseData {
+		domain: Some(domain),
+		..Default::default()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn result(service_name: &str, reg_type: &str, domain: &str) -> BrowseResult {
+		BrowseResult {
+			flags: BrowsedFlags::ADD,
+			interface: Interface::Any,
+			service_name: service_name.to_string(),
+			reg_type: reg_type.to_string(),
+			domain: domain.to_string(),
+		}
+	}
+
+	#[test]
+	fn reassemble() {
+		let t = ServiceTypeEnumeration::from_browse(&result("_http", "_tcp.local.", ".")).unwrap();
+		assert_eq!(t.reg_type, "_http._tcp.");
+		assert_eq!(t.domain, "local.");
+		assert!(t.added);
+		let t =
+			ServiceTypeEnumeration::from_browse(&result("_ipp", "_tcp.", "example.com.")).unwrap();
+		assert_eq!(t.reg_type, "_ipp._tcp.");
+		assert_eq!(t.domain, "example.com.");
+		assert!(ServiceTypeEnumeration::from_browse(&result("http", "_tcp.local.", ".")).is_none());
+	}
+}
@@ -0,0 +1,308 @@
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
+use std::{
+	fmt,
+	io,
+	net::IpAddr,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+
+use crate::{
+	ffi,
+	inner,
+	interface::Interface,
+	service::{
+		DnssecStatus,
+		QueryRecordFlags,
+	},
+};
+
+type CallbackStream = crate::stream::ServiceStream<inner::StreamService, GetAddrInfoResult>;
+
+// the avahi compat layer doesn't implement `DNSServiceGetAddrInfo`
+pub(crate) const GET_ADDR_INFO_SUPPORTED: bool = !cfg!(dnssd_avahi);
+
+bitflags::bitflags! {
+	/// Flags for [`GetAddrInfoResult`](struct.GetAddrInfoResult.html)
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct AddrInfoFlags: ffi::DNSServiceFlags {
+		/// Indicates at least one more result is pending in the queue.  If
+		/// not set there still might be more results coming in the future.
+		///
+		/// See [`kDNSServiceFlagsMoreComing`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsmorecoming).
+		const MORE_COMING = ffi::FLAGS_MORE_COMING;
+
+		/// Indicates the result is new.  If not set indicates the result
+		/// was removed.
+		///
+		/// See [`kDNSServiceFlagsAdd`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsadd).
+		const ADD = ffi::FLAGS_ADD;
+
+		/// The address expired and is being refreshed (only with
+		/// [`ALLOW_EXPIRED_ANSWERS`]).
+		///
+		/// See [`kDNSServiceFlagsExpiredAnswer`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsexpiredanswer).
+		///
+		/// [`ALLOW_EXPIRED_ANSWERS`]: struct.QueryRecordFlags.html#associatedconstant.ALLOW_EXPIRED_ANSWERS
+		const EXPIRED_ANSWER = ffi::FLAGS_EXPIRED_ANSWER;
+	}
+}
+
+/// Address families to look up with
+/// [`get_addr_info_extended`](fn.get_addr_info_extended.html)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AddrProtocol {
+	/// Let the daemon decide (only families with routable addresses
+	/// on this host, both for `.local` names)
+	#[default]
+	Any,
+	/// IPv4 addresses (`A` records)
+	Ipv4,
+	/// IPv6 addresses (`AAAA` records)
+	Ipv6,
+	/// IPv4 and IPv6 addresses
+	Both,
+}
+
+/// Optional data when looking up addresses; either use its default
+/// value or customize it like:
+///
+/// ```
+/// # use async_dnssd::{AddrProtocol, GetAddrInfoData};
+/// GetAddrInfoData {
+///     protocol: AddrProtocol::Ipv6,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct GetAddrInfoData {
+	/// flags for the lookup
+	pub flags: QueryRecordFlags,
+	/// interface to look up addresses on
+	pub interface: Interface,
+	/// address families to look up
+	pub protocol: AddrProtocol,
+	/// end the operation after the given time (default: never)
+	///
+	/// The stream simply ends (without an error) and the operation is
+	/// stopped right away.
+	pub timeout: Option<Duration>,
+	#[doc(hidden)]
+	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+}
+
+/// Pending address lookup
+#[must_use = "streams do nothing unless polled"]
+pub struct GetAddrInfo {
+	stream: crate::deadline::Deadline<crate::fused_err_stream::FusedErrorStream<CallbackStream>>,
+}
+
+impl GetAddrInfo {
+	/// Wait for the next result; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+}
+
+impl futures_core::Stream for GetAddrInfo {
+	type Item = io::Result<GetAddrInfoResult>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for GetAddrInfo {
+	fn is_terminated(&self) -> bool {
+		self.stream.is_terminated()
+	}
+}
+
+/// Address lookup result
+///
+/// See [`DNSServiceGetAddrInfoReply`](https://developer.apple.com/documentation/dnssd/dnsservicegetaddrinforeply).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct GetAddrInfoResult {
+	/// flags
+	pub flags: AddrInfoFlags,
+	/// interface the address was found on
+	pub interface: Interface,
+	/// name the address belongs to
+	pub hostname: String,
+	/// address
+	pub address: IpAddr,
+	/// TTL (time to live) of the address record
+	pub ttl: u32,
+	/// The daemon reported no address of this family exists (only with
+	/// [`RETURN_INTERMEDIATES`]); `address` is unspecified.
+	///
+	/// [`RETURN_INTERMEDIATES`]: struct.QueryRecordFlags.html#associatedconstant.RETURN_INTERMEDIATES
+	pub negative: bool,
+}
+
+impl GetAddrInfoResult {
+	/// DNSSEC validation result (only with [`VALIDATE`] or
+	/// [`VALIDATE_OPTIONAL`])
+	///
+	/// [`VALIDATE`]: struct.QueryRecordFlags.html#associatedconstant.VALIDATE
+	/// [`VALIDATE_OPTIONAL`]: struct.QueryRecordFlags.html#associatedconstant.VALIDATE_OPTIONAL
+	pub fn dnssec_status(&self) -> Option<DnssecStatus> {
+		DnssecStatus::from_flags(self.flags.bits())
+	}
+}
+
+/// One line summary like `+ printer.local. 120 192.0.2.1 (en0)`
+impl fmt::Display for GetAddrInfoResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sign = if self.flags.contains(AddrInfoFlags::ADD) {
+			'+'
+		} else {
+			'-'
+		};
+		write!(
+			f,
+			"{} {} {} {}",
+			sign, self.hostname, self.ttl, self.address
+		)?;
+		if self.negative {
+			f.write_str(" (no such record)")?;
+		}
+		if self.interface != Interface::Any {
+			write!(f, " ({})", self.interface)?;
+		}
+		Ok(())
+	}
+}
+
+// address of a `DNSServiceGetAddrInfo` result
+#[cfg(not(dnssd_avahi))]
+unsafe fn socket_addr(address: *const ffi::sockaddr) -> Option<std::net::SocketAddr> {
+	if address.is_null() {
+		return None;
+	}
+	#[allow(clippy::unnecessary_cast)] // type of `sa_family` depends on the platform
+	let len = match (*address).sa_family as i32 {
+		ffi::AF_INET => size_of::<ffi::sockaddr_in>(),
+		ffi::AF_INET6 => size_of::<ffi::sockaddr_in6>(),
+		_ => return None,
+	};
+	let mut storage = socket2::SockAddrStorage::zeroed();
+	std::ptr::copy_nonoverlapping(
+		address.cast::<u8>(),
+		(&mut storage as *mut socket2::SockAddrStorage).cast::<u8>(),
+		len,
+	);
+	socket2::SockAddr::new(storage, len as socket2::socklen_t).as_socket()
+}
+
+#[cfg(not(dnssd_avahi))]
+unsafe extern "C" fn get_addr_info_callback(
+	_sd_ref: ffi::DNSServiceRef,
+	flags: ffi::DNSServiceFlags,
+	interface_index: u32,
+	error_code: ffi::DNSServiceErrorType,
+	hostname: *const std::os::raw::c_char,
+	address: *const ffi::sockaddr,
+	ttl: u32,
+	context: *mut std::os::raw::c_void,
+) {
+	// negative answers (with `kDNSServiceFlagsReturnIntermediates`) are
+	// reported as "error", but the lookup continues
+	let negative = error_code == ffi::DNSServiceError::NoSuchRecord as ffi::DNSServiceErrorType;
+	let error_code = if negative {
+		ffi::DNSServiceNoError::NoError as ffi::DNSServiceErrorType
+	} else {
+		error_code
+	};
+	CallbackStream::run_callback(context, error_code, || {
+		let hostname = crate::cstr::from_cstr_protocol_name(hostname)?;
+		let address = socket_addr(address).ok_or_else(|| {
+			io::Error::new(io::ErrorKind::InvalidData, "unsupported address family")
+		})?;
+
+		Ok(GetAddrInfoResult {
+			flags: AddrInfoFlags::from_bits_retain(flags),
+			interface: Interface::from_raw(interface_index),
+			hostname: hostname.to_string(),
+			address: address.ip(),
+			ttl,
+			negative,
+		})
+	});
+}
+
+#[cfg(not(dnssd_avahi))]
+fn _get_addr_info_extended(hostname: &str, data: GetAddrInfoData) -> io::Result<CallbackStream> {
+	crate::init();
+
+	let operation = format!("get_addr_info {}", hostname);
+	let hostname = crate::cstr::CStr::from(&hostname)?;
+	let protocol = match data.protocol {
+		AddrProtocol::Any => 0,
+		AddrProtocol::Ipv4 => ffi::PROTOCOL_IPV4,
+		AddrProtocol::Ipv6 => ffi::PROTOCOL_IPV6,
+		AddrProtocol::Both => ffi::PROTOCOL_IPV4 | ffi::PROTOCOL_IPV6,
+	};
+
+	CallbackStream::new(operation, move |sender| {
+		inner::StreamService::get_addr_info(
+			data.flags.bits(),
+			data.interface.into_raw(),
+			protocol,
+			&hostname,
+			Some(get_addr_info_callback),
+			sender,
+		)
+	})
+}
+
+#[cfg(dnssd_avahi)]
+fn _get_addr_info_extended(_hostname: &str, _data: GetAddrInfoData) -> io::Result<CallbackStream> {
+	Err(crate::Error::KnownError(ffi::DNSServiceError::Unsupported).into())
+}
+
+/// Look up the addresses of a host
+///
+/// Unlike separate `A` and `AAAA` queries the daemon also considers
+/// other sources (e.g. `/etc/hosts`) and reports addresses with their
+/// interface.  Not supported by the avahi compat layer (the stream fails
+/// with `Unsupported`); [`resolve_host_extended`] uses separate queries
+/// there.
+///
+/// See [`DNSServiceGetAddrInfo`](https://developer.apple.com/documentation/dnssd/1804700-dnsservicegetaddrinfo).
+///
+/// [`resolve_host_extended`]: fn.resolve_host_extended.html
+#[doc(alias = "DNSServiceGetAddrInfo")]
+pub fn get_addr_info_extended(hostname: &str, data: GetAddrInfoData) -> GetAddrInfo {
+	GetAddrInfo {
+		stream: crate::deadline::Deadline::new(
+			_get_addr_info_extended(hostname, data).into(),
+			data.timeout,
+		),
+	}
+}
+
+/// Look up the addresses of a host
+///
+/// Uses [`get_addr_info_extended`] with default [`GetAddrInfoData`].
+///
+/// See [`DNSServiceGetAddrInfo`](https://developer.apple.com/documentation/dnssd/1804700-dnsservicegetaddrinfo).
+///
+/// [`get_addr_info_extended`]: fn.get_addr_info_extended.html
+/// [`GetAddrInfoData`]: struct.GetAddrInfoData.html
+#[doc(alias = "DNSServiceGetAddrInfo")]
+pub fn get_addr_info(hostname: &str) -> GetAddrInfo {
+	get_addr_info_extended(hostname, GetAddrInfoData::default())
+}
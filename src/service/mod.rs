@@ -1,32 +1,131 @@
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub use self::sleep_keepalive::*;
 pub use self::{
 	browse::*,
+	browse_many::*,
+	browse_txt::*,
 	connection::*,
+	discover::*,
 	enumerate_domains::*,
+	enumerate_service_types::*,
+	get_addr_info::*,
+	monitor_record::*,
+	property::*,
 	query_record::*,
-	records::Record,
+	query_service_records::*,
+	record_set::{
+		ApplyRecordSet,
+		RecordSet,
+		RecordSetChanges,
+	},
+	records::{
+		Record,
+		RecordDropPolicy,
+		RecordInfo,
+		RecordState,
+		UpdateRecordFlags,
+	},
 	register::*,
 	resolve::*,
 	resolve_host::*,
 };
 
 mod browse;
+mod browse_follow;
+mod browse_many;
+mod browse_txt;
 mod connection;
+mod discover;
 mod enumerate_domains;
+mod enumerate_service_types;
+mod get_addr_info;
+mod monitor_record;
+mod property;
 mod query_record;
+mod query_service_records;
+mod record_set;
 mod records;
 mod register;
 mod resolve;
 mod resolve_host;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod sleep_keepalive;
 
-use crate::dns_consts::{
-	Class,
-	Type,
+pub(crate) use self::monitor_record::same_record;
+
+use async_dnssd_types::name::{
+	split_labels,
+	unescape_label,
+};
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+	io,
+	os::raw::c_char,
+	time::Duration,
+};
+
+use crate::{
+	dns_consts::{
+		Class,
+		Type,
+	},
+	domain_name::DomainName,
 };
-use std::os::raw::c_char;
+
+// first result passing `accept`; the stream (and the operation) is
+// dropped when done
+pub(crate) async fn first_result<S, T>(
+	mut stream: S,
+	timeout: Option<Duration>,
+	mut accept: impl FnMut(&T) -> bool,
+) -> io::Result<T>
+where
+	S: Stream<Item = io::Result<T>> + Unpin,
+{
+	let first = async {
+		while let Some(item) = stream.next().await {
+			let item = item?;
+			if accept(&item) {
+				return Ok(item);
+			}
+		}
+		Err(io::Error::new(
+			io::ErrorKind::UnexpectedEof,
+			"operation ended without result",
+		))
+	};
+	match timeout {
+		Some(timeout) => tokio::time::timeout(timeout, first).await.map_err(|_| {
+			io::Error::new(
+				io::ErrorKind::TimedOut,
+				"operation timed out without result",
+			)
+		})?,
+		None => first.await,
+	}
+}
+
+bitflags::bitflags! {
+	/// Flags for [`reconfirm_record_with_flags`](fn.reconfirm_record_with_flags.html)
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct ReconfirmFlags: crate::ffi::DNSServiceFlags {
+		/// Remove the record from the cache right away instead of
+		/// verifying it first.
+		///
+		/// See [`kDNSServiceFlagsForce`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsforce).
+		const FORCE = crate::ffi::FLAGS_FORCE;
+	}
+}
 
 /// Purge record from cache
 ///
+/// Uses [`reconfirm_record_with_flags`] without flags.
+///
 /// See [`DNSServiceReconfirmRecord`](https://developer.apple.com/documentation/dnssd/1804726-dnsservicereconfirmrecord).
+///
+/// [`reconfirm_record_with_flags`]: fn.reconfirm_record_with_flags.html
 #[doc(alias = "DNSServiceReconfirmRecord")]
 pub fn reconfirm_record(
 	interface: crate::interface::Interface,
@@ -34,22 +133,66 @@ pub fn reconfirm_record(
 	rr_type: Type,
 	rr_class: Class,
 	rdata: &[u8],
-) -> ::std::io::Result<()> {
+) -> io::Result<()> {
+	reconfirm_record_with_flags(
+		interface,
+		fullname,
+		rr_type,
+		rr_class,
+		rdata,
+		ReconfirmFlags::empty(),
+	)
+}
+
+/// Purge record from cache
+///
+/// Asks the daemon to verify the record (or to remove it right away
+/// with [`ReconfirmFlags::FORCE`]); the returned error tells whether
+/// the daemon accepted the request.
+///
+/// See [`DNSServiceReconfirmRecord`](https://developer.apple.com/documentation/dnssd/1804726-dnsservicereconfirmrecord).
+///
+/// [`ReconfirmFlags::FORCE`]: struct.ReconfirmFlags.html#associatedconstant.FORCE
+#[doc(alias = "DNSServiceReconfirmRecord")]
+pub fn reconfirm_record_with_flags(
+	interface: crate::interface::Interface,
+	fullname: &str,
+	rr_type: Type,
+	rr_class: Class,
+	rdata: &[u8],
+	flags: ReconfirmFlags,
+) -> io::Result<()> {
 	crate::init();
 
 	let fullname = crate::cstr::CStr::from(&fullname)?;
 	crate::inner::reconfirm_record(
-		0, // no flags
+		flags.bits(),
 		interface.into_raw(),
 		&fullname,
 		rr_type,
 		rr_class,
 		rdata,
-	);
-
+	)?;
 	Ok(())
 }
 
+/// Check whether the daemon is available
+///
+/// Connects to the daemon (and disconnects right away); the avahi compat
+/// layer doesn't support `DNSServiceCreateConnection`, so a browse gets
+/// started (and stopped right away) instead.  On Windows this also
+/// checks whether the installed Bonjour version is compatible and fails
+/// with [`Error::Incompatible`] otherwise.
+///
+/// [`Error::Incompatible`]: enum.Error.html#variant.Incompatible
+pub fn daemon_status() -> Result<(), crate::Error> {
+	crate::init();
+
+	#[cfg(windows)]
+	crate::inner::check_version()?;
+	crate::inner::check_daemon()
+}
+
 /// Full name consiting of (up to) three parts
 pub struct FullName<'a> {
 	/// (unescaped) service name (becomes single label in full name)
@@ -68,9 +211,10 @@ impl<'a> FullName<'a> {
 	pub fn construct(&self) -> ::std::io::Result<String> {
 		use std::io;
 
+		let domain = DomainName::new(self.domain)?;
 		let service = crate::cstr::NullableCStr::from(&self.service)?;
 		let reg_type = crate::cstr::CStr::from(&self.reg_type)?;
-		let domain = crate::cstr::CStr::from(&self.domain)?;
+		let domain = crate::cstr::CStr::from(&domain)?;
 
 		const SIZE: usize = crate::ffi::MAX_DOMAIN_NAME;
 		let mut buf: Vec<u8> = Vec::with_capacity(SIZE);
@@ -97,4 +241,93 @@ impl<'a> FullName<'a> {
 
 		String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
 	}
+
+	/// Split full name of a service instance (as found in PTR records)
+	/// into its parts
+	///
+	/// The first label is the (unescaped) service name, the next two
+	/// labels the registration type and the remaining labels the (still
+	/// escaped) domain; the reverse of [`construct`](#method.construct).
+	pub fn parse(fullname: &str) -> ::std::io::Result<ParsedFullName> {
+		use std::io;
+
+		let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "invalid service full name");
+		let labels = split_labels(fullname).ok_or_else(invalid)?;
+		if labels.len() < 4 || !labels[1].starts_with('_') || !labels[2].starts_with('_') {
+			return Err(invalid());
+		}
+		let service = unescape_label(labels[0])
+			.and_then(|service| String::from_utf8(service).ok())
+			.ok_or_else(invalid)?;
+		let reg_type = format!("{}.{}.", labels[1], labels[2]);
+		let mut domain = labels[3..].join(".");
+		domain.push('.');
+		Ok(ParsedFullName {
+			service,
+			reg_type,
+			domain,
+		})
+	}
+}
+
+/// Parts of a service instance full name
+///
+/// Result of [`FullName::parse`](struct.FullName.html#method.parse).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ParsedFullName {
+	/// (unescaped) service name
+	pub service: String,
+	/// registration type (e.g. `_http._tcp.`)
+	pub reg_type: String,
+	/// (escaped) domain name
+	pub domain: String,
+}
+
+impl ParsedFullName {
+	/// Borrow parts as `FullName`
+	pub fn as_full_name(&self) -> FullName<'_> {
+		FullName {
+			service: Some(&self.service),
+			reg_type: &self.reg_type,
+			domain: &self.domain,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		first_result,
+		FullName,
+	};
+	use std::{
+		io,
+		time::Duration,
+	};
+
+	#[test]
+	fn parse_full_name() {
+		let parsed = FullName::parse("My\\.Printer\\0322._ipp._tcp.example\\.net.local.").unwrap();
+		assert_eq!(parsed.service, "My.Printer 2");
+		assert_eq!(parsed.reg_type, "_ipp._tcp.");
+		assert_eq!(parsed.domain, "example\\.net.local.");
+		assert!(FullName::parse("_ipp._tcp.local.").is_err());
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn first_result_timeout() {
+		let (tx, rx) = futures_channel::mpsc::unbounded::<io::Result<u32>>();
+		tx.unbounded_send(Ok(1)).unwrap();
+		tx.unbounded_send(Ok(2)).unwrap();
+		let mut rx = rx;
+		let first = first_result(&mut rx, None, |&v| v > 1).await.unwrap();
+		assert_eq!(first, 2);
+		let err = first_result(&mut rx, Some(Duration::from_secs(1)), |_| true)
+			.await
+			.unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+		drop(tx);
+		let err = first_result(rx, None, |_| true).await.unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+	}
 }
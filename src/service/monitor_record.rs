@@ -0,0 +1,252 @@
+use futures_util::StreamExt;
+use std::{
+	collections::VecDeque,
+	io,
+	mem,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	dns_consts::Type,
+	service::{
+		query_record_extended,
+		QueriedRecordFlags,
+		QueryRecord,
+		QueryRecordData,
+		QueryRecordResult,
+	},
+};
+
+/// Change of a monitored record set
+///
+/// Produced by [`MonitorRecord`](struct.MonitorRecord.html).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum RecordChange {
+	/// New record
+	Added(QueryRecordResult),
+	/// A record was replaced by a record with different RDATA (same
+	/// interface, name, type and class)
+	Updated {
+		/// previous record
+		old: QueryRecordResult,
+		/// new record
+		new: QueryRecordResult,
+	},
+	/// Record was removed
+	Removed(QueryRecordResult),
+}
+
+fn same_rrset(a: &QueryRecordResult, b: &QueryRecordResult) -> bool {
+	a.interface == b.interface
+		&& a.fullname == b.fullname
+		&& a.rr_type == b.rr_type
+		&& a.rr_class == b.rr_class
+}
+
+pub(crate) fn same_record(a: &QueryRecordResult, b: &QueryRecordResult) -> bool {
+	same_rrset(a, b) && a.rdata == b.rdata
+}
+
+#[derive(Default)]
+struct RecordSet {
+	records: Vec<QueryRecordResult>,
+}
+
+impl RecordSet {
+	fn apply(&mut self, mut result: QueryRecordResult) {
+		let pos = self.records.iter().position(|r| same_record(r, &result));
+		if result.flags.contains(QueriedRecordFlags::ADD) {
+			result.flags.remove(QueriedRecordFlags::MORE_COMING);
+			match pos {
+				Some(pos) => self.records[pos] = result, // refresh (e.g. TTL)
+				None => self.records.push(result),
+			}
+		} else if let Some(pos) = pos {
+			self.records.remove(pos);
+		}
+	}
+
+	// apply a batch of results and return the resulting changes
+	fn apply_batch(&mut self, batch: Vec<QueryRecordResult>) -> Vec<RecordChange> {
+		let before = self.records.clone();
+		for result in batch {
+			self.apply(result);
+		}
+
+		let mut added: Vec<QueryRecordResult> = self
+			.records
+			.iter()
+			.filter(|r| !before.iter().any(|b| same_record(b, r)))
+			.cloned()
+			.collect();
+		let mut changes = Vec::new();
+		for mut old in before {
+			if self.records.iter().any(|r| same_record(r, &old)) {
+				continue;
+			}
+			old.flags.remove(QueriedRecordFlags::ADD);
+			match added.iter().position(|a| same_rrset(a, &old)) {
+				Some(pos) => changes.push(RecordChange::Updated {
+					old,
+					new: added.remove(pos),
+				}),
+				None => changes.push(RecordChange::Removed(old)),
+			}
+		}
+		changes.extend(added.into_iter().map(RecordChange::Added));
+		changes
+	}
+}
+
+/// Pending record monitor
+///
+/// Results are delivered through `Stream`.
+#[must_use = "streams do nothing unless polled"]
+pub struct MonitorRecord {
+	query: QueryRecord,
+	records: RecordSet,
+	// results until `MORE_COMING` is cleared
+	batch: Vec<QueryRecordResult>,
+	changes: VecDeque<RecordChange>,
+	query_done: bool,
+}
+
+impl MonitorRecord {
+	fn flush_batch(&mut self) {
+		let batch = mem::take(&mut self.batch);
+		self.changes.extend(self.records.apply_batch(batch));
+	}
+}
+
+impl futures_core::Stream for MonitorRecord {
+	type Item = io::Result<RecordChange>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		loop {
+			if let Some(change) = this.changes.pop_front() {
+				return Poll::Ready(Some(Ok(change)));
+			}
+			if this.query_done {
+				return Poll::Ready(None);
+			}
+			match futures_core::ready!(this.query.poll_next_unpin(cx)) {
+				Some(Ok(result)) => {
+					let more_coming = result.flags.contains(QueriedRecordFlags::MORE_COMING);
+					this.batch.push(result);
+					if !more_coming {
+						this.flush_batch();
+					}
+				},
+				Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+				None => {
+					this.query_done = true;
+					this.flush_batch();
+				},
+			}
+		}
+	}
+}
+
+/// Monitor a record set for changes
+///
+/// Keeps track of the current records found by a (long-lived) query
+/// and reports changes instead of raw add/remove results; a removal and
+/// an addition in the same batch of results (see
+/// [`QueriedRecordFlags::MORE_COMING`]) for the same interface, name,
+/// type and class are reported as update.
+///
+/// [`QueriedRecordFlags::MORE_COMING`]: struct.QueriedRecordFlags.html#associatedconstant.MORE_COMING
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn monitor_record_extended(
+	fullname: &str,
+	rr_type: Type,
+	data: QueryRecordData,
+) -> MonitorRecord {
+	MonitorRecord {
+		query: query_record_extended(fullname, rr_type, data),
+		records: RecordSet::default(),
+		batch: Vec::new(),
+		changes: VecDeque::new(),
+		query_done: false,
+	}
+}
+
+/// Monitor a record set for changes
+///
+/// Uses [`monitor_record_extended`] with default [`QueryRecordData`].
+///
+/// [`monitor_record_extended`]: fn.monitor_record_extended.html
+/// [`QueryRecordData`]: struct.QueryRecordData.html
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn monitor_record(fullname: &str, rr_type: Type) -> MonitorRecord {
+	monitor_record_extended(fullname, rr_type, QueryRecordData::default())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		Class,
+		Interface,
+	};
+
+	fn txt(rdata: &[u8], flags: QueriedRecordFlags) -> QueryRecordResult {
+		QueryRecordResult {
+			flags,
+			interface: Interface::Any,
+			fullname: "foo._http._tcp.local.".to_string(),
+			rr_type: Type::TXT,
+			rr_class: Class::IN,
+			rdata: bytes::Bytes::copy_from_slice(rdata),
+			ttl: 4500,
+			negative: false,
+		}
+	}
+
+	#[test]
+	fn updates_match_rdata() {
+		let mut set = RecordSet::default();
+		let changes = set.apply_batch(vec![txt(b"\x03a=1", QueriedRecordFlags::ADD)]);
+		assert_eq!(
+			changes,
+			[RecordChange::Added(txt(
+				b"\x03a=1",
+				QueriedRecordFlags::ADD
+			))]
+		);
+
+		// refresh doesn't change anything
+		assert!(set
+			.apply_batch(vec![txt(b"\x03a=1", QueriedRecordFlags::ADD)])
+			.is_empty());
+
+		let changes = set.apply_batch(vec![
+			txt(
+				b"\x03a=2",
+				QueriedRecordFlags::ADD | QueriedRecordFlags::MORE_COMING,
+			),
+			txt(b"\x03a=1", QueriedRecordFlags::empty()),
+		]);
+		assert_eq!(
+			changes,
+			[RecordChange::Updated {
+				old: txt(b"\x03a=1", QueriedRecordFlags::empty()),
+				new: txt(b"\x03a=2", QueriedRecordFlags::ADD),
+			}]
+		);
+
+		let changes = set.apply_batch(vec![txt(b"\x03a=2", QueriedRecordFlags::empty())]);
+		assert_eq!(
+			changes,
+			[RecordChange::Removed(txt(
+				b"\x03a=2",
+				QueriedRecordFlags::empty()
+			))]
+		);
+	}
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+
+use crate::{
+	ffi,
+	inner,
+};
+
+/// Daemon property to query with [`get_property`](fn.get_property.html)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[non_exhaustive]
+pub enum Property {
+	/// Version of the daemon (`DaemonVersion`)
+	DaemonVersion,
+}
+
+/// Value of a [`Property`](enum.Property.html)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[non_exhaustive]
+pub enum PropertyValue {
+	/// Value of [`Property::DaemonVersion`](enum.Property.html#variant.DaemonVersion)
+	DaemonVersion(DaemonVersion),
+}
+
+/// Version of the daemon, like `1310.80.1` for `mDNSResponder-1310.80.1`
+///
+/// Compare versions with `Ord` (or the raw value) to check for fixes
+/// or features of specific daemon releases.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DaemonVersion(u32);
+
+impl DaemonVersion {
+	/// Decode version encoded as `major * 10000 + minor * 100 + patch`
+	pub fn from_raw(raw: u32) -> Self {
+		Self(raw)
+	}
+
+	/// Encoded version as reported by the daemon
+	pub fn into_raw(self) -> u32 {
+		self.0
+	}
+
+	/// Major version (`1310` in `1310.80.1`)
+	pub fn major(self) -> u32 {
+		self.0 / 10000
+	}
+
+	/// Minor version (`80` in `1310.80.1`)
+	pub fn minor(self) -> u32 {
+		self.0 / 100 % 100
+	}
+
+	/// Patch version (`1` in `1310.80.1`)
+	pub fn patch(self) -> u32 {
+		self.0 % 100
+	}
+}
+
+impl fmt::Display for DaemonVersion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}.{}.{}", self.major(), self.minor(), self.patch())
+	}
+}
+
+impl fmt::Debug for DaemonVersion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+/// Query a property of the daemon
+///
+/// Also checks whether the daemon is available (see
+/// [`daemon_status`](fn.daemon_status.html)).  The avahi compat layer
+/// doesn't support this and always fails with `Unsupported`.
+///
+/// See [`DNSServiceGetProperty`](https://developer.apple.com/documentation/dnssd/1804733-dnsservicegetproperty).
+#[doc(alias = "DNSServiceGetProperty")]
+pub fn get_property(property: Property) -> Result<PropertyValue, crate::Error> {
+	crate::init();
+
+	match property {
+		Property::DaemonVersion => {
+			let raw = inner::get_property_u32(ffi::PROPERTY_DAEMON_VERSION)?;
+			Ok(PropertyValue::DaemonVersion(DaemonVersion(raw)))
+		},
+	}
+}
+
+/// Version of the daemon
+///
+/// Shortcut for [`get_property`](fn.get_property.html) with
+/// [`Property::DaemonVersion`](enum.Property.html#variant.DaemonVersion).
+#[doc(alias = "DNSServiceGetProperty")]
+pub fn daemon_version() -> Result<DaemonVersion, crate::Error> {
+	match get_property(Property::DaemonVersion)? {
+		PropertyValue::DaemonVersion(version) => Ok(version),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::DaemonVersion;
+
+	#[test]
+	fn daemon_version() {
+		let version = DaemonVersion::from_raw(13108001);
+		assert_eq!(
+			(version.major(), version.minor(), version.patch()),
+			(1310, 80, 1)
+		);
+		assert_eq!(version.to_string(), "1310.80.1");
+		assert!(DaemonVersion::from_raw(8780101) < version);
+	}
+}
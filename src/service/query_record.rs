@@ -1,4 +1,8 @@
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
 use std::{
+	fmt,
+	future::Future,
 	io,
 	os::raw::{
 		c_char,
@@ -9,9 +13,14 @@ use std::{
 		Context,
 		Poll,
 	},
+	time::Duration,
 };
 
 use crate::{
+	clock::{
+		Clock,
+		TokioClock,
+	},
 	cstr,
 	dns_consts::{
 		Class,
@@ -20,24 +29,119 @@ use crate::{
 	ffi,
 	inner,
 	interface::Interface,
+	quiescence::Quiescence,
+	rdata::Rdata,
 };
 
-type CallbackStream = crate::stream::ServiceStream<inner::OwnedService, QueryRecordResult>;
+type CallbackStream = crate::stream::ServiceStream<inner::StreamService, QueryRecordResult>;
 
 bitflags::bitflags! {
 	/// Flags used to query for a record
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct QueryRecordFlags: ffi::DNSServiceFlags {
 		/// long-lived unicast query
 		///
 		/// See [`kDNSServiceFlagsLongLivedQuery`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagslonglivedquery).
 		const LONG_LIVED_QUERY = ffi::FLAGS_LONG_LIVED_QUERY;
+
+		/// Also return intermediate results, e.g. CNAME records and
+		/// negative answers (see [`QueryRecordResult::negative`]).
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsReturnIntermediates`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsreturnintermediates).
+		///
+		/// [`QueryRecordResult::negative`]: struct.QueryRecordResult.html#structfield.negative
+		const RETURN_INTERMEDIATES = ffi::FLAGS_RETURN_INTERMEDIATES;
+
+		/// Query via multicast even for names outside `.local`.
+		///
+		/// Also defined by the avahi compat layer (with the same value).
+		///
+		/// See [`kDNSServiceFlagsForceMulticast`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsforcemulticast).
+		const FORCE_MULTICAST = ffi::FLAGS_FORCE_MULTICAST;
+
+		/// Request unicast responses ("QU" questions) for multicast
+		/// queries, e.g. for one-shot probes.
+		///
+		/// Only honored by mDNSResponder (on Apple platforms and its
+		/// POSIX build); not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsUnicastResponse`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsunicastresponse).
+		const UNICAST_RESPONSE = ffi::FLAGS_UNICAST_RESPONSE;
+
+		/// Validate answers with DNSSEC (see
+		/// [`QueryRecordResult::dnssec_status`]).
+		///
+		/// Only honored by mDNSResponder versions with DNSSEC support; not
+		/// supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsValidate`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsvalidate).
+		///
+		/// [`QueryRecordResult::dnssec_status`]: struct.QueryRecordResult.html#method.dnssec_status
+		const VALIDATE = ffi::FLAGS_VALIDATE;
+
+		/// Validate answers with DNSSEC if the zone is signed, without
+		/// delaying answers from unsigned zones.
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsValidateOptional`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsvalidateoptional).
+		const VALIDATE_OPTIONAL = ffi::FLAGS_VALIDATE_OPTIONAL;
+
+		/// Also return CNAME records when following them (only when
+		/// linked against the avahi compat layer).
+		///
+		/// Only defined by older versions of `dns_sd.h`, which the avahi
+		/// compat layer is based on; current mDNSResponder uses this bit
+		/// for `kDNSServiceFlagsForce`, and returns intermediate results
+		/// with [`RETURN_INTERMEDIATES`] instead.  Also see
+		/// [`QueryRecordData::return_cnames`].
+		///
+		/// [`RETURN_INTERMEDIATES`]: #associatedconstant.RETURN_INTERMEDIATES
+		/// [`QueryRecordData::return_cnames`]: struct.QueryRecordData.html#structfield.return_cnames
+		#[cfg(dnssd_avahi)]
+		const RETURN_CNAME = ffi::FLAGS_RETURN_CNAME;
+
+		/// Don't return addresses that aren't usable on this host (e.g.
+		/// `AAAA` records without IPv6 connectivity).
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsSuppressUnusable`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagssuppressunusable).
+		const SUPPRESS_UNUSABLE = ffi::FLAGS_SUPPRESS_UNUSABLE;
+
+		/// Return expired cached records right away (marked with
+		/// [`EXPIRED_ANSWER`]) while refreshing them.
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsAllowExpiredAnswers`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsallowexpiredanswers).
+		///
+		/// [`EXPIRED_ANSWER`]: struct.QueriedRecordFlags.html#associatedconstant.EXPIRED_ANSWER
+		const ALLOW_EXPIRED_ANSWERS = ffi::FLAGS_ALLOW_EXPIRED_ANSWERS;
+
+		/// Don't use cellular interfaces for unicast DNS (only on macOS
+		/// and iOS).
+		///
+		/// See [`kDNSServiceFlagsDenyCellular`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsdenycellular).
+		#[cfg(any(target_os = "macos", target_os = "ios"))]
+		const DENY_CELLULAR = ffi::FLAGS_DENY_CELLULAR;
+
+		/// Don't use expensive interfaces (e.g. cellular, personal
+		/// hotspot) for unicast DNS (only on macOS and iOS).
+		///
+		/// See [`kDNSServiceFlagsDenyExpensive`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsdenyexpensive).
+		#[cfg(any(target_os = "macos", target_os = "ios"))]
+		const DENY_EXPENSIVE = ffi::FLAGS_DENY_EXPENSIVE;
 	}
 }
 
 bitflags::bitflags! {
 	/// Flags for [`QueryRecordResult`](struct.QueryRecordResult.html)
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct QueriedRecordFlags: ffi::DNSServiceFlags {
 		/// Indicates at least one more result is pending in the queue.  If
 		/// not set there still might be more results coming in the future.
@@ -50,24 +154,235 @@ bitflags::bitflags! {
 		///
 		/// See [`kDNSServiceFlagsAdd`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsadd).
 		const ADD = ffi::FLAGS_ADD;
+
+		/// The record expired and is being refreshed (only with
+		/// [`ALLOW_EXPIRED_ANSWERS`]).
+		///
+		/// See [`kDNSServiceFlagsExpiredAnswer`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsexpiredanswer).
+		///
+		/// [`ALLOW_EXPIRED_ANSWERS`]: struct.QueryRecordFlags.html#associatedconstant.ALLOW_EXPIRED_ANSWERS
+		const EXPIRED_ANSWER = ffi::FLAGS_EXPIRED_ANSWER;
 	}
 }
 
 /// Pending query
 #[must_use = "streams do nothing unless polled"]
 pub struct QueryRecord {
-	stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>,
+	stream: crate::deadline::Deadline<
+		crate::limit::Limit<crate::fused_err_stream::FusedErrorStream<CallbackStream>>,
+	>,
+	rr_type: Type,
 }
 
 impl QueryRecord {
-	pin_utils::unsafe_pinned!(stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>);
+	/// Wait for the next result; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+
+	/// Deliver results as [`QueryEvent`]s, telling negative answers
+	/// apart from records
+	///
+	/// [`QueryEvent`]: enum.QueryEvent.html
+	pub fn events(self) -> QueryEvents {
+		QueryEvents { query: self }
+	}
+
+	// whether the result states no record of the queried type exists
+	fn is_negative(&self, result: &QueryRecordResult) -> bool {
+		result.negative || (result.rr_type == Type::NSEC && self.rr_type != Type::NSEC)
+	}
+
+	// target of a CNAME record followed for the query
+	fn cname_target(&self, result: &QueryRecordResult) -> Option<String> {
+		if result.rr_type != Type::CNAME || self.rr_type == Type::CNAME {
+			return None;
+		}
+		async_dnssd_types::rdata::parse_name(&result.rdata)
+	}
 }
 
 impl futures_core::Stream for QueryRecord {
 	type Item = io::Result<QueryRecordResult>;
 
-	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		self.stream().poll_next(cx)
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for QueryRecord {
+	fn is_terminated(&self) -> bool {
+		self.stream.is_terminated()
+	}
+}
+
+/// Pending query for a single record
+///
+/// Created by [`query_record_once`](fn.query_record_once.html).
+#[must_use = "futures do nothing unless polled"]
+pub struct QueryRecordOnce {
+	query: Option<QueryRecord>,
+}
+
+impl Future for QueryRecordOnce {
+	type Output = io::Result<QueryRecordResult>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let query = self
+			.query
+			.as_mut()
+			.expect("cannot poll QueryRecordOnce twice");
+		loop {
+			match futures_core::ready!(query.poll_next_unpin(cx)) {
+				Some(Ok(result)) if query.is_negative(&result) => {
+					self.query = None;
+					return Poll::Ready(Err(io::Error::new(
+						io::ErrorKind::NotFound,
+						"record doesn't exist",
+					)));
+				},
+				Some(Ok(result)) if query.cname_target(&result).is_some() => (), // ignore aliases
+				Some(Ok(result)) if result.flags.contains(QueriedRecordFlags::ADD) => {
+					// stop query right away
+					self.query = None;
+					return Poll::Ready(Ok(result));
+				},
+				Some(Ok(_)) => (), // ignore removals
+				Some(Err(e)) => {
+					self.query = None;
+					return Poll::Ready(Err(e));
+				},
+				None => {
+					let kind = if query.stream.timed_out() {
+						io::ErrorKind::TimedOut
+					} else {
+						io::ErrorKind::UnexpectedEof
+					};
+					self.query = None;
+					return Poll::Ready(Err(io::Error::new(kind, "query ended without result")));
+				},
+			}
+		}
+	}
+}
+
+/// Pending query for the current record set
+///
+/// Created by [`query_record_snapshot`](fn.query_record_snapshot.html).
+#[must_use = "futures do nothing unless polled"]
+pub struct QueryRecordSnapshot<C: Clock = TokioClock> {
+	query: Option<QueryRecord>,
+	collector: Quiescence<QueryRecordResult, C>,
+}
+
+impl<C: Clock + Unpin> Future for QueryRecordSnapshot<C> {
+	type Output = io::Result<Vec<QueryRecordResult>>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = &mut *self;
+		let query = this
+			.query
+			.as_mut()
+			.expect("cannot poll QueryRecordSnapshot twice");
+		loop {
+			let mut result = match query.poll_next_unpin(cx) {
+				Poll::Ready(Some(Ok(result))) => result,
+				Poll::Ready(Some(Err(e))) => {
+					this.query = None;
+					this.collector.finish();
+					return Poll::Ready(Err(e));
+				},
+				// timeout from `QueryRecordData` or broken query: return what we have
+				Poll::Ready(None) => break,
+				Poll::Pending => {
+					futures_core::ready!(this.collector.poll_done(cx));
+					break;
+				},
+			};
+			let more_coming = result.flags.contains(QueriedRecordFlags::MORE_COMING);
+			if !query.is_negative(&result) && query.cname_target(&result).is_none() {
+				if result.flags.contains(QueriedRecordFlags::ADD) {
+					result.flags.remove(QueriedRecordFlags::MORE_COMING);
+					this.collector.add(result, crate::service::same_record);
+				} else {
+					this.collector.remove(&result, crate::service::same_record);
+				}
+			}
+			this.collector.seen(more_coming);
+		}
+		this.query = None;
+		Poll::Ready(Ok(this.collector.finish()))
+	}
+}
+
+/// Event of a query created with [`QueryRecord::events`]
+///
+/// [`QueryRecord::events`]: struct.QueryRecord.html#method.events
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum QueryEvent {
+	/// A record was added or removed
+	Record(QueryRecordResult),
+	/// No record of type `covers` exists (only with
+	/// [`RETURN_INTERMEDIATES`](struct.QueryRecordFlags.html#associatedconstant.RETURN_INTERMEDIATES));
+	/// `result` has either empty `rdata` or is the NSEC record proving
+	/// it.
+	NegativeAnswer {
+		/// the queried type
+		covers: Type,
+		/// the answer as delivered by the daemon
+		result: QueryRecordResult,
+	},
+	/// A CNAME record followed for the query was added or removed (only
+	/// with [`QueryRecordData::return_cnames`])
+	///
+	/// [`QueryRecordData::return_cnames`]: struct.QueryRecordData.html#structfield.return_cnames
+	Cname {
+		/// the name the alias (`result.fullname`) points to
+		target: String,
+		/// the CNAME record
+		result: QueryRecordResult,
+	},
+}
+
+/// Query delivering [`QueryEvent`](enum.QueryEvent.html)s
+///
+/// Created by [`QueryRecord::events`](struct.QueryRecord.html#method.events).
+#[must_use = "streams do nothing unless polled"]
+pub struct QueryEvents {
+	query: QueryRecord,
+}
+
+impl futures_core::Stream for QueryEvents {
+	type Item = io::Result<QueryEvent>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let result = match futures_core::ready!(self.query.poll_next_unpin(cx)) {
+			Some(Ok(result)) => result,
+			Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+			None => return Poll::Ready(None),
+		};
+		let event = if self.query.is_negative(&result) {
+			QueryEvent::NegativeAnswer {
+				covers: self.query.rr_type,
+				result,
+			}
+		} else if let Some(target) = self.query.cname_target(&result) {
+			QueryEvent::Cname { target, result }
+		} else {
+			QueryEvent::Record(result)
+		};
+		Poll::Ready(Some(Ok(event)))
+	}
+}
+
+impl FusedStream for QueryEvents {
+	fn is_terminated(&self) -> bool {
+		self.query.is_terminated()
 	}
 }
 
@@ -86,10 +401,112 @@ pub struct QueryRecordResult {
 	pub rr_type: Type,
 	/// class of record
 	pub rr_class: Class,
-	/// wire RDATA of record
-	pub rdata: Vec<u8>,
+	/// wire RDATA of record (cheap to clone)
+	pub rdata: bytes::Bytes,
 	/// TTL (time to live) of record
 	pub ttl: u32,
+	/// The daemon reported no record of the queried type exists (only
+	/// with [`RETURN_INTERMEDIATES`]); `rdata` is empty.
+	///
+	/// Negative answers delivered as NSEC records don't set this; use
+	/// [`QueryRecord::events`] to handle both.
+	///
+	/// [`RETURN_INTERMEDIATES`]: struct.QueryRecordFlags.html#associatedconstant.RETURN_INTERMEDIATES
+	/// [`QueryRecord::events`]: struct.QueryRecord.html#method.events
+	pub negative: bool,
+}
+
+impl QueryRecordResult {
+	/// Whether the daemon reported no record of the queried type exists
+	///
+	/// Same as [`negative`](#structfield.negative); only reported with
+	/// [`RETURN_INTERMEDIATES`].
+	///
+	/// [`RETURN_INTERMEDIATES`]: struct.QueryRecordFlags.html#associatedconstant.RETURN_INTERMEDIATES
+	pub fn is_negative(&self) -> bool {
+		self.negative
+	}
+
+	/// Parse the record data (see [`Rdata::parse`])
+	///
+	/// Returns `None` for negative answers and invalid data.
+	///
+	/// [`Rdata::parse`]: enum.Rdata.html#method.parse
+	pub fn parse_rdata(&self) -> Option<Rdata> {
+		if self.negative {
+			return None;
+		}
+		Rdata::parse(self.rr_type, &self.rdata)
+	}
+
+	/// DNSSEC validation result (only with [`VALIDATE`] or
+	/// [`VALIDATE_OPTIONAL`])
+	///
+	/// [`VALIDATE`]: struct.QueryRecordFlags.html#associatedconstant.VALIDATE
+	/// [`VALIDATE_OPTIONAL`]: struct.QueryRecordFlags.html#associatedconstant.VALIDATE_OPTIONAL
+	pub fn dnssec_status(&self) -> Option<DnssecStatus> {
+		DnssecStatus::from_flags(self.flags.bits())
+	}
+}
+
+/// DNSSEC validation result of an answer
+///
+/// See [`kDNSServiceFlagsSecure`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagssecure)
+/// and the following flags.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DnssecStatus {
+	/// The chain of trust to a trust anchor was validated
+	Secure,
+	/// The answer is provably not signed
+	Insecure,
+	/// Validation failed (the answer might have been tampered with)
+	Bogus,
+	/// Validation couldn't be completed
+	Indeterminate,
+}
+
+impl DnssecStatus {
+	pub(crate) fn from_flags(flags: ffi::DNSServiceFlags) -> Option<Self> {
+		// the status bits are only meaningful combined with the validate bit
+		let status =
+			ffi::FLAGS_SECURE | ffi::FLAGS_INSECURE | ffi::FLAGS_BOGUS | ffi::FLAGS_INDETERMINATE;
+		match flags & status {
+			ffi::FLAGS_SECURE => Some(Self::Secure),
+			ffi::FLAGS_INSECURE => Some(Self::Insecure),
+			ffi::FLAGS_BOGUS => Some(Self::Bogus),
+			ffi::FLAGS_INDETERMINATE => Some(Self::Indeterminate),
+			_ => None,
+		}
+	}
+}
+
+/// One line summary like `+ printer.local. 120 IN A (4 bytes) (en0)`
+impl fmt::Display for QueryRecordResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sign = if self.flags.contains(QueriedRecordFlags::ADD) {
+			'+'
+		} else {
+			'-'
+		};
+		write!(
+			f,
+			"{} {} {} {} {} ({} bytes)",
+			sign,
+			self.fullname,
+			self.ttl,
+			self.rr_class,
+			self.rr_type,
+			self.rdata.len()
+		)?;
+		if self.negative {
+			f.write_str(" (no such record)")?;
+		}
+		if self.interface != Interface::Any {
+			write!(f, " ({})", self.interface)?;
+		}
+		Ok(())
+	}
 }
 
 unsafe extern "C" fn query_record_callback(
@@ -105,18 +522,32 @@ unsafe extern "C" fn query_record_callback(
 	ttl: u32,
 	context: *mut c_void,
 ) {
+	// negative answers (with `kDNSServiceFlagsReturnIntermediates`) are
+	// reported as "error", but the query continues
+	let negative = error_code == ffi::DNSServiceError::NoSuchRecord as ffi::DNSServiceErrorType;
+	let error_code = if negative {
+		ffi::DNSServiceNoError::NoError as ffi::DNSServiceErrorType
+	} else {
+		error_code
+	};
 	CallbackStream::run_callback(context, error_code, || {
-		let fullname = cstr::from_cstr(fullname)?;
-		let rdata = ::std::slice::from_raw_parts(rdata, rd_len as usize);
+		let fullname = cstr::from_cstr_protocol_name(fullname)?;
+		// negative answers might not pass any rdata
+		let rdata: &[u8] = if rd_len == 0 {
+			&[]
+		} else {
+			::std::slice::from_raw_parts(rdata, rd_len as usize)
+		};
 
 		Ok(QueryRecordResult {
-			flags: QueriedRecordFlags::from_bits_truncate(flags),
+			flags: QueriedRecordFlags::from_bits_retain(flags),
 			interface: Interface::from_raw(interface_index),
 			fullname: fullname.to_string(),
 			rr_type: Type(rr_type),
 			rr_class: Class(rr_class),
-			rdata: rdata.into(),
+			rdata: bytes::Bytes::copy_from_slice(rdata),
 			ttl,
+			negative,
 		})
 	});
 }
@@ -140,6 +571,25 @@ pub struct QueryRecordData {
 	pub interface: Interface,
 	/// class of the resource record (default: `IN`)
 	pub rr_class: Class,
+	/// end the operation after the given time (default: never)
+	///
+	/// The stream simply ends (without an error) and the operation is
+	/// stopped right away.
+	pub timeout: Option<Duration>,
+	/// also deliver the CNAME records followed to find the queried
+	/// records (default: false); see [`QueryEvent::Cname`]
+	///
+	/// Uses `kDNSServiceFlagsReturnIntermediates`, or
+	/// `kDNSServiceFlagsReturnCNAME` with the avahi compat layer.
+	///
+	/// [`QueryEvent::Cname`]: enum.QueryEvent.html#variant.Cname
+	pub return_cnames: bool,
+	/// end the operation after the given number of found records
+	/// (default: no limit)
+	///
+	/// Only results with `ADD` set count; the stream ends right after
+	/// the last one and the operation is stopped.
+	pub max_results: Option<usize>,
 	#[doc(hidden)]
 	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 }
@@ -150,23 +600,45 @@ impl Default for QueryRecordData {
 			flags: QueryRecordFlags::default(),
 			interface: Interface::default(),
 			rr_class: Class::IN,
+			timeout: None,
+			return_cnames: false,
+			max_results: None,
 			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 		}
 	}
 }
 
+fn counts_query_result(item: &io::Result<QueryRecordResult>) -> bool {
+	matches!(item, Ok(result) if result.flags.contains(QueriedRecordFlags::ADD))
+}
+
 fn _query_record_extended(
+	connection: Option<&inner::SharedService>,
 	fullname: &str,
 	rr_type: Type,
 	data: QueryRecordData,
 ) -> io::Result<QueryRecord> {
 	crate::init();
 
+	let operation = format!("query_record {} {}", fullname, rr_type);
 	let fullname = cstr::CStr::from(&fullname)?;
+	let mut flags = data.flags.bits();
+	if data.return_cnames {
+		flags |= if cfg!(dnssd_avahi) {
+			ffi::FLAGS_RETURN_CNAME
+		} else {
+			ffi::FLAGS_RETURN_INTERMEDIATES
+		};
+	}
 
-	let stream = CallbackStream::new(move |sender| {
-		inner::OwnedService::query_record(
-			data.flags.bits(),
+	let stream = CallbackStream::new(operation, move |sender| {
+		inner::StreamService::query_record(
+			connection,
+			crate::compat::filter_flags(
+				"DNSServiceQueryRecord",
+				flags,
+				ffi::FLAGS_FORCE_MULTICAST | ffi::FLAGS_RETURN_CNAME,
+			),
 			data.interface.into_raw(),
 			&fullname,
 			rr_type,
@@ -177,7 +649,13 @@ fn _query_record_extended(
 	})
 	.into();
 
-	Ok(QueryRecord { stream })
+	Ok(QueryRecord {
+		stream: crate::deadline::Deadline::new(
+			crate::limit::Limit::new(stream, data.max_results, counts_query_result),
+			data.timeout,
+		),
+		rr_type,
+	})
 }
 
 /// Query for an arbitrary DNS record
@@ -185,10 +663,24 @@ fn _query_record_extended(
 /// See [`DNSServiceQueryRecord`](https://developer.apple.com/documentation/dnssd/1804747-dnsservicequeryrecord).
 #[doc(alias = "DNSServiceQueryRecord")]
 pub fn query_record_extended(fullname: &str, rr_type: Type, data: QueryRecordData) -> QueryRecord {
-	match _query_record_extended(fullname, rr_type, data) {
+	query_record_on(None, fullname, rr_type, data)
+}
+
+// query on the given connection (or the default one)
+pub(crate) fn query_record_on(
+	connection: Option<&inner::SharedService>,
+	fullname: &str,
+	rr_type: Type,
+	data: QueryRecordData,
+) -> QueryRecord {
+	match _query_record_extended(connection, fullname, rr_type, data) {
 		Ok(qr) => qr,
 		Err(e) => QueryRecord {
-			stream: Err(e).into(),
+			stream: crate::deadline::Deadline::new(
+				crate::limit::Limit::new(Err(e).into(), None, counts_query_result),
+				None,
+			),
+			rr_type,
 		},
 	}
 }
@@ -205,3 +697,126 @@ pub fn query_record_extended(fullname: &str, rr_type: Type, data: QueryRecordDat
 pub fn query_record(fullname: &str, rr_type: Type) -> QueryRecord {
 	query_record_extended(fullname, rr_type, QueryRecordData::default())
 }
+
+/// Query for the first record matching name and type
+///
+/// Completes with the first added record and stops the query right
+/// away; set [`QueryRecordData::timeout`] as the query might never find
+/// anything (the future then fails with `TimedOut`).  Negative answers
+/// fail with `NotFound`.
+///
+/// See [`DNSServiceQueryRecord`](https://developer.apple.com/documentation/dnssd/1804747-dnsservicequeryrecord).
+///
+/// [`QueryRecordData::timeout`]: struct.QueryRecordData.html#structfield.timeout
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn query_record_once(fullname: &str, rr_type: Type, data: QueryRecordData) -> QueryRecordOnce {
+	QueryRecordOnce {
+		query: Some(query_record_extended(fullname, rr_type, data)),
+	}
+}
+
+/// Query for the current records matching name and type
+///
+/// Collects results until no new results arrived for `quiesce` (and
+/// the daemon doesn't announce more with `MORE_COMING`), but at most
+/// for `max_wait`, then completes with the collected records and stops
+/// the query.  If nothing is found the future completes with an empty
+/// list after `quiesce`.
+///
+/// Negative answers and aliases (see [`QueryEvent`]) aren't included;
+/// if the query ends early (e.g. by [`QueryRecordData::timeout`]) the
+/// records found so far are returned.
+///
+/// Needs to be polled within a tokio runtime.
+///
+/// [`QueryEvent`]: enum.QueryEvent.html
+/// [`QueryRecordData::timeout`]: struct.QueryRecordData.html#structfield.timeout
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn query_record_snapshot_extended(
+	fullname: &str,
+	rr_type: Type,
+	data: QueryRecordData,
+	quiesce: Duration,
+	max_wait: Duration,
+) -> QueryRecordSnapshot {
+	query_record_snapshot_with_clock(fullname, rr_type, data, quiesce, max_wait, TokioClock)
+}
+
+/// Query for the current records matching name and type
+///
+/// Same as [`query_record_snapshot_extended`], using the given
+/// [`Clock`](trait.Clock.html) for `quiesce` and `max_wait`.
+///
+/// [`query_record_snapshot_extended`]: fn.query_record_snapshot_extended.html
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn query_record_snapshot_with_clock<C: Clock>(
+	fullname: &str,
+	rr_type: Type,
+	data: QueryRecordData,
+	quiesce: Duration,
+	max_wait: Duration,
+	clock: C,
+) -> QueryRecordSnapshot<C> {
+	QueryRecordSnapshot {
+		query: Some(query_record_extended(fullname, rr_type, data)),
+		collector: Quiescence::new(quiesce, max_wait, clock),
+	}
+}
+
+/// Query for the current records matching name and type
+///
+/// Uses [`query_record_snapshot_extended`] with default
+/// [`QueryRecordData`].
+///
+/// [`query_record_snapshot_extended`]: fn.query_record_snapshot_extended.html
+/// [`QueryRecordData`]: struct.QueryRecordData.html
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn query_record_snapshot(
+	fullname: &str,
+	rr_type: Type,
+	quiesce: Duration,
+	max_wait: Duration,
+) -> QueryRecordSnapshot {
+	query_record_snapshot_extended(
+		fullname,
+		rr_type,
+		QueryRecordData::default(),
+		quiesce,
+		max_wait,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		DnssecStatus,
+		QueryRecordFlags,
+	};
+	use crate::ffi;
+
+	#[test]
+	fn validate_flag_bits() {
+		// values from dns_sd.h
+		assert_eq!(QueryRecordFlags::VALIDATE.bits(), 0x200000);
+		assert_eq!(QueryRecordFlags::VALIDATE_OPTIONAL.bits(), 0x800000);
+		assert_eq!(QueryRecordFlags::UNICAST_RESPONSE.bits(), 0x400000);
+	}
+
+	#[test]
+	fn dnssec_status() {
+		assert_eq!(
+			DnssecStatus::from_flags(ffi::FLAGS_ADD | ffi::FLAGS_SECURE),
+			Some(DnssecStatus::Secure)
+		);
+		assert_eq!(
+			DnssecStatus::from_flags(ffi::FLAGS_BOGUS),
+			Some(DnssecStatus::Bogus)
+		);
+		// same bits as `kDNSServiceFlagsShared` without the validate bit
+		assert_eq!(DnssecStatus::from_flags(ffi::FLAGS_ADD | 0x10), None);
+		assert_eq!(
+			DnssecStatus::from_flags(ffi::FLAGS_SECURE | ffi::FLAGS_BOGUS),
+			None
+		);
+	}
+}
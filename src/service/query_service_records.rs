@@ -0,0 +1,330 @@
+use async_dnssd_types::rdata;
+use futures_util::StreamExt;
+use std::{
+	fmt,
+	io,
+	net::{
+		Ipv4Addr,
+		Ipv6Addr,
+	},
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	dns_consts::{
+		Class,
+		Type,
+	},
+	interface::Interface,
+	service::{
+		query_record_extended,
+		QueriedRecordFlags,
+		QueryRecord,
+		QueryRecordData,
+		QueryRecordResult,
+	},
+	txt_record::TxtRecord,
+};
+
+/// Decoded data of a [`ServiceRecord`](struct.ServiceRecord.html)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ServiceRecordData {
+	/// `SRV` record of the service
+	Srv {
+		/// priority of the target host
+		priority: u16,
+		/// relative weight for entries with the same priority
+		weight: u16,
+		/// port the service is provided on
+		port: u16,
+		/// hostname the service is provided on
+		target: String,
+	},
+	/// `TXT` record of the service
+	Txt(TxtRecord),
+	/// `A` record of a `SRV` target
+	A(Ipv4Addr),
+	/// `AAAA` record of a `SRV` target
+	Aaaa(Ipv6Addr),
+}
+
+/// Result of [`query_service_records`](fn.query_service_records.html)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ServiceRecord {
+	/// raw record (including flags and TTL)
+	pub record: QueryRecordResult,
+	/// decoded record data
+	pub data: ServiceRecordData,
+}
+
+impl ServiceRecord {
+	fn decode(record: QueryRecordResult) -> Option<Self> {
+		if record.rr_class != Class::IN {
+			return None;
+		}
+		let rdata = &record.rdata;
+		let data = match record.rr_type {
+			Type::SRV => {
+				let srv = rdata::Srv::parse(rdata)?;
+				ServiceRecordData::Srv {
+					priority: srv.priority,
+					weight: srv.weight,
+					port: srv.port,
+					target: srv.target,
+				}
+			},
+			Type::TXT => ServiceRecordData::Txt(TxtRecord::parse(rdata)?),
+			Type::A => ServiceRecordData::A(rdata::parse_a(rdata)?),
+			Type::AAAA => ServiceRecordData::Aaaa(rdata::parse_aaaa(rdata)?),
+			_ => return None,
+		};
+		Some(Self { record, data })
+	}
+}
+
+struct AddressQuery {
+	interface: Interface,
+	target: String,
+	// (priority, weight, port) of the SRV records pointing to the
+	// target; the daemon repeats ADD for refreshed records
+	srvs: Vec<(u16, u16, u16)>,
+	a: QueryRecord,
+	aaaa: QueryRecord,
+}
+
+/// Pending query for the records of a service
+#[must_use = "streams do nothing unless polled"]
+pub struct QueryServiceRecords {
+	data: QueryRecordData,
+	srv: QueryRecord,
+	txt: QueryRecord,
+	addresses: Vec<AddressQuery>,
+}
+
+impl QueryServiceRecords {
+	fn follow_srv(&mut self, record: &ServiceRecord) {
+		let (srv, target) = match &record.data {
+			ServiceRecordData::Srv {
+				priority,
+				weight,
+				port,
+				target,
+			} => ((*priority, *weight, *port), target),
+			_ => return,
+		};
+		let interface = record.record.interface;
+		let pos = self
+			.addresses
+			.iter()
+			.position(|q| q.interface == interface && q.target == *target);
+		if record.record.flags.contains(QueriedRecordFlags::ADD) {
+			match pos {
+				Some(pos) => {
+					let srvs = &mut self.addresses[pos].srvs;
+					if !srvs.contains(&srv) {
+						srvs.push(srv);
+					}
+				},
+				None => {
+					// the whole stream ends with the SRV query
+					let data = QueryRecordData {
+						interface,
+						timeout: None,
+						..self.data
+					};
+					self.addresses.push(AddressQuery {
+						interface,
+						target: target.clone(),
+						srvs: vec![srv],
+						a: query_record_extended(target, Type::A, data),
+						aaaa: query_record_extended(target, Type::AAAA, data),
+					});
+				},
+			}
+		} else if let Some(pos) = pos {
+			let query = &mut self.addresses[pos];
+			query.srvs.retain(|s| *s != srv);
+			if query.srvs.is_empty() {
+				self.addresses.remove(pos);
+			}
+		}
+	}
+
+	fn poll_record(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<QueryRecordResult>>> {
+		let addresses = self
+			.addresses
+			.iter_mut()
+			.flat_map(|q| [&mut q.a, &mut q.aaaa]);
+		if let Poll::Ready(item) = self.srv.poll_next_unpin(cx) {
+			// the whole stream ends with the SRV query
+			return Poll::Ready(item);
+		}
+		for query in std::iter::once(&mut self.txt).chain(addresses) {
+			match query.poll_next_unpin(cx) {
+				// other queries might end early (after errors); don't end
+				// the whole stream for them
+				Poll::Ready(None) | Poll::Pending => (),
+				Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+			}
+		}
+		Poll::Pending
+	}
+}
+
+impl fmt::Debug for QueryServiceRecords {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let targets: Vec<_> = self
+			.addresses
+			.iter()
+			.map(|q| (q.interface, &q.target))
+			.collect();
+		f.debug_struct("QueryServiceRecords")
+			.field("data", &self.data)
+			.field("targets", &targets)
+			.finish_non_exhaustive()
+	}
+}
+
+impl futures_core::Stream for QueryServiceRecords {
+	type Item = io::Result<ServiceRecord>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+		loop {
+			let record = match futures_core::ready!(this.poll_record(cx)) {
+				Some(Ok(record)) => record,
+				Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+				None => return Poll::Ready(None),
+			};
+			match ServiceRecord::decode(record) {
+				Some(record) => {
+					this.follow_srv(&record);
+					return Poll::Ready(Some(Ok(record)));
+				},
+				None => log::debug!("ignoring invalid service record"),
+			}
+		}
+	}
+}
+
+/// Query `SRV` and `TXT` records of a service, and `A` and `AAAA`
+/// records of the `SRV` targets
+///
+/// Like [`resolve`] and [`ResolveResult::resolve_socket_address`], but
+/// yields the individual records, including their flags and TTLs.
+///
+/// Address queries are started for each `SRV` target (on the interface
+/// the `SRV` record was found on) and stopped once no `SRV` record points
+/// to the target anymore; addresses of stopped queries aren't reported as
+/// removed.
+///
+/// The `interface` in `data` only applies to the `SRV` and `TXT` queries.
+///
+/// [`resolve`]: fn.resolve.html
+/// [`ResolveResult::resolve_socket_address`]: struct.ResolveResult.html#method.resolve_socket_address
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn query_service_records_extended(
+	fullname: &str,
+	data: QueryRecordData,
+) -> QueryServiceRecords {
+	let data = QueryRecordData {
+		rr_class: Class::IN,
+		..data
+	};
+	QueryServiceRecords {
+		data,
+		srv: query_record_extended(fullname, Type::SRV, data),
+		txt: query_record_extended(fullname, Type::TXT, data),
+		addresses: Vec::new(),
+	}
+}
+
+/// Query `SRV` and `TXT` records of a service, and `A` and `AAAA`
+/// records of the `SRV` targets
+///
+/// Uses [`query_service_records_extended`] with default
+/// [`QueryRecordData`].
+///
+/// [`query_service_records_extended`]: fn.query_service_records_extended.html
+/// [`QueryRecordData`]: struct.QueryRecordData.html
+#[doc(alias = "DNSServiceQueryRecord")]
+pub fn query_service_records(fullname: &str) -> QueryServiceRecords {
+	query_service_records_extended(fullname, QueryRecordData::default())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn srv(
+		flags: QueriedRecordFlags,
+		interface: u32,
+		priority: u16,
+		target: &str,
+	) -> ServiceRecord {
+		ServiceRecord {
+			record: QueryRecordResult {
+				flags,
+				interface: Interface::from_raw(interface),
+				fullname: "Printer._ipp._tcp.local.".to_string(),
+				rr_type: Type::SRV,
+				rr_class: Class::IN,
+				rdata: bytes::Bytes::new(),
+				ttl: 120,
+				negative: false,
+			},
+			data: ServiceRecordData::Srv {
+				priority,
+				weight: 0,
+				port: 631,
+				target: target.to_string(),
+			},
+		}
+	}
+
+	fn targets(query: &QueryServiceRecords) -> Vec<(u32, &str, usize)> {
+		query
+			.addresses
+			.iter()
+			.map(|q| (q.interface.into_raw(), q.target.as_str(), q.srvs.len()))
+			.collect()
+	}
+
+	#[tokio::test]
+	async fn follow_srv() {
+		let add = QueriedRecordFlags::ADD;
+		let remove = QueriedRecordFlags::empty();
+		// the queries fail without a daemon; only the bookkeeping matters
+		let mut query = query_service_records("Printer._ipp._tcp.local.");
+
+		// two SRV records pointing to the same target share the queries
+		query.follow_srv(&srv(add, 1, 0, "printer.local."));
+		query.follow_srv(&srv(add, 1, 10, "printer.local."));
+		query.follow_srv(&srv(add, 2, 0, "printer.local."));
+		// refreshed records are reported with ADD again
+		query.follow_srv(&srv(add, 1, 0, "printer.local."));
+		assert_eq!(
+			targets(&query),
+			[(1, "printer.local.", 2), (2, "printer.local.", 1)]
+		);
+
+		query.follow_srv(&srv(remove, 1, 0, "printer.local."));
+		assert_eq!(
+			targets(&query),
+			[(1, "printer.local.", 1), (2, "printer.local.", 1)]
+		);
+		query.follow_srv(&srv(remove, 1, 10, "printer.local."));
+		assert_eq!(targets(&query), [(2, "printer.local.", 1)]);
+
+		// removals of unknown targets and non-SRV records are ignored
+		query.follow_srv(&srv(remove, 1, 0, "other.local."));
+		let mut txt = srv(add, 1, 0, "printer.local.");
+		txt.data = ServiceRecordData::Txt(TxtRecord::new());
+		query.follow_srv(&txt);
+		assert_eq!(targets(&query), [(2, "printer.local.", 1)]);
+	}
+}
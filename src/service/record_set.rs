@@ -0,0 +1,263 @@
+use std::{
+	future::Future,
+	io,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+};
+
+use crate::{
+	dns_consts::{
+		Class,
+		Type,
+	},
+	interface::Interface,
+	service::{
+		RegisterRecordData,
+		RegisterRecordFlags,
+	},
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct RecordSetEntry {
+	pub(crate) fullname: String,
+	pub(crate) rr_type: Type,
+	pub(crate) rdata: Vec<u8>,
+	pub(crate) data: RegisterRecordData,
+}
+
+impl RecordSetEntry {
+	pub(crate) fn key(&self) -> RecordKey {
+		RecordKey::new(&self.fullname, self.rr_type, &self.data)
+	}
+}
+
+/// Desired set of records for
+/// [`Connection::apply`](struct.Connection.html#method.apply)
+///
+/// Records are identified by full name (ignoring ASCII case), type,
+/// class, interface and flags; multiple records with the same identity
+/// (but different rdata) form a record set in the DNS sense.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RecordSet {
+	entries: Vec<RecordSetEntry>,
+}
+
+impl RecordSet {
+	/// Empty set
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add record with default [`RegisterRecordData`]
+	///
+	/// [`RegisterRecordData`]: struct.RegisterRecordData.html
+	pub fn insert(&mut self, fullname: &str, rr_type: Type, rdata: &[u8]) {
+		self.insert_extended(fullname, rr_type, rdata, RegisterRecordData::default())
+	}
+
+	/// Add record; exact duplicates are ignored
+	///
+	/// [`RegisterRecordData::drop_policy`] only applies once the
+	/// [`Connection`](struct.Connection.html) is gone.  Invalid records
+	/// (e.g. `rdata` longer than 65535 bytes) fail in
+	/// [`Connection::apply`](struct.Connection.html#method.apply).
+	///
+	/// [`RegisterRecordData::drop_policy`]: struct.RegisterRecordData.html#structfield.drop_policy
+	pub fn insert_extended(
+		&mut self,
+		fullname: &str,
+		rr_type: Type,
+		rdata: &[u8],
+		data: RegisterRecordData,
+	) {
+		let entry = RecordSetEntry {
+			fullname: fullname.to_string(),
+			rr_type,
+			rdata: rdata.to_vec(),
+			data,
+		};
+		if !self.entries.contains(&entry) {
+			self.entries.push(entry);
+		}
+	}
+
+	/// Number of records
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Whether the set is empty
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Remove all records
+	pub fn clear(&mut self) {
+		self.entries.clear();
+	}
+
+	pub(crate) fn entries(&self) -> &[RecordSetEntry] {
+		&self.entries
+	}
+}
+
+/// What [`Connection::apply`](struct.Connection.html#method.apply)
+/// changed
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[non_exhaustive]
+pub struct RecordSetChanges {
+	/// newly registered records
+	pub added: usize,
+	/// records updated in place (new rdata or TTL)
+	pub updated: usize,
+	/// removed records
+	pub removed: usize,
+	/// records left alone
+	pub unchanged: usize,
+}
+
+/// Pending [`Connection::apply`](struct.Connection.html#method.apply)
+#[must_use = "futures do nothing unless polled"]
+pub struct ApplyRecordSet<'a> {
+	pub(crate) inner: Pin<Box<dyn Future<Output = io::Result<RecordSetChanges>> + Send + 'a>>,
+}
+
+impl Future for ApplyRecordSet<'_> {
+	type Output = io::Result<RecordSetChanges>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.inner.as_mut().poll(cx)
+	}
+}
+
+// records with the same key can be updated into each other
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct RecordKey {
+	fullname: String,
+	rr_type: Type,
+	rr_class: Class,
+	interface: Interface,
+	flags: RegisterRecordFlags,
+}
+
+impl RecordKey {
+	pub(crate) fn new(fullname: &str, rr_type: Type, data: &RegisterRecordData) -> Self {
+		let fullname = fullname.strip_suffix('.').unwrap_or(fullname);
+		Self {
+			fullname: fullname.to_ascii_lowercase(),
+			rr_type,
+			rr_class: data.rr_class,
+			interface: data.interface,
+			flags: data.flags,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RecordChange {
+	Keep { existing: usize },
+	Update { existing: usize, desired: usize },
+	Remove { existing: usize },
+	Add { desired: usize },
+}
+
+// match desired records against existing ones: same rdata first (kept or
+// TTL updated), then any record with the same key (updated in place)
+pub(crate) fn diff_records(
+	existing: &[(RecordKey, &[u8], u32)],
+	desired: &[(RecordKey, &[u8], u32)],
+) -> Vec<RecordChange> {
+	let mut used = vec![false; existing.len()];
+	let mut matched = vec![None; desired.len()];
+	for (d, (key, rdata, _)) in desired.iter().enumerate() {
+		let found = existing
+			.iter()
+			.enumerate()
+			.position(|(e, (k, r, _))| !used[e] && k == key && r == rdata);
+		if let Some(e) = found {
+			used[e] = true;
+			matched[d] = Some(e);
+		}
+	}
+	for (d, (key, _, _)) in desired.iter().enumerate() {
+		if matched[d].is_some() {
+			continue;
+		}
+		let found = existing
+			.iter()
+			.enumerate()
+			.position(|(e, (k, _, _))| !used[e] && k == key);
+		if let Some(e) = found {
+			used[e] = true;
+			matched[d] = Some(e);
+		}
+	}
+
+	let mut changes = Vec::new();
+	for (e, used) in used.iter().enumerate() {
+		if !used {
+			changes.push(RecordChange::Remove { existing: e });
+		}
+	}
+	for (d, matched) in matched.iter().enumerate() {
+		match *matched {
+			Some(e) if existing[e].1 == desired[d].1 && existing[e].2 == desired[d].2 => {
+				changes.push(RecordChange::Keep { existing: e })
+			},
+			Some(e) => changes.push(RecordChange::Update {
+				existing: e,
+				desired: d,
+			}),
+			None => changes.push(RecordChange::Add { desired: d }),
+		}
+	}
+	changes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn minimal_changes() {
+		let data = RegisterRecordData::default();
+		let a = RecordKey::new("host.local.", Type::A, &data);
+		let ptr = RecordKey::new("_http._tcp.local", Type::PTR, &data);
+		let txt = RecordKey::new("Host.local", Type::TXT, &data);
+		let existing = [
+			(a.clone(), &[10, 0, 0, 1][..], 120),
+			(ptr.clone(), b"one", 4500),
+			(ptr.clone(), b"two", 4500),
+			(txt.clone(), b"\0", 4500),
+		];
+		let desired = [
+			(ptr.clone(), &b"two"[..], 4500),
+			(a.clone(), &[10, 0, 0, 2][..], 120),
+			(txt.clone(), b"\0", 60),
+			(ptr.clone(), b"three", 4500),
+			(ptr.clone(), b"four", 4500),
+		];
+		assert_eq!(
+			diff_records(&existing, &desired),
+			[
+				RecordChange::Keep { existing: 2 },
+				RecordChange::Update {
+					existing: 0,
+					desired: 1
+				},
+				RecordChange::Update {
+					existing: 3,
+					desired: 2
+				},
+				RecordChange::Update {
+					existing: 1,
+					desired: 3
+				},
+				RecordChange::Add { desired: 4 },
+			]
+		);
+	}
+}
@@ -1,10 +1,88 @@
 use std::io;
 
 use crate::{
-	dns_consts::Type,
+	dns_consts::{
+		Class,
+		Type,
+	},
+	ffi,
 	inner,
+	service::Deregister,
 };
 
+bitflags::bitflags! {
+	/// Flags used to update a record
+	///
+	/// The daemons don't define any flags for `DNSServiceUpdateRecord`
+	/// yet; [`from_bits_retain`](#method.from_bits_retain) passes flags
+	/// of newer daemons through.
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct UpdateRecordFlags: ffi::DNSServiceFlags {
+		// all bits might be used by some daemon
+		const _ = !0;
+	}
+}
+
+/// What happens to a record when its [`Record`](struct.Record.html)
+/// handle is dropped
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum RecordDropPolicy {
+	/// Remove the record right away
+	#[default]
+	RemoveOnDrop,
+	/// Keep the record for as long as the underlying
+	/// [`Registration`](struct.Registration.html) or
+	/// [`Connection`](struct.Connection.html) lives (like
+	/// [`Record::keep`](struct.Record.html#method.keep))
+	KeepUntilConnectionDrop,
+	/// Keep the record for its TTL (120 seconds if it used the default
+	/// TTL), or until the underlying `Registration` or `Connection` is
+	/// gone, whatever comes first
+	///
+	/// The removal is delayed by a task on the tokio runtime the handle
+	/// was dropped on: without a runtime the record gets removed right
+	/// away, and if the runtime shuts down earlier the record stays
+	/// until the connection is gone.  Records always die with the
+	/// connection to the daemon, so this doesn't survive the process
+	/// exiting.
+	KeepForTtl,
+}
+
+/// State of a record in [`RecordInfo`](struct.RecordInfo.html)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum RecordState {
+	/// Waiting for the daemon to confirm the registration
+	Pending,
+	/// Registered; the [`Record`](struct.Record.html) handle is alive
+	Registered,
+	/// Registered, but the handle is gone (see
+	/// [`RecordDropPolicy`](enum.RecordDropPolicy.html) and
+	/// [`Record::keep`](struct.Record.html#method.keep))
+	Detached,
+}
+
+/// Metadata of a record registered through a
+/// [`Connection`](struct.Connection.html)
+///
+/// See [`Connection::records`](struct.Connection.html#method.records).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[non_exhaustive]
+pub struct RecordInfo {
+	/// full name of the record
+	pub fullname: String,
+	/// type of the record
+	pub rr_type: Type,
+	/// class of the record
+	pub rr_class: Class,
+	/// TTL of the last registration or update (0: daemon default)
+	pub ttl: u32,
+	/// length of the current rdata
+	pub rdata_len: usize,
+	/// state of the record
+	pub state: RecordState,
+}
+
 /// A successful record registration
 ///
 /// Releases the record when dropped (unless it is a
@@ -25,23 +103,67 @@ impl Record {
 		self.0.rr_type()
 	}
 
-	/// Update record
+	/// Update record with flags
 	///
 	/// Cannot change type or class of record.
 	///
 	/// See [`DNSServiceUpdateRecord`](https://developer.apple.com/documentation/dnssd/1804739-dnsserviceupdaterecord).
 	#[doc(alias = "DNSServiceUpdateRecord")]
-	pub fn update_record(&self, rdata: &[u8], ttl: u32) -> io::Result<()> {
-		self.0.update_record(0 /* no flags */, rdata, ttl)?;
+	pub fn update_record_extended(
+		&self,
+		flags: UpdateRecordFlags,
+		rdata: &[u8],
+		ttl: u32,
+	) -> io::Result<()> {
+		self.0.update_record(flags.bits(), rdata, ttl)?;
 		Ok(())
 	}
 
+	/// Update record
+	///
+	/// Cannot change type or class of record.  Uses
+	/// [`update_record_extended`](#method.update_record_extended)
+	/// without flags.
+	///
+	/// See [`DNSServiceUpdateRecord`](https://developer.apple.com/documentation/dnssd/1804739-dnsserviceupdaterecord).
+	#[doc(alias = "DNSServiceUpdateRecord")]
+	pub fn update_record(&self, rdata: &[u8], ttl: u32) -> io::Result<()> {
+		self.update_record_extended(UpdateRecordFlags::empty(), rdata, ttl)
+	}
+
 	/// Keep record alive for as long as the underlying
 	/// [`Registration`](struct.Registration.html) or
 	/// [`Connection`](struct.Connection.html) lives
 	pub fn keep(self) {
 		self.0.keep()
 	}
+
+	/// Remove record now
+	///
+	/// The daemon reports whether it accepted the removal right away;
+	/// the returned future only forwards that result.  Ignores the
+	/// [drop policy](#method.set_drop_policy).  Removing the default TXT
+	/// record of a registration isn't possible; it completes right away.
+	#[doc(alias = "DNSServiceRemoveRecord")]
+	pub fn remove(self) -> Deregister {
+		match self.0.remove() {
+			Ok(()) => Deregister::new(None),
+			Err(e) => Deregister::failed(e.into()),
+		}
+	}
+
+	/// Set what happens when dropping the handle (default:
+	/// [`RemoveOnDrop`](enum.RecordDropPolicy.html#variant.RemoveOnDrop))
+	pub fn set_drop_policy(&mut self, policy: RecordDropPolicy) {
+		self.0.set_drop_policy(policy)
+	}
+}
+
+impl Record {
+	// daemon confirmed the registration
+	pub(crate) fn set_registered(&self) {
+		self.0.set_state(RecordState::Registered)
+	}
 }
 
 impl From<inner::DNSRecord> for Record {
@@ -1,6 +1,9 @@
+use futures_core::future::FusedFuture;
+use futures_util::FutureExt;
 use std::{
 	future::Future,
 	io,
+	net::SocketAddr,
 	os::raw::{
 		c_char,
 		c_void,
@@ -10,21 +13,36 @@ use std::{
 		Context,
 		Poll,
 	},
+	time::Duration,
 };
 
 use crate::{
 	cstr,
 	dns_consts::Type,
+	domain_name::DomainName,
 	ffi,
-	inner,
-	interface::Interface,
+	inner::{
+		self,
+		EventedService,
+	},
+	instance_name::ServiceInstanceName,
+	interface::{
+		Interface,
+		InterfaceIndex,
+	},
+	service::{
+		query_record_snapshot,
+		FullName,
+		RegisterRecordFlags,
+	},
 };
 
 type CallbackFuture = crate::future::ServiceFuture<inner::SharedService, RegisterResult>;
 
 bitflags::bitflags! {
 	/// Flags used to register service
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct RegisterFlags: ffi::DNSServiceFlags {
 		/// Indicates a name conflict should not get handled automatically.
 		///
@@ -40,6 +58,81 @@ bitflags::bitflags! {
 		///
 		/// See [`kDNSServiceFlagsUnique`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsunique).
 		const UNIQUE = ffi::FLAGS_UNIQUE;
+
+		/// Register the service without the PTR record for its service
+		/// type: it can be resolved by name, but isn't found by browsing.
+		///
+		/// Not supported by the avahi compat layer (registration fails
+		/// with `Unsupported`, or the flag is dropped with a warning if
+		/// [`set_normalize_daemon_behavior`](fn.set_normalize_daemon_behavior.html)
+		/// is enabled).
+		///
+		/// See [`kDNSServiceFlagsNonBrowsable`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsnonbrowsable).
+		const NON_BROWSABLE = ffi::FLAGS_NON_BROWSABLE;
+
+		/// Answer queries for the service records from outside the local
+		/// link.
+		///
+		/// Not supported by the avahi compat layer (registration fails
+		/// with `Unsupported`, or the flag is dropped with a warning if
+		/// [`set_normalize_daemon_behavior`](fn.set_normalize_daemon_behavior.html)
+		/// is enabled).
+		///
+		/// See [`kDNSServiceFlagsAllowRemoteQuery`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsallowremotequery).
+		const ALLOW_REMOTE_QUERY = ffi::FLAGS_ALLOW_REMOTE_QUERY;
+	}
+}
+
+bitflags::bitflags! {
+	/// Flags for [`RegisterResult`](struct.RegisterResult.html)
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct RegisteredFlags: ffi::DNSServiceFlags {
+		/// Indicates the service was registered.
+		///
+		/// Not set by the avahi compat layer unless
+		/// [`set_normalize_daemon_behavior`](fn.set_normalize_daemon_behavior.html)
+		/// is enabled.
+		///
+		/// See [`kDNSServiceFlagsAdd`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsadd).
+		const ADD = ffi::FLAGS_ADD;
+	}
+}
+
+/// State of a registration
+///
+/// See [`Registration::state`](struct.Registration.html#method.state).
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum RegistrationState {
+	/// Waiting for the daemon to confirm the registration
+	Pending,
+	/// Registered
+	Active {
+		/// registered name (might differ from the requested name)
+		name: String,
+	},
+	/// The name is already in use (only with
+	/// [`NO_AUTO_RENAME`](struct.RegisterFlags.html#associatedconstant.NO_AUTO_RENAME))
+	Conflicted,
+	/// Deregistered (dropped, closed or deregistered explicitly)
+	Removed,
+	/// Registration failed (e.g. the daemon got restarted)
+	Errored {
+		/// description of the error
+		message: String,
+	},
+}
+
+impl RegistrationState {
+	pub(crate) fn from_error(e: &io::Error) -> Self {
+		if crate::Error::from_io_error(e).is_some_and(crate::Error::is_name_conflict) {
+			Self::Conflicted
+		} else {
+			Self::Errored {
+				message: e.to_string(),
+			}
+		}
 	}
 }
 
@@ -49,28 +142,229 @@ bitflags::bitflags! {
 /// Registered [`Record`](struct.Record.html)s from this `Registration`
 /// or the originating [`Register`](struct.Register.html) future will
 /// keep the `Registration` alive.
-pub struct Registration(inner::SharedService);
+pub struct Registration {
+	service: inner::SharedService,
+	// to confirm deregistration
+	fullname: Option<String>,
+	// from `RegisterData::additional_records`
+	records: Vec<crate::Record>,
+}
 
 impl Registration {
+	// completes with an error once the registration is gone (e.g. the
+	// daemon got restarted)
+	pub(crate) fn poll_error(&mut self, cx: &mut Context<'_>) -> Poll<io::Error> {
+		match self.service.poll_service(cx) {
+			Ok(()) => Poll::Pending,
+			Err(e) => Poll::Ready(e),
+		}
+	}
+
+	/// Watch the state of the registration
+	///
+	/// Other tasks (health checks, metrics) can observe the state
+	/// without owning the registration.  The state becomes `Removed`
+	/// once the service got deregistered; the sender is gone after that.
+	pub fn state(&self) -> tokio::sync::watch::Receiver<RegistrationState> {
+		self.service.state()
+	}
+
+	/// Wait until the registration fails (e.g. the daemon got
+	/// restarted)
+	///
+	/// A registration doesn't deliver results like a stream; this is
+	/// the counterpart of `recv` on streams like
+	/// [`Browse::recv`](struct.Browse.html#method.recv).  Cancel safe:
+	/// dropping the future before it completes doesn't lose the error.
+	pub fn closed(&mut self) -> RegistrationClosed<'_> {
+		RegistrationClosed { registration: self }
+	}
+
 	/// Add a record to a registered service
 	///
 	/// See [`DNSServiceAddRecord`](https://developer.apple.com/documentation/dnssd/1804730-dnsserviceaddrecord)
 	#[doc(alias = "DNSServiceAddRecord")]
-	pub fn add_record(&self, rr_type: Type, rdata: &[u8], ttl: u32) -> io::Result<crate::Record> {
+	pub fn add_record_extended(
+		&self,
+		rr_type: Type,
+		rdata: &[u8],
+		data: AddRecordData,
+	) -> io::Result<crate::Record> {
 		Ok(self
-			.0
+			.service
 			.clone()
-			.add_record(0 /* no flags */, rr_type, rdata, ttl)?
+			.add_record(data.flags.bits(), rr_type, rdata, data.ttl)?
 			.into())
 	}
 
+	/// Add a record to a registered service
+	///
+	/// Uses [`add_record_extended`](#method.add_record_extended) without
+	/// flags.
+	///
+	/// See [`DNSServiceAddRecord`](https://developer.apple.com/documentation/dnssd/1804730-dnsserviceaddrecord)
+	#[doc(alias = "DNSServiceAddRecord")]
+	pub fn add_record(&self, rr_type: Type, rdata: &[u8], ttl: u32) -> io::Result<crate::Record> {
+		let data = AddRecordData {
+			ttl,
+			..Default::default()
+		};
+		self.add_record_extended(rr_type, rdata, data)
+	}
+
 	/// Get [`Record`](struct.Record.html) handle for default TXT record
 	/// associated with the service registration (e.g. to update it).
 	///
 	/// [`Record::keep`](struct.Record.html#method.keep) doesn't do
 	/// anything useful on that handle.
 	pub fn get_default_txt_record(&self) -> crate::Record {
-		self.0.clone().get_default_txt_record().into()
+		self.service.clone().get_default_txt_record().into()
+	}
+
+	/// Records added with [`RegisterData::additional_records`] (in the
+	/// same order)
+	///
+	/// [`RegisterData::additional_records`]: struct.RegisterData.html#structfield.additional_records
+	pub fn additional_records(&self) -> &[crate::Record] {
+		&self.records
+	}
+
+	/// Keep the service registered for `linger` after the registration
+	/// (and all its records) got dropped (default: `None`, deregister
+	/// right away)
+	///
+	/// Gives the daemon time to process updates issued right before
+	/// dropping.  Needs the tokio runtime to still be running.
+	pub fn set_linger(&self, linger: Option<Duration>) {
+		self.service.set_linger(linger);
+	}
+
+	/// TXT record rdata to publish right before deregistering (e.g. a
+	/// "shutting down" state); best combined with
+	/// [`set_linger`](#method.set_linger) so clients can see it
+	///
+	/// Fails with `BadParam` if `rdata` is longer than 65535 bytes.
+	pub fn set_final_txt(&self, rdata: Option<&[u8]>) -> io::Result<()> {
+		Ok(self.service.set_final_txt(rdata)?)
+	}
+
+	/// Deregister the service
+	///
+	/// Unlike dropping this also removes the service (and all its
+	/// records) if [`Record`](struct.Record.html)s still keep it alive,
+	/// and ignores [`set_linger`](#method.set_linger).
+	///
+	/// The daemon doesn't confirm the removal; the returned future
+	/// completes right away unless [`Deregister::wait_until_gone`] is
+	/// used.
+	///
+	/// [`Deregister::wait_until_gone`]: struct.Deregister.html#method.wait_until_gone
+	pub fn deregister(self) -> Deregister {
+		self.service.close();
+		Deregister::new(self.fullname)
+	}
+}
+
+/// Pending failure of a [`Registration`](struct.Registration.html)
+///
+/// Created by [`Registration::closed`](struct.Registration.html#method.closed).
+#[must_use = "futures do nothing unless polled"]
+pub struct RegistrationClosed<'a> {
+	registration: &'a mut Registration,
+}
+
+impl Future for RegistrationClosed<'_> {
+	type Output = io::Error;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.registration.poll_error(cx)
+	}
+}
+
+/// Pending deregistration
+///
+/// Returned by [`Registration::deregister`] and [`Record::remove`].
+///
+/// [`Registration::deregister`]: struct.Registration.html#method.deregister
+/// [`Record::remove`]: struct.Record.html#method.remove
+#[must_use = "futures do nothing unless polled"]
+pub struct Deregister {
+	// full name of a deregistered service
+	fullname: Option<String>,
+	inner: Pin<Box<dyn Future<Output = io::Result<()>> + Send>>,
+}
+
+impl Deregister {
+	// the removal request was sent already
+	pub(crate) fn new(fullname: Option<String>) -> Self {
+		Self {
+			fullname,
+			inner: Box::pin(async { Ok(()) }),
+		}
+	}
+
+	pub(crate) fn failed(e: io::Error) -> Self {
+		Self {
+			fullname: None,
+			inner: Box::pin(async { Err(e) }),
+		}
+	}
+
+	/// Also wait until the deregistered service can't be found anymore
+	/// with a query for its SRV record (at most `timeout`)
+	///
+	/// Useful to register the same name again right away without
+	/// running into a name conflict.  Fails with `TimedOut` if the
+	/// service is still visible after `timeout` (e.g. the daemon is
+	/// slow, or another host announces the same name), and with the
+	/// error of the query if it fails; the service was deregistered
+	/// nonetheless.  Has no effect for
+	/// [`Record::remove`](struct.Record.html#method.remove).
+	pub fn wait_until_gone(self, timeout: Duration) -> Self {
+		match self.fullname {
+			Some(fullname) => {
+				let inner = self.inner;
+				Self {
+					fullname: None,
+					inner: Box::pin(async move {
+						inner.await?;
+						wait_until_gone(&fullname, timeout).await
+					}),
+				}
+			},
+			None => self,
+		}
+	}
+}
+
+impl Future for Deregister {
+	type Output = io::Result<()>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.inner.as_mut().poll(cx)
+	}
+}
+
+// how long to collect SRV records per check for a deregistered service
+const DEREGISTER_QUIESCE: Duration = Duration::from_millis(100);
+
+async fn wait_until_gone(fullname: &str, timeout: Duration) -> io::Result<()> {
+	let check = async {
+		loop {
+			let records =
+				query_record_snapshot(fullname, Type::SRV, DEREGISTER_QUIESCE, timeout).await?;
+			if records.is_empty() {
+				return Ok(());
+			}
+			tokio::time::sleep(DEREGISTER_QUIESCE).await;
+		}
+	};
+	match tokio::time::timeout(timeout, check).await {
+		Ok(result) => result,
+		Err(_) => Err(io::Error::new(
+			io::ErrorKind::TimedOut,
+			format!("{} still visible after deregistering", fullname),
+		)),
 	}
 }
 
@@ -81,24 +375,49 @@ impl Registration {
 #[must_use = "futures do nothing unless polled"]
 pub struct Register {
 	future: CallbackFuture,
+	records: Vec<crate::Record>,
 }
 
 impl Register {
-	pin_utils::unsafe_pinned!(future: CallbackFuture);
+	pub(crate) fn service(&self) -> &inner::SharedService {
+		self.future.service()
+	}
 
-	/// Add a record to a registered service
-	///
-	/// See [`DNSServiceAddRecord`](https://developer.apple.com/documentation/dnssd/1804730-dnsserviceaddrecord)
+	/// Watch the state of the registration (see
+	/// [`Registration::state`](struct.Registration.html#method.state))
+	pub fn state(&self) -> tokio::sync::watch::Receiver<RegistrationState> {
+		self.future.service().state()
+	}
+
+	/// Add a record to a registered service (see
+	/// [`Registration::add_record_extended`](struct.Registration.html#method.add_record_extended))
 	#[doc(alias = "DNSServiceAddRecord")]
-	pub fn add_record(&self, rr_type: Type, rdata: &[u8], ttl: u32) -> io::Result<crate::Record> {
+	pub fn add_record_extended(
+		&self,
+		rr_type: Type,
+		rdata: &[u8],
+		data: AddRecordData,
+	) -> io::Result<crate::Record> {
 		Ok(self
 			.future
 			.service()
 			.clone()
-			.add_record(0 /* no flags */, rr_type, rdata, ttl)?
+			.add_record(data.flags.bits(), rr_type, rdata, data.ttl)?
 			.into())
 	}
 
+	/// Add a record to a registered service
+	///
+	/// See [`DNSServiceAddRecord`](https://developer.apple.com/documentation/dnssd/1804730-dnsserviceaddrecord)
+	#[doc(alias = "DNSServiceAddRecord")]
+	pub fn add_record(&self, rr_type: Type, rdata: &[u8], ttl: u32) -> io::Result<crate::Record> {
+		let data = AddRecordData {
+			ttl,
+			..Default::default()
+		};
+		self.add_record_extended(rr_type, rdata, data)
+	}
+
 	/// Get [`Record`](struct.Record.html) handle for default TXT record
 	/// associated with the service registration (e.g. to update it).
 	///
@@ -113,12 +432,61 @@ impl Register {
 	}
 }
 
+// registration completing with `result` without a daemon
+#[cfg(all(test, unix))]
+impl Register {
+	pub(crate) fn unconnected(result: io::Result<RegisterResult>) -> Self {
+		let service = inner::SharedService::unconnected();
+		let (future, ()) = CallbackFuture::new_with("register".to_string(), service, |sender| {
+			unsafe { CallbackFuture::run_callback(sender, 0, || result) };
+			Ok(())
+		})
+		.unwrap();
+		Self {
+			future,
+			records: Vec::new(),
+		}
+	}
+}
+
 impl Future for Register {
 	type Output = io::Result<(Registration, RegisterResult)>;
 
-	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-		let (service, item) = futures_core::ready!(self.future().poll(cx))?;
-		Poll::Ready(Ok((Registration(service), item)))
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		if self.future.is_terminated() {
+			// can only get ready once
+			return Poll::Pending;
+		}
+		let service = self.future.service().clone();
+		let (service, item) = match futures_core::ready!(self.future.poll_unpin(cx)) {
+			Ok(result) => result,
+			Err(e) => {
+				service.set_state(RegistrationState::from_error(&e));
+				return Poll::Ready(Err(e));
+			},
+		};
+		service.set_state(RegistrationState::Active {
+			name: item.name.clone(),
+		});
+		let fullname = FullName {
+			service: Some(&item.name),
+			reg_type: &item.reg_type,
+			domain: &item.domain,
+		}
+		.construct()
+		.ok();
+		let registration = Registration {
+			service,
+			fullname,
+			records: std::mem::take(&mut self.records),
+		};
+		Poll::Ready(Ok((registration, item)))
+	}
+}
+
+impl FusedFuture for Register {
+	fn is_terminated(&self) -> bool {
+		self.future.is_terminated()
 	}
 }
 
@@ -127,6 +495,8 @@ impl Future for Register {
 /// See [`DNSServiceRegisterReply`](https://developer.apple.com/documentation/dnssd/dnsserviceregisterreply).
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct RegisterResult {
+	/// flags
+	pub flags: RegisteredFlags,
 	/// if [`NoAutoRename`](enum.RegisterFlag.html#variant.NoAutoRename)
 	/// was set this is the original name, otherwise it might be
 	/// different.
@@ -139,7 +509,7 @@ pub struct RegisterResult {
 
 unsafe extern "C" fn register_callback(
 	_sd_ref: ffi::DNSServiceRef,
-	_flags: ffi::DNSServiceFlags,
+	flags: ffi::DNSServiceFlags,
 	error_code: ffi::DNSServiceErrorType,
 	name: *const c_char,
 	reg_type: *const c_char,
@@ -152,6 +522,7 @@ unsafe extern "C" fn register_callback(
 		let domain = cstr::from_cstr(domain)?;
 
 		Ok(RegisterResult {
+			flags: RegisteredFlags::from_bits_retain(crate::compat::register_flags(flags)),
 			name: name.to_string(),
 			reg_type: reg_type.to_string(),
 			domain: domain.to_string(),
@@ -175,9 +546,18 @@ pub struct RegisterData<'a> {
 	pub flags: RegisterFlags,
 	/// interface to register service on
 	pub interface: Interface,
-	/// service name, defaults to hostname
+	/// service name, defaults to hostname (also if empty)
+	///
+	/// Checked with [`ServiceInstanceName::check`] (and normalized to
+	/// NFC with the `nfc` feature); see [`sanitize_instance_name`] for
+	/// names from untrusted sources.
+	///
+	/// [`ServiceInstanceName::check`]: struct.ServiceInstanceName.html#method.check
+	/// [`sanitize_instance_name`]: fn.sanitize_instance_name.html
 	pub name: Option<&'a str>,
 	/// domain on which to advertise the service
+	///
+	/// Checked and normalized like [`DomainName`](struct.DomainName.html).
 	pub domain: Option<&'a str>,
 	/// the SRV target host name, defaults to local hostname(s).
 	/// Address records are NOT automatically generated for other names.
@@ -193,6 +573,22 @@ pub struct RegisterData<'a> {
 	/// [`TxtRecord::data`]: struct.TxtRecord.html#method.data
 	/// [`TxtRecord::rdata`]: struct.TxtRecord.html#method.rdata
 	pub txt: &'a [u8],
+	/// records (type, rdata, TTL) to add to the service right after
+	/// registering it, before the daemon announces it (default: none)
+	///
+	/// The records are available through
+	/// [`Registration::additional_records`]; they live as long as the
+	/// registration.  Failing to add one fails the registration.
+	///
+	/// [`Registration::additional_records`]: struct.Registration.html#method.additional_records
+	pub additional_records: &'a [(Type, &'a [u8], u32)],
+	/// TTL in seconds for the default TXT record (default: `None`, the
+	/// daemon picks it)
+	///
+	/// `DNSServiceRegister` has no TTL parameter; the TXT record gets
+	/// updated with this TTL right after registering.  The TTL of the
+	/// SRV record can't be changed through the DNS-SD API.
+	pub ttl: Option<u32>,
 	#[doc(hidden)]
 	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 }
@@ -206,11 +602,35 @@ impl<'a> Default for RegisterData<'a> {
 			domain: None,
 			host: None,
 			txt: b"",
+			additional_records: &[],
+			ttl: None,
 			_non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 		}
 	}
 }
 
+/// Optional data when adding a record to a registered service; either
+/// use its default value or customize it like:
+///
+/// ```
+/// # use async_dnssd::{AddRecordData, RegisterRecordFlags};
+/// AddRecordData {
+///     flags: RegisterRecordFlags::UNIQUE,
+///     ttl: 120,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct AddRecordData {
+	/// flags for the record
+	pub flags: RegisterRecordFlags,
+	/// time to live of the resource record in seconds (passing 0 will
+	/// select a sensible default)
+	pub ttl: u32,
+	#[doc(hidden)]
+	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+}
+
 /// Register a service
 ///
 /// * `reg_type`: the service type followed by the protocol, separated
@@ -232,14 +652,24 @@ pub fn register_extended(
 ) -> io::Result<Register> {
 	crate::init();
 
-	let name = cstr::NullableCStr::from(&data.name)?;
+	let name = ServiceInstanceName::for_register(data.name)?;
+	let operation = match &name {
+		Some(name) => format!("register {} name={:?}", reg_type, name.as_str()),
+		None => format!("register {}", reg_type),
+	};
+	let name = cstr::NullableCStr::from(&name)?;
 	let reg_type = cstr::CStr::from(&reg_type)?;
-	let domain = cstr::NullableCStr::from(&data.domain)?;
+	let domain = data.domain.map(DomainName::new).transpose()?;
+	let domain = cstr::NullableCStr::from(&domain)?;
 	let host = cstr::NullableCStr::from(&data.host)?;
 
-	let future = CallbackFuture::new(move |sender| {
+	let future = CallbackFuture::new(operation, move |sender| {
 		inner::OwnedService::register(
-			data.flags.bits(),
+			crate::compat::filter_flags(
+				"DNSServiceRegister",
+				data.flags.bits(),
+				ffi::FLAGS_NO_AUTO_RENAME,
+			),
 			data.interface.into_raw(),
 			&name,
 			&reg_type,
@@ -253,7 +683,81 @@ pub fn register_extended(
 		.map(|s| s.share())
 	})?;
 
-	Ok(Register { future })
+	if let Some(ttl) = data.ttl {
+		// empty TXT data is registered as a single empty string
+		let txt = if data.txt.is_empty() { b"\0" } else { data.txt };
+		future
+			.service()
+			.clone()
+			.get_default_txt_record()
+			.update_record(0 /* no flags */, txt, ttl)?;
+	}
+
+	// add records before the daemon gets to announce the service
+	let records = data
+		.additional_records
+		.iter()
+		.map(|&(rr_type, rdata, ttl)| {
+			let record = future.service().clone().add_record(
+				0, // no flags
+				rr_type, rdata, ttl,
+			)?;
+			Ok(crate::Record::from(record))
+		})
+		.collect::<io::Result<_>>()?;
+
+	Ok(Register { future, records })
+}
+
+/// Register a service for a bound socket address
+///
+/// Uses the port of `address` (e.g. from `TcpListener::local_addr`);
+/// if `data.interface` is `Any` the registration is restricted to the
+/// interface owning `address`:
+///
+/// * the unspecified address (`0.0.0.0` or `::`) doesn't restrict the
+///   interface
+/// * loopback addresses use `LocalOnly`; the avahi compat layer doesn't
+///   support it, so the registration fails with `Unsupported` there
+/// * IPv6 addresses with a scope id use that interface
+/// * otherwise the interface is looked up by address; if it can't be
+///   found the registration fails with `AddrNotAvailable`.
+///
+/// See [`register_extended`](fn.register_extended.html).
+#[doc(alias = "DNSServiceRegister")]
+pub fn register_socket_address(
+	reg_type: &str,
+	address: SocketAddr,
+	mut data: RegisterData<'_>,
+) -> io::Result<Register> {
+	if data.interface == Interface::Any {
+		data.interface = socket_interface(address)?;
+	}
+	register_extended(reg_type, address.port(), data)
+}
+
+// interface to register a service listening on `address` on
+fn socket_interface(address: SocketAddr) -> io::Result<Interface> {
+	let ip = address.ip();
+	match address {
+		_ if ip.is_unspecified() => Ok(Interface::Any),
+		_ if ip.is_loopback() && cfg!(dnssd_avahi) => Err(io::Error::new(
+			io::ErrorKind::Unsupported,
+			format!(
+				"avahi can't register services on loopback address {} (LocalOnly)",
+				ip
+			),
+		)),
+		_ if ip.is_loopback() => Ok(Interface::LocalOnly),
+		SocketAddr::V6(a) if a.scope_id() != 0 => Ok(Interface::from_raw(a.scope_id())),
+		_ => match InterfaceIndex::from_address(ip) {
+			Some(ndx) => Ok(Interface::Index(ndx)),
+			None => Err(io::Error::new(
+				io::ErrorKind::AddrNotAvailable,
+				format!("no interface found for address {}", ip),
+			)),
+		},
+	}
 }
 
 /// Register a service
@@ -290,3 +794,24 @@ pub fn register_extended(
 pub fn register(reg_type: &str, port: u16) -> io::Result<Register> {
 	register_extended(reg_type, port, RegisterData::default())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::socket_interface;
+	use crate::Interface;
+	use std::io;
+
+	#[test]
+	fn loopback_interface() {
+		let result = socket_interface("127.0.0.1:631".parse().unwrap());
+		if cfg!(dnssd_avahi) {
+			assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+		} else {
+			assert_eq!(result.unwrap(), Interface::LocalOnly);
+		}
+		assert_eq!(
+			socket_interface("[::]:631".parse().unwrap()).unwrap(),
+			Interface::Any
+		);
+	}
+}
@@ -1,4 +1,7 @@
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
 use std::{
+	fmt,
 	io,
 	os::raw::{
 		c_char,
@@ -9,10 +12,12 @@ use std::{
 		Context,
 		Poll,
 	},
+	time::Duration,
 };
 
 use crate::{
 	cstr,
+	domain_name::DomainName,
 	ffi,
 	inner,
 	interface::Interface,
@@ -21,13 +26,65 @@ use crate::{
 		ResolveHost,
 		ResolveHostData,
 	},
+	txt_record::{
+		TxtRecord,
+		TxtRecordRef,
+	},
 };
 
-type CallbackStream = crate::stream::ServiceStream<inner::OwnedService, ResolveResult>;
+type CallbackStream = crate::stream::ServiceStream<inner::StreamService, ResolveResult>;
+
+bitflags::bitflags! {
+	/// Flags used to resolve a service
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct ResolveFlags: ffi::DNSServiceFlags {
+		/// Resolve via multicast even for services outside `.local`.
+		///
+		/// Not supported by the avahi compat layer (resolving fails with
+		/// `Unsupported`, or the flag is dropped with a warning if
+		/// [`set_normalize_daemon_behavior`](fn.set_normalize_daemon_behavior.html)
+		/// is enabled).
+		///
+		/// See [`kDNSServiceFlagsForceMulticast`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsforcemulticast).
+		const FORCE_MULTICAST = ffi::FLAGS_FORCE_MULTICAST;
+
+		/// Let the daemon fail the operation with `Timeout` if the service
+		/// can't be resolved within a daemon-defined time.
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsTimeout`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagstimeout).
+		const TIMEOUT = ffi::FLAGS_TIMEOUT;
+
+		/// Wake the host providing the service through a sleep proxy
+		/// before resolving.
+		///
+		/// Not supported by the avahi compat layer.
+		///
+		/// See [`kDNSServiceFlagsWakeOnResolve`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagswakeonresolve).
+		const WAKE_ON_RESOLVE = ffi::FLAGS_WAKE_ON_RESOLVE;
+
+		/// Don't use cellular interfaces for unicast DNS (only on macOS
+		/// and iOS).
+		///
+		/// See [`kDNSServiceFlagsDenyCellular`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsdenycellular).
+		#[cfg(any(target_os = "macos", target_os = "ios"))]
+		const DENY_CELLULAR = ffi::FLAGS_DENY_CELLULAR;
+
+		/// Don't use expensive interfaces (e.g. cellular, personal
+		/// hotspot) for unicast DNS (only on macOS and iOS).
+		///
+		/// See [`kDNSServiceFlagsDenyExpensive`](https://developer.apple.com/documentation/dnssd/1823436-anonymous/kdnsserviceflagsdenyexpensive).
+		#[cfg(any(target_os = "macos", target_os = "ios"))]
+		const DENY_EXPENSIVE = ffi::FLAGS_DENY_EXPENSIVE;
+	}
+}
 
 bitflags::bitflags! {
 	/// Flags for [`ResolveResult`](struct.ResolveResult.html)
-	#[derive(Default)]
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct ResolvedFlags: ffi::DNSServiceFlags {
 		/// Indicates at least one more result is pending in the queue.  If
 		/// not set there still might be more results coming in the future.
@@ -44,14 +101,28 @@ pub struct Resolve {
 }
 
 impl Resolve {
-	pin_utils::unsafe_pinned!(stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>);
+	/// Wait for the next result; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
 }
 
 impl futures_core::Stream for Resolve {
 	type Item = io::Result<ResolveResult>;
 
-	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		self.stream().poll_next(cx)
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for Resolve {
+	fn is_terminated(&self) -> bool {
+		self.stream.is_terminated()
 	}
 }
 
@@ -71,10 +142,29 @@ pub struct ResolveResult {
 	/// port the service is provided on (native endian)
 	pub port: u16,
 	/// TXT RDATA describing service parameters
-	pub txt: Vec<u8>,
+	///
+	/// Cheap to clone; parse with [`txt_record`] or [`txt_ref`].
+	///
+	/// [`txt_record`]: #method.txt_record
+	/// [`txt_ref`]: #method.txt_ref
+	pub txt: bytes::Bytes,
 }
 
 impl ResolveResult {
+	/// Parsed TXT record (copy of `txt`)
+	///
+	/// `None` if `txt` isn't a valid TXT RDATA.
+	pub fn txt_record(&self) -> Option<TxtRecord> {
+		TxtRecord::parse(&self.txt)
+	}
+
+	/// Parsed TXT record borrowing `txt`
+	///
+	/// `None` if `txt` isn't a valid TXT RDATA.
+	pub fn txt_ref(&self) -> Option<TxtRecordRef<'_>> {
+		TxtRecordRef::parse(&self.txt)
+	}
+
 	/// Lookup socket addresses for resolved service
 	pub fn resolve_socket_address(&self) -> ResolveHost {
 		let rhdata = ResolveHostData {
@@ -85,6 +175,17 @@ impl ResolveResult {
 	}
 }
 
+/// One line summary like `Printer._ipp._tcp.local. at printer.local.:631 (en0)`
+impl fmt::Display for ResolveResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at {}:{}", self.fullname, self.host_target, self.port)?;
+		if self.interface != Interface::Any {
+			write!(f, " ({})", self.interface)?;
+		}
+		Ok(())
+	}
+}
+
 unsafe extern "C" fn resolve_callback(
 	_sd_ref: ffi::DNSServiceRef,
 	flags: ffi::DNSServiceFlags,
@@ -98,31 +199,59 @@ unsafe extern "C" fn resolve_callback(
 	context: *mut c_void,
 ) {
 	CallbackStream::run_callback(context, error_code, || {
-		let fullname = cstr::from_cstr(fullname)?;
-		let host_target = cstr::from_cstr(host_target)?;
+		let fullname = cstr::from_cstr_protocol_name(fullname)?;
+		let host_target = cstr::from_cstr_protocol_name(host_target)?;
 		let txt = ::std::slice::from_raw_parts(txt_record, txt_len as usize);
 
 		Ok(ResolveResult {
-			flags: ResolvedFlags::from_bits_truncate(flags),
+			flags: ResolvedFlags::from_bits_retain(flags),
 			interface: Interface::from_raw(interface_index),
 			fullname: fullname.to_string(),
 			host_target: host_target.to_string(),
 			port: u16::from_be(port),
-			txt: txt.into(),
+			txt: bytes::Bytes::copy_from_slice(txt),
 		})
 	});
 }
 
-fn _resolve(interface: Interface, name: &str, reg_type: &str, domain: &str) -> io::Result<Resolve> {
+/// Optional data when resolving a service; either use its default value
+/// or customize it like:
+///
+/// ```
+/// # use async_dnssd::{ResolveData, ResolveFlags};
+/// ResolveData {
+///     flags: ResolveFlags::FORCE_MULTICAST,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ResolveData {
+	/// flags for resolving
+	pub flags: ResolveFlags,
+	#[doc(hidden)]
+	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
+}
+
+fn _resolve(
+	connection: Option<&inner::SharedService>,
+	interface: Interface,
+	name: &str,
+	reg_type: &str,
+	domain: &DomainName,
+	data: ResolveData,
+) -> io::Result<Resolve> {
 	crate::init();
 
+	let operation = format!("resolve {:?} {} {}", name, reg_type, domain);
 	let name = cstr::CStr::from(&name)?;
 	let reg_type = cstr::CStr::from(&reg_type)?;
-	let domain = cstr::CStr::from(&domain)?;
+	domain.check()?;
+	let domain = cstr::CStr::from(domain)?;
 
-	let stream = CallbackStream::new(move |sender| {
-		inner::OwnedService::resolve(
-			0, // no flags
+	let stream = CallbackStream::new(operation, move |sender| {
+		inner::StreamService::resolve(
+			connection,
+			crate::compat::filter_flags("DNSServiceResolve", data.flags.bits(), 0),
 			interface.into_raw(),
 			&name,
 			&reg_type,
@@ -136,19 +265,92 @@ fn _resolve(interface: Interface, name: &str, reg_type: &str, domain: &str) -> i
 	Ok(Resolve { stream })
 }
 
+/// Find hostname and port (and more) for a service
+///
+/// See [`DNSServiceResolve`](https://developer.apple.com/documentation/dnssd/1804744-dnsserviceresolve).
+#[doc(alias = "DNSServiceResolve")]
+pub fn resolve_extended(
+	interface: Interface,
+	name: &str,
+	reg_type: &str,
+	domain: impl Into<DomainName>,
+	data: ResolveData,
+) -> Resolve {
+	resolve_on(None, interface, name, reg_type, &domain.into(), data)
+}
+
 /// Find hostname and port (and more) for a service
 ///
 /// You probably want to use [`BrowseResult::resolve`] instead.
 ///
+/// Uses [`resolve_extended`] with default [`ResolveData`].
+///
 /// See [`DNSServiceResolve`](https://developer.apple.com/documentation/dnssd/1804744-dnsserviceresolve).
 ///
 /// [`BrowseResult::resolve`]: struct.BrowseResult.html#method.resolve
+/// [`resolve_extended`]: fn.resolve_extended.html
+/// [`ResolveData`]: struct.ResolveData.html
 #[doc(alias = "DNSServiceResolve")]
-pub fn resolve(interface: Interface, name: &str, reg_type: &str, domain: &str) -> Resolve {
-	match _resolve(interface, name, reg_type, domain) {
+pub fn resolve(
+	interface: Interface,
+	name: &str,
+	reg_type: &str,
+	domain: impl Into<DomainName>,
+) -> Resolve {
+	resolve_extended(interface, name, reg_type, domain, ResolveData::default())
+}
+
+/// Find hostname and port (and more) for a service; completes with the
+/// first result
+///
+/// The resolve is stopped once a result arrived (or the future got
+/// dropped).  Fails with `TimedOut` if no result arrived within
+/// `timeout`.
+///
+/// Uses [`resolve`](fn.resolve.html).
+#[doc(alias = "DNSServiceResolve")]
+pub async fn resolve_first(
+	interface: Interface,
+	name: &str,
+	reg_type: &str,
+	domain: impl Into<DomainName>,
+	timeout: Option<Duration>,
+) -> io::Result<ResolveResult> {
+	let resolve = resolve(interface, name, reg_type, domain);
+	crate::service::first_result(resolve, timeout, |_| true).await
+}
+
+// resolve on the given connection (or the default one)
+pub(crate) fn resolve_on(
+	connection: Option<&inner::SharedService>,
+	interface: Interface,
+	name: &str,
+	reg_type: &str,
+	domain: &DomainName,
+	data: ResolveData,
+) -> Resolve {
+	match _resolve(connection, interface, name, reg_type, domain, data) {
 		Ok(r) => r,
 		Err(e) => Resolve {
 			stream: Err(e).into(),
 		},
 	}
 }
+
+/// Find hostname and port (and more) for a service given by its full
+/// name
+///
+/// Splits the (escaped) full name (e.g. from a `PTR` query) with
+/// [`FullName::parse`] and passes the parts to [`resolve`].
+///
+/// [`FullName::parse`]: struct.FullName.html#method.parse
+/// [`resolve`]: fn.resolve.html
+#[doc(alias = "DNSServiceResolve")]
+pub fn resolve_service(interface: Interface, fullname: &str) -> Resolve {
+	match crate::service::FullName::parse(fullname) {
+		Ok(parts) => resolve(interface, &parts.service, &parts.reg_type, &parts.domain),
+		Err(e) => Resolve {
+			stream: Err(e).into(),
+		},
+	}
+}
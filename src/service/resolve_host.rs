@@ -1,9 +1,8 @@
-use futures_util::{
-	StreamExt,
-	TryStreamExt,
-};
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
 use std::{
 	fmt,
+	future::Future,
 	io,
 	net::{
 		IpAddr,
@@ -18,6 +17,7 @@ use std::{
 		Context,
 		Poll,
 	},
+	time::Duration,
 };
 
 use crate::{
@@ -28,10 +28,19 @@ use crate::{
 	ffi,
 	interface::Interface,
 	service::{
+		get_addr_info_extended,
 		query_record_extended,
+		AddrInfoFlags,
+		AddrProtocol,
+		GetAddrInfo,
+		GetAddrInfoData,
+		GetAddrInfoResult,
+		QueriedRecordFlags,
+		QueryRecord,
 		QueryRecordData,
 		QueryRecordFlags,
 		QueryRecordResult,
+		GET_ADDR_INFO_SUPPORTED,
 	},
 };
 
@@ -44,6 +53,10 @@ fn decode_a(a: QueryRecordResult, port: u16) -> Option<ResolveHostResult> {
 		Some(ResolveHostResult {
 			flags: ResolvedHostFlags::from_bits_truncate(a.flags.bits()),
 			address: addr,
+			rr_type: Type::A,
+			hostname: a.fullname,
+			ttl: a.ttl,
+			more_coming: false,
 		})
 	} else {
 		println!("Invalid A response: {:?}", a);
@@ -60,6 +73,10 @@ fn decode_aaaa(a: QueryRecordResult, port: u16) -> Option<ResolveHostResult> {
 		Some(ResolveHostResult {
 			flags: ResolvedHostFlags::from_bits_truncate(a.flags.bits()),
 			address: addr,
+			rr_type: Type::AAAA,
+			hostname: a.fullname,
+			ttl: a.ttl,
+			more_coming: false,
 		})
 	} else {
 		println!("Invalid AAAA response: {:?}", a);
@@ -70,8 +87,10 @@ fn decode_aaaa(a: QueryRecordResult, port: u16) -> Option<ResolveHostResult> {
 bitflags::bitflags! {
 	/// Flags for [`ResolveHostResult`](struct.ResolveHostResult.html)
 	///
-	/// Doesn't include `MORE_COMING` as there are two underlying streams.
-	#[derive(Default)]
+	/// Doesn't include `MORE_COMING` as there are two underlying streams;
+	/// see [`ResolveHostResult::more_coming`](struct.ResolveHostResult.html#structfield.more_coming).
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 	pub struct ResolvedHostFlags: ffi::DNSServiceFlags {
 		/// Indicates the result is new.  If not set indicates the result
 		/// was removed.
@@ -98,23 +117,233 @@ pub struct ResolveHostData {
 	pub flags: QueryRecordFlags,
 	/// interface to query records on
 	pub interface: Interface,
+	/// end the operation after the given time (default: never)
+	///
+	/// The stream simply ends (without an error) and the operation is
+	/// stopped right away.
+	pub timeout: Option<Duration>,
+	/// end the stream once the initial answers arrived (default: false)
+	///
+	/// The stream ends when both queries delivered results without
+	/// `MORE_COMING`, or when no results arrived for a second (e.g. a
+	/// host without IPv6 addresses); both queries are stopped right
+	/// away.
+	pub one_shot: bool,
 	#[doc(hidden)]
 	pub _non_exhaustive: crate::non_exhaustive_struct::NonExhaustiveMarker,
 }
 
+// how long a one-shot resolve waits for further results
+const ONE_SHOT_QUIESCE: Duration = Duration::from_secs(1);
+
+fn decode_addr_info(a: GetAddrInfoResult, port: u16) -> Option<ResolveHostResult> {
+	if a.negative {
+		return None;
+	}
+	let rr_type = match a.address {
+		IpAddr::V4(_) => Type::A,
+		IpAddr::V6(_) => Type::AAAA,
+	};
+	Some(ResolveHostResult {
+		flags: ResolvedHostFlags::from_bits_truncate(a.flags.bits()),
+		address: ScopedSocketAddr::new(a.address, port, a.interface.scope_id()),
+		rr_type,
+		hostname: a.hostname,
+		ttl: a.ttl,
+		more_coming: false,
+	})
+}
+
+// lookup for one address family
+enum FamilySource {
+	Query(
+		QueryRecord,
+		fn(QueryRecordResult, u16) -> Option<ResolveHostResult>,
+	),
+	AddrInfo(GetAddrInfo),
+}
+
+impl FamilySource {
+	fn new(host: &str, rr_type: Type, data: ResolveHostData) -> Self {
+		if GET_ADDR_INFO_SUPPORTED {
+			let protocol = if rr_type == Type::A {
+				AddrProtocol::Ipv4
+			} else {
+				AddrProtocol::Ipv6
+			};
+			let data = GetAddrInfoData {
+				flags: data.flags,
+				interface: data.interface,
+				protocol,
+				timeout: data.timeout,
+				..Default::default()
+			};
+			return Self::AddrInfo(get_addr_info_extended(host, data));
+		}
+		let data = QueryRecordData {
+			flags: data.flags,
+			interface: data.interface,
+			rr_class: Class::IN,
+			timeout: data.timeout,
+			..Default::default()
+		};
+		let decode = if rr_type == Type::A {
+			decode_a
+		} else {
+			decode_aaaa
+		};
+		Self::Query(query_record_extended(host, rr_type, data), decode)
+	}
+
+	// result (if valid) and whether more results are pending
+	#[allow(clippy::type_complexity)]
+	fn poll_next(
+		&mut self,
+		port: u16,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<io::Result<(Option<ResolveHostResult>, bool)>>> {
+		match self {
+			Self::Query(query, decode) => query.poll_next_unpin(cx).map(|item| {
+				item.map(|item| {
+					item.map(|record| {
+						let more_coming = record.flags.contains(QueriedRecordFlags::MORE_COMING);
+						(decode(record, port), more_coming)
+					})
+				})
+			}),
+			Self::AddrInfo(lookup) => lookup.poll_next_unpin(cx).map(|item| {
+				item.map(|item| {
+					item.map(|result| {
+						let more_coming = result.flags.contains(AddrInfoFlags::MORE_COMING);
+						(decode_addr_info(result, port), more_coming)
+					})
+				})
+			}),
+		}
+	}
+}
+
+// query for one address family
+struct FamilyQuery {
+	// `None` after the query ended
+	query: Option<FamilySource>,
+	more_coming: bool,
+	// got at least one result
+	answered: bool,
+}
+
+impl FamilyQuery {
+	fn new(host: &str, rr_type: Type, data: ResolveHostData) -> Self {
+		Self {
+			query: Some(FamilySource::new(host, rr_type, data)),
+			more_coming: false,
+			answered: false,
+		}
+	}
+
+	fn poll(&mut self, port: u16, cx: &mut Context<'_>) -> Poll<io::Result<ResolveHostResult>> {
+		loop {
+			let query = match &mut self.query {
+				Some(query) => query,
+				None => return Poll::Pending,
+			};
+			match futures_core::ready!(query.poll_next(port, cx)) {
+				Some(Ok((result, more_coming))) => {
+					self.more_coming = more_coming;
+					self.answered = true;
+					if let Some(result) = result {
+						return Poll::Ready(Ok(result));
+					}
+				},
+				Some(Err(e)) => return Poll::Ready(Err(e)),
+				None => {
+					self.query = None;
+					self.more_coming = false;
+				},
+			}
+		}
+	}
+}
+
 /// Pending resolve
 #[must_use = "streams do nothing unless polled"]
 pub struct ResolveHost {
-	inner: Pin<
-		Box<dyn futures_core::Stream<Item = io::Result<ResolveHostResult>> + 'static + Send + Sync>,
-	>,
+	port: u16,
+	v6: FamilyQuery,
+	v4: FamilyQuery,
+	// alternate between the queries
+	v4_first: bool,
+	one_shot: bool,
+	// one-shot mode: restarted with each result
+	quiesce: Option<Pin<Box<tokio::time::Sleep>>>,
+}
+
+impl ResolveHost {
+	fn stop(&mut self) {
+		self.v4.query = None;
+		self.v6.query = None;
+		self.quiesce = None;
+	}
+
+	fn initial_answers_done(&self) -> bool {
+		[&self.v4, &self.v6]
+			.iter()
+			.all(|family| family.answered && !family.more_coming)
+	}
 }
 
 impl futures_core::Stream for ResolveHost {
 	type Item = io::Result<ResolveHostResult>;
 
 	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		self.inner.poll_next_unpin(cx)
+		let this = &mut *self;
+		if this.one_shot && this.quiesce.is_none() && this.v4.query.is_some() {
+			this.quiesce = Some(Box::pin(tokio::time::sleep(ONE_SHOT_QUIESCE)));
+		}
+		this.v4_first = !this.v4_first;
+		let (first, second) = if this.v4_first {
+			(&mut this.v4, &mut this.v6)
+		} else {
+			(&mut this.v6, &mut this.v4)
+		};
+		let item = match first.poll(this.port, cx) {
+			Poll::Ready(item) => item,
+			Poll::Pending => match second.poll(this.port, cx) {
+				Poll::Ready(item) => item,
+				Poll::Pending if first.query.is_none() && second.query.is_none() => {
+					return Poll::Ready(None);
+				},
+				Poll::Pending => {
+					if let Some(quiesce) = &mut this.quiesce {
+						futures_core::ready!(quiesce.as_mut().poll(cx));
+						this.stop();
+						return Poll::Ready(None);
+					}
+					return Poll::Pending;
+				},
+			},
+		};
+		let item = item.map(|mut result| {
+			result.more_coming = this.v4.more_coming || this.v6.more_coming;
+			result
+		});
+		if this.one_shot {
+			if this.initial_answers_done() {
+				// deliver this result, end the stream afterwards
+				this.stop();
+			} else {
+				this.quiesce = Some(Box::pin(tokio::time::sleep(ONE_SHOT_QUIESCE)));
+			}
+		}
+		Poll::Ready(Some(item))
+	}
+}
+
+/// Ends once both address queries ended (after errors, or in one-shot
+/// mode after the initial answers)
+impl FusedStream for ResolveHost {
+	fn is_terminated(&self) -> bool {
+		self.v4.query.is_none() && self.v6.query.is_none()
 	}
 }
 
@@ -127,6 +356,17 @@ pub struct ResolveHostResult {
 	pub flags: ResolvedHostFlags,
 	/// address
 	pub address: ScopedSocketAddr,
+	/// record the address was found in (`A` or `AAAA`)
+	pub rr_type: Type,
+	/// name the address record belongs to (as reported by the daemon)
+	pub hostname: String,
+	/// TTL (time to live) of the address record
+	pub ttl: u32,
+	/// at least one of the underlying queries has more results pending
+	///
+	/// Combines `MORE_COMING` of the `A` and `AAAA` queries; wait for a
+	/// result without it before acting on a batch of address changes.
+	pub more_coming: bool,
 }
 
 /// IP address with port and "scope id" (even for IPv4)
@@ -241,23 +481,45 @@ impl fmt::Debug for ScopedSocketAddr {
 
 /// Resolves hostname (with passed port) to stream of `ScopedSocketAddr`.
 ///
-/// Uses
+/// Uses [`get_addr_info_extended`] (one lookup per address family),
+/// or with the avahi compat layer (which doesn't support it)
 /// [`DNSServiceQueryRecord`](https://developer.apple.com/documentation/dnssd/1804747-dnsservicequeryrecord)
 /// to query for `A` and `AAAA` records (in the `IN` class).
+///
+/// [`get_addr_info_extended`]: fn.get_addr_info_extended.html
+#[doc(alias = "DNSServiceGetAddrInfo")]
 #[doc(alias = "DNSServiceQueryRecord")]
 pub fn resolve_host_extended(host: &str, port: u16, data: ResolveHostData) -> ResolveHost {
-	let qrdata = QueryRecordData {
-		flags: data.flags,
-		interface: data.interface,
-		rr_class: Class::IN,
-		..Default::default()
-	};
-
-	let inner_v6 = query_record_extended(host, Type::AAAA, qrdata)
-		.try_filter_map(move |addr| async move { Ok(decode_aaaa(addr, port)) });
-	let inner_v4 = query_record_extended(host, Type::A, qrdata)
-		.try_filter_map(move |addr| async move { Ok(decode_a(addr, port)) });
-	let inner = Box::pin(futures_util::stream::select(inner_v6, inner_v4));
+	ResolveHost {
+		port,
+		v6: FamilyQuery::new(host, Type::AAAA, data),
+		v4: FamilyQuery::new(host, Type::A, data),
+		v4_first: true,
+		one_shot: data.one_shot,
+		quiesce: None,
+	}
+}
 
-	ResolveHost { inner }
+/// Resolves hostname (with passed port); completes with the first
+/// address found
+///
+/// The lookup is stopped once an address was found (or the future got
+/// dropped).  Fails with `TimedOut` if nothing was found within
+/// `timeout`.
+///
+/// Uses [`resolve_host_extended`](fn.resolve_host_extended.html) with
+/// default [`ResolveHostData`](struct.ResolveHostData.html).
+#[doc(alias = "DNSServiceGetAddrInfo")]
+#[doc(alias = "DNSServiceQueryRecord")]
+pub async fn resolve_host_first(
+	host: &str,
+	port: u16,
+	timeout: Option<Duration>,
+) -> io::Result<ScopedSocketAddr> {
+	let lookup = resolve_host_extended(host, port, ResolveHostData::default());
+	let result = crate::service::first_result(lookup, timeout, |r: &ResolveHostResult| {
+		r.flags.contains(ResolvedHostFlags::ADD)
+	})
+	.await?;
+	Ok(result.address)
 }
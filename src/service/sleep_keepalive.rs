@@ -0,0 +1,104 @@
+use futures_core::stream::FusedStream;
+use futures_util::StreamExt;
+use std::{
+	io,
+	os::{
+		fd::{
+			AsFd,
+			AsRawFd,
+		},
+		raw::c_void,
+	},
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+
+use crate::{
+	ffi,
+	inner,
+};
+
+type CallbackStream = crate::stream::ServiceStream<inner::OwnedService, ()>;
+
+/// Keepalive registered with the sleep proxy
+///
+/// Yields `Ok(())` when the daemon reports the keepalive records were
+/// registered; ends permanently after an error.  Dropping it stops the
+/// keepalive.
+#[must_use = "streams do nothing unless polled"]
+pub struct SleepKeepalive {
+	stream: crate::fused_err_stream::FusedErrorStream<CallbackStream>,
+}
+
+impl SleepKeepalive {
+	/// Wait for the next status report; `None` once the operation ended
+	///
+	/// Same as `StreamExt::next`; cancel safe (see
+	/// [`Recv`](struct.Recv.html)).
+	pub fn recv(&mut self) -> crate::Recv<'_, Self> {
+		crate::Recv::new(self)
+	}
+}
+
+impl futures_core::Stream for SleepKeepalive {
+	type Item = io::Result<()>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// Ends permanently after an error reported by the daemon (or a broken
+/// connection to it)
+impl FusedStream for SleepKeepalive {
+	fn is_terminated(&self) -> bool {
+		self.stream.is_terminated()
+	}
+}
+
+unsafe extern "C" fn sleep_keepalive_callback(
+	_sd_ref: ffi::DNSServiceRef,
+	error_code: ffi::DNSServiceErrorType,
+	context: *mut c_void,
+) {
+	CallbackStream::run_callback(context, error_code, || Ok(()));
+}
+
+fn _sleep_keepalive(fd: std::os::raw::c_int, timeout: Duration) -> io::Result<CallbackStream> {
+	crate::init();
+
+	// the daemon takes whole seconds
+	let timeout = u32::try_from(timeout.as_secs()).unwrap_or(u32::MAX);
+
+	CallbackStream::new(format!("sleep_keepalive fd {}", fd), move |sender| {
+		inner::OwnedService::sleep_keepalive(
+			0, // no flags defined
+			fd,
+			timeout,
+			Some(sleep_keepalive_callback),
+			sender,
+		)
+	})
+}
+
+/// Keep a TCP connection alive through the sleep proxy while the host
+/// sleeps
+///
+/// `socket` must be a connected TCP socket; the daemon reads the
+/// addresses and sequence numbers from it and registers keepalive
+/// records with a sleep proxy, which answers for the connection for
+/// `timeout` (rounded down to seconds).  The socket is only borrowed;
+/// keep it open as long as the keepalive is needed.  Only available
+/// with mDNSResponder on Apple platforms.
+///
+/// See `DNSServiceSleepKeepalive` in `dns_sd_private.h`.
+#[doc(alias = "DNSServiceSleepKeepalive")]
+pub fn sleep_keepalive(socket: &impl AsFd, timeout: Duration) -> SleepKeepalive {
+	SleepKeepalive {
+		stream: _sleep_keepalive(socket.as_fd().as_raw_fd(), timeout).into(),
+	}
+}
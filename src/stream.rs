@@ -11,7 +11,10 @@ use std::{
 };
 
 use crate::{
-	error::Error,
+	error::{
+		with_context,
+		Error,
+	},
 	ffi,
 	inner::EventedService,
 };
@@ -28,6 +31,10 @@ pub(crate) struct ServiceStream<S: EventedService, T> {
 	service: S,
 	_sender: Box<CallbackContext<T>>,
 	receiver: mpsc::UnboundedReceiver<io::Result<T>>,
+	// attached to errors
+	operation: String,
+	// connection failure; reported after the queued results
+	failed: Option<io::Error>,
 }
 
 impl<S: EventedService, T> ServiceStream<S, T> {
@@ -42,28 +49,40 @@ impl<S: EventedService, T> ServiceStream<S, T> {
 		let sender = context as *mut CallbackContext<T>;
 		let sender: &mut CallbackContext<T> = &mut *sender;
 
-		let data = Error::from(error_code)
-			.map_err(io::Error::from)
-			.and_then(|()| f());
+		let error = Error::from(crate::compat::callback_error(error_code));
+		let fatal = error.is_err();
+		let data = error.map_err(io::Error::from).and_then(|()| f());
+
+		if let Err(e) = &data {
+			if crate::cstr::is_skip_result(e) {
+				log::debug!("dropping result with invalid name");
+				return;
+			}
+		}
 
-		sender
-			.unbounded_send(data)
-			.expect("receiver must still be alive");
+		// fails only if the stream already ended with a fatal error
+		let _ = sender.unbounded_send(data);
+		if fatal {
+			// the daemon doesn't report anything after an error; end stream
+			sender.close_channel();
+		}
 	}
 
-	pub(crate) fn new<F>(f: F) -> io::Result<Self>
+	pub(crate) fn new<F>(operation: String, f: F) -> io::Result<Self>
 	where
 		F: FnOnce(*mut c_void) -> Result<S, Error>,
 	{
 		let (sender, receiver) = mpsc::unbounded::<io::Result<T>>();
 		let mut sender = Box::new(sender);
 
-		let service = f(box_raw(&mut sender))?;
+		let service = f(box_raw(&mut sender)).map_err(|e| with_context(e.into(), &operation))?;
 
 		Ok(Self {
 			service,
 			_sender: sender,
 			receiver,
+			operation,
+			failed: None,
 		})
 	}
 }
@@ -72,7 +91,50 @@ impl<S: EventedService, T> futures_core::Stream for ServiceStream<S, T> {
 	type Item = io::Result<T>;
 
 	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		self.service.poll_service(cx)?;
-		self.receiver.poll_next_unpin(cx)
+		if !self._sender.is_closed() {
+			if let Err(e) = self.service.poll_service(cx) {
+				// end the stream after already queued results
+				self._sender.close_channel();
+				self.failed = Some(with_context(e, &self.operation));
+			}
+		}
+		match futures_core::ready!(self.receiver.poll_next_unpin(cx)) {
+			Some(item) => Poll::Ready(Some(item.map_err(|e| with_context(e, &self.operation)))),
+			None => Poll::Ready(self.failed.take().map(Err)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// connection to the daemon is gone
+	struct Broken;
+
+	impl EventedService for Broken {
+		fn poll_service(&mut self, _cx: &mut Context<'_>) -> io::Result<()> {
+			Err(io::Error::new(io::ErrorKind::BrokenPipe, "connection lost"))
+		}
+	}
+
+	type BrokenStream = ServiceStream<Broken, u32>;
+
+	#[tokio::test]
+	async fn results_before_error() {
+		let mut stream = BrokenStream::new("test".to_string(), |context| {
+			// results the daemon delivered before the connection broke
+			unsafe {
+				BrokenStream::run_callback(context, 0, || Ok(1));
+				BrokenStream::run_callback(context, 0, || Ok(2));
+			}
+			Ok(Broken)
+		})
+		.unwrap();
+		assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+		assert_eq!(stream.next().await.unwrap().unwrap(), 2);
+		let e = stream.next().await.unwrap().unwrap_err();
+		assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+		assert!(stream.next().await.is_none());
 	}
 }
@@ -0,0 +1,475 @@
+//! Scripted fake services for tests
+//!
+//! A [`Scenario`] declares services and a script of events (services
+//! appearing, disappearing, changing their TXT record) on a timeline
+//! relative to the first poll of a stream.  Streams created from a scenario
+//! yield the same result types as [`browse`], [`resolve`] and
+//! [`resolve_host_extended`], with timers using `tokio::time` (so
+//! `tokio::time::pause` makes the timeline virtual).
+//!
+//! Discovery code needs to be generic over the stream types to be tested
+//! this way (e.g. take an `impl TryStream<Ok = BrowseResult>`).
+//!
+//! Scenario streams end after the last scripted event; a scenario can
+//! also simulate a daemon restart (see [`Scenario::daemon_gone_at`]).
+//!
+//! ```
+//! # use async_dnssd::testing::{FakeService, Scenario};
+//! # use async_dnssd::TxtRecord;
+//! # use std::time::Duration;
+//! let printer = FakeService::new("Printer", "_ipp._tcp.", "printer.local.", 631);
+//! let mut txt = TxtRecord::new();
+//! txt.set_value(b"rp", b"ipp/print").unwrap();
+//! let scenario = Scenario::new()
+//!     .service(printer)
+//!     .update_txt_at(Duration::from_secs(5), "Printer", "_ipp._tcp.", txt)
+//!     .remove_at(Duration::from_secs(10), "Printer", "_ipp._tcp.");
+//! let browse = scenario.browse("_ipp._tcp.");
+//! ```
+//!
+//! [`Scenario`]: struct.Scenario.html
+//! [`Scenario::daemon_gone_at`]: struct.Scenario.html#method.daemon_gone_at
+//! [`browse`]: ../fn.browse.html
+//! [`resolve`]: ../fn.resolve.html
+//! [`resolve_host_extended`]: ../fn.resolve_host_extended.html
+
+use async_dnssd_types::name::escape_label;
+use futures_core::Stream;
+use std::{
+	collections::VecDeque,
+	future::Future,
+	io,
+	net::IpAddr,
+	pin::Pin,
+	task::{
+		Context,
+		Poll,
+	},
+	time::Duration,
+};
+use tokio::time::{
+	Instant,
+	Sleep,
+};
+
+use crate::{
+	dns_consts::Type,
+	interface::Interface,
+	service::{
+		BrowseResult,
+		BrowsedFlags,
+		ResolveHostResult,
+		ResolveResult,
+		ResolvedFlags,
+		ResolvedHostFlags,
+		ScopedSocketAddr,
+	},
+	txt_record::TxtRecord,
+};
+
+/// Service declared in a [`Scenario`](struct.Scenario.html)
+#[derive(Clone)]
+pub struct FakeService {
+	/// interface the service is found on
+	pub interface: Interface,
+	/// (unescaped) service name
+	pub name: String,
+	/// service type
+	pub reg_type: String,
+	/// domain
+	pub domain: String,
+	/// hostname the service is provided on
+	pub host: String,
+	/// port the service is provided on
+	pub port: u16,
+	/// TXT record
+	pub txt: TxtRecord,
+	/// addresses of `host`
+	pub addresses: Vec<IpAddr>,
+	/// whether the service is hidden from browsing (like
+	/// [`RegisterFlags::NON_BROWSABLE`](../struct.RegisterFlags.html#associatedconstant.NON_BROWSABLE))
+	pub non_browsable: bool,
+}
+
+impl FakeService {
+	/// New service in the `local.` domain (on any interface, with an
+	/// empty TXT record and without addresses)
+	pub fn new(name: &str, reg_type: &str, host: &str, port: u16) -> Self {
+		Self {
+			interface: Interface::Any,
+			name: name.to_string(),
+			reg_type: reg_type.to_string(),
+			domain: "local.".to_string(),
+			host: host.to_string(),
+			port,
+			txt: TxtRecord::new(),
+			addresses: Vec::new(),
+			non_browsable: false,
+		}
+	}
+
+	/// Add address for `host`
+	pub fn address(mut self, address: IpAddr) -> Self {
+		self.addresses.push(address);
+		self
+	}
+
+	/// Set TXT record
+	pub fn txt(mut self, txt: TxtRecord) -> Self {
+		self.txt = txt;
+		self
+	}
+
+	/// Hide service from browsing; it still can be resolved
+	pub fn non_browsable(mut self) -> Self {
+		self.non_browsable = true;
+		self
+	}
+
+	fn is(&self, name: &str, reg_type: &str) -> bool {
+		self.name == name && self.reg_type == reg_type
+	}
+
+	// same escaping as `FullName::construct`, without the daemon library
+	fn fullname(&self) -> String {
+		let mut fullname = escape_label(self.name.as_bytes());
+		fullname.push('.');
+		fullname.push_str(&self.reg_type);
+		fullname.push_str(&self.domain);
+		fullname
+	}
+
+	fn browse_result(&self, flags: BrowsedFlags) -> BrowseResult {
+		BrowseResult {
+			flags,
+			interface: self.interface,
+			service_name: self.name.clone(),
+			reg_type: self.reg_type.clone(),
+			domain: self.domain.clone(),
+		}
+	}
+
+	fn resolve_result(&self) -> ResolveResult {
+		ResolveResult {
+			flags: ResolvedFlags::empty(),
+			interface: self.interface,
+			fullname: self.fullname(),
+			host_target: self.host.clone(),
+			port: self.port,
+			txt: bytes::Bytes::copy_from_slice(self.txt.rdata()),
+		}
+	}
+
+	fn resolve_host_results(&self, port: u16, flags: ResolvedHostFlags) -> Vec<ResolveHostResult> {
+		self.addresses
+			.iter()
+			.map(|&address| ResolveHostResult {
+				flags,
+				address: ScopedSocketAddr::new(address, port, self.interface.scope_id()),
+				rr_type: if address.is_ipv4() {
+					Type::A
+				} else {
+					Type::AAAA
+				},
+				hostname: self.host.clone(),
+				ttl: 120, // mDNS default for host records
+				more_coming: false,
+			})
+			.collect()
+	}
+}
+
+#[derive(Clone)]
+enum Event {
+	Add(FakeService),
+	Remove {
+		name: String,
+		reg_type: String,
+	},
+	UpdateTxt {
+		name: String,
+		reg_type: String,
+		txt: TxtRecord,
+	},
+	DaemonGone,
+}
+
+/// Services and a script of events for tests
+///
+/// See the [module documentation](index.html).
+#[derive(Clone, Default)]
+pub struct Scenario {
+	// ordered by time (stable for events at the same time)
+	events: Vec<(Duration, Event)>,
+}
+
+impl Scenario {
+	/// New empty scenario
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	fn push(mut self, at: Duration, event: Event) -> Self {
+		let pos = self.events.partition_point(|&(t, _)| t <= at);
+		self.events.insert(pos, (at, event));
+		self
+	}
+
+	/// Service available from the start
+	pub fn service(self, service: FakeService) -> Self {
+		self.add_at(Duration::ZERO, service)
+	}
+
+	/// Service appearing at `at`
+	pub fn add_at(self, at: Duration, service: FakeService) -> Self {
+		self.push(at, Event::Add(service))
+	}
+
+	/// Service disappearing at `at`
+	pub fn remove_at(self, at: Duration, name: &str, reg_type: &str) -> Self {
+		self.push(
+			at,
+			Event::Remove {
+				name: name.to_string(),
+				reg_type: reg_type.to_string(),
+			},
+		)
+	}
+
+	/// Service changing its TXT record at `at`
+	pub fn update_txt_at(self, at: Duration, name: &str, reg_type: &str, txt: TxtRecord) -> Self {
+		self.push(
+			at,
+			Event::UpdateTxt {
+				name: name.to_string(),
+				reg_type: reg_type.to_string(),
+				txt,
+			},
+		)
+	}
+
+	/// Connection to the daemon breaking at `at`
+	///
+	/// Streams yield a `ServiceNotRunning` error at `at` and end; later
+	/// events are not replayed.  Every stream created from the scenario
+	/// starts its own timeline, so a restarted operation can use a
+	/// different scenario to continue.
+	pub fn daemon_gone_at(self, at: Duration) -> Self {
+		self.push(at, Event::DaemonGone)
+	}
+
+	// replay events, passing the state before each event
+	fn replay<T>(&self, mut f: impl FnMut(&[FakeService], &Event) -> Vec<T>) -> Timeline<T> {
+		let mut services: Vec<FakeService> = Vec::new();
+		let mut items = VecDeque::new();
+		for (at, event) in &self.events {
+			items.extend(f(&services, event).into_iter().map(|item| (*at, item)));
+			match event {
+				Event::DaemonGone => return Timeline::new(items, Some(*at)),
+				Event::Add(service) => services.push(service.clone()),
+				Event::Remove { name, reg_type } => services.retain(|s| !s.is(name, reg_type)),
+				Event::UpdateTxt {
+					name,
+					reg_type,
+					txt,
+				} => {
+					for service in &mut services {
+						if service.is(name, reg_type) {
+							service.txt = txt.clone();
+						}
+					}
+				},
+			}
+		}
+		Timeline::new(items, None)
+	}
+
+	/// Browse for services of type `reg_type`
+	///
+	/// Like [`browse`](../fn.browse.html); results at the same time are
+	/// flagged with `MORE_COMING` (apart from the last).
+	pub fn browse(&self, reg_type: &str) -> ScenarioStream<BrowseResult> {
+		let mut timeline = self.replay(|services, event| match event {
+			Event::Add(service) if service.reg_type == reg_type && !service.non_browsable => {
+				vec![service.browse_result(BrowsedFlags::ADD)]
+			},
+			Event::Remove { name, reg_type: rt } if rt == reg_type => services
+				.iter()
+				.filter(|s| s.is(name, rt) && !s.non_browsable)
+				.map(|s| s.browse_result(BrowsedFlags::empty()))
+				.collect(),
+			_ => Vec::new(),
+		});
+		for ndx in 1..timeline.items.len() {
+			if timeline.items[ndx - 1].0 == timeline.items[ndx].0 {
+				timeline.items[ndx - 1].1.flags |= BrowsedFlags::MORE_COMING;
+			}
+		}
+		ScenarioStream { timeline }
+	}
+
+	/// Resolve service; yields a new result each time the service is
+	/// added or its TXT record changes
+	///
+	/// Like [`resolve`](../fn.resolve.html).
+	pub fn resolve(&self, name: &str, reg_type: &str) -> ScenarioStream<ResolveResult> {
+		let timeline = self.replay(|services, event| match event {
+			Event::Add(service) if service.is(name, reg_type) => vec![service.resolve_result()],
+			Event::UpdateTxt {
+				name: n,
+				reg_type: rt,
+				txt,
+			} if n == name && rt == reg_type => services
+				.iter()
+				.filter(|s| s.is(name, reg_type))
+				.map(|s| {
+					let mut result = s.resolve_result();
+					result.txt = bytes::Bytes::copy_from_slice(txt.rdata());
+					result
+				})
+				.collect(),
+			_ => Vec::new(),
+		});
+		ScenarioStream { timeline }
+	}
+
+	/// Resolve addresses of `host` (as declared by the services)
+	///
+	/// Like [`resolve_host_extended`](../fn.resolve_host_extended.html);
+	/// addresses are removed when the service declaring them disappears.
+	pub fn resolve_host(&self, host: &str, port: u16) -> ScenarioStream<ResolveHostResult> {
+		let timeline = self.replay(|services, event| match event {
+			Event::Add(service) if service.host == host => {
+				service.resolve_host_results(port, ResolvedHostFlags::ADD)
+			},
+			Event::Remove { name, reg_type } => services
+				.iter()
+				.filter(|s| s.is(name, reg_type) && s.host == host)
+				.flat_map(|s| s.resolve_host_results(port, ResolvedHostFlags::empty()))
+				.collect(),
+			_ => Vec::new(),
+		});
+		ScenarioStream { timeline }
+	}
+}
+
+struct Timeline<T> {
+	items: VecDeque<(Duration, T)>,
+	// daemon connection breaks after the last item
+	gone: Option<Duration>,
+	start: Option<Instant>,
+	timer: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T> Timeline<T> {
+	fn new(items: VecDeque<(Duration, T)>, gone: Option<Duration>) -> Self {
+		Self {
+			items,
+			gone,
+			start: None,
+			timer: None,
+		}
+	}
+}
+
+/// Stream of results created by a [`Scenario`](struct.Scenario.html)
+///
+/// The timeline starts when the stream is polled the first time.
+#[must_use = "streams do nothing unless polled"]
+pub struct ScenarioStream<T> {
+	timeline: Timeline<T>,
+}
+
+impl<T: Unpin> Stream for ScenarioStream<T> {
+	type Item = io::Result<T>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let timeline = &mut self.timeline;
+		let at = match (timeline.items.front(), timeline.gone) {
+			(Some(&(at, _)), _) => at,
+			(None, Some(at)) => at,
+			(None, None) => return Poll::Ready(None),
+		};
+		let deadline = *timeline.start.get_or_insert_with(Instant::now) + at;
+		if deadline > Instant::now() {
+			let timer = match &mut timeline.timer {
+				Some(timer) => {
+					timer.as_mut().reset(deadline);
+					timer
+				},
+				timer @ None => timer.insert(Box::pin(tokio::time::sleep_until(deadline))),
+			};
+			futures_core::ready!(timer.as_mut().poll(cx));
+		}
+		match timeline.items.pop_front() {
+			Some((_, item)) => Poll::Ready(Some(Ok(item))),
+			None => {
+				timeline.gone = None;
+				let e = crate::Error::KnownError(crate::ffi::DNSServiceError::ServiceNotRunning);
+				Poll::Ready(Some(Err(e.into())))
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures_util::StreamExt;
+
+	#[tokio::test(start_paused = true)]
+	async fn browse_timeline() {
+		let scenario = Scenario::new()
+			.service(FakeService::new("a", "_http._tcp.", "a.local.", 80))
+			.service(FakeService::new("b", "_http._tcp.", "b.local.", 80))
+			.remove_at(Duration::from_secs(3), "a", "_http._tcp.");
+		let start = Instant::now();
+		let results: Vec<_> = scenario
+			.browse("_http._tcp.")
+			.map(|r| r.unwrap())
+			.collect()
+			.await;
+		assert_eq!(start.elapsed(), Duration::from_secs(3));
+		let summary: Vec<_> = results.iter().map(ToString::to_string).collect();
+		assert_eq!(
+			summary,
+			[
+				"+ \"a\" _http._tcp. local.",
+				"+ \"b\" _http._tcp. local.",
+				"- \"a\" _http._tcp. local.",
+			]
+		);
+		assert!(results[0].flags.contains(BrowsedFlags::MORE_COMING));
+		assert!(!results[1].flags.contains(BrowsedFlags::MORE_COMING));
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn non_browsable() {
+		let scenario = Scenario::new()
+			.service(FakeService::new("a", "_http._tcp.", "a.local.", 80))
+			.service(FakeService::new("hidden", "_http._tcp.", "h.local.", 80).non_browsable())
+			.remove_at(Duration::from_secs(3), "hidden", "_http._tcp.");
+		let browsed: Vec<_> = scenario
+			.browse("_http._tcp.")
+			.map(|r| r.unwrap().service_name)
+			.collect()
+			.await;
+		assert_eq!(browsed, ["a"]);
+		let resolved: Vec<_> = scenario
+			.resolve("hidden", "_http._tcp.")
+			.map(|r| r.unwrap().host_target)
+			.collect()
+			.await;
+		assert_eq!(resolved, ["h.local."]);
+	}
+
+	#[test]
+	fn fullname_escaping() {
+		let service =
+			FakeService::new("My Printer.\\2\u{7f}é", "_ipp._tcp.", "printer.local.", 631);
+		assert_eq!(
+			service.fullname(),
+			"My\\032Printer\\.\\\\2\\127é._ipp._tcp.local."
+		);
+	}
+}
@@ -9,6 +9,11 @@ use std::{
 	time::Duration,
 };
 
+use crate::clock::{
+	Clock,
+	TokioClock,
+};
+
 /// `Stream` extension to simplify building
 /// [`TimeoutStream`](struct.TimeoutStream.html)
 pub trait StreamTimeoutExt: Stream + Sized {
@@ -27,37 +32,54 @@ impl<S: Stream> StreamTimeoutExt for S {
 ///
 /// If the timeout triggers the stream ends (without an error).
 #[must_use = "streams do nothing unless polled"]
-pub struct TimeoutStream<S> {
+pub struct TimeoutStream<S, C: Clock = TokioClock> {
 	stream: S,
 	duration: Duration,
-	timeout: tokio::time::Sleep,
+	clock: C,
+	timeout: C::Sleep,
 }
 
-impl<S: Stream> TimeoutStream<S> {
-	pin_utils::unsafe_pinned!(stream: S);
+impl<S: Stream, C: Clock> TimeoutStream<S, C> {
+	fn stream(self: Pin<&mut Self>) -> Pin<&mut S> {
+		// `stream` is structurally pinned
+		unsafe { self.map_unchecked_mut(|s| &mut s.stream) }
+	}
 
-	pin_utils::unsafe_pinned!(timeout: tokio::time::Sleep);
+	fn timeout(self: Pin<&mut Self>) -> Pin<&mut C::Sleep> {
+		// `timeout` is structurally pinned
+		unsafe { self.map_unchecked_mut(|s| &mut s.timeout) }
+	}
+}
 
+impl<S: Stream> TimeoutStream<S> {
 	/// Create new `TimeoutStream`.
 	///
 	/// Also see [`StreamTimeoutExt::timeout`](trait.StreamTimeoutExt.html#method.timeout).
 	pub fn new(stream: S, duration: Duration) -> Self {
+		Self::with_clock(stream, duration, TokioClock)
+	}
+}
+
+impl<S: Stream, C: Clock> TimeoutStream<S, C> {
+	/// Create new `TimeoutStream` using the given [`Clock`](trait.Clock.html)
+	pub fn with_clock(stream: S, duration: Duration, clock: C) -> Self {
+		let timeout = clock.sleep_until(clock.now() + duration);
 		Self {
 			stream,
 			duration,
-			timeout: tokio::time::sleep(duration),
+			clock,
+			timeout,
 		}
 	}
-}
 
-impl<S: Stream> TimeoutStream<S> {
-	fn reset_timer(self: Pin<&mut Self>) {
-		let next = tokio::time::Instant::now() + self.duration;
-		self.timeout().reset(next);
+	fn reset_timer(mut self: Pin<&mut Self>) {
+		let next = self.clock.now() + self.duration;
+		let timeout = self.clock.sleep_until(next);
+		self.as_mut().timeout().set(timeout);
 	}
 }
 
-impl<S: futures_core::TryStream> Stream for TimeoutStream<S> {
+impl<S: futures_core::TryStream, C: Clock> Stream for TimeoutStream<S, C> {
 	type Item = Result<S::Ok, S::Error>;
 
 	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
@@ -84,3 +106,25 @@ impl<S: futures_core::TryStream> Stream for TimeoutStream<S> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures_util::StreamExt;
+	use std::io;
+	use tokio::time::Instant;
+
+	#[tokio::test(start_paused = true)]
+	async fn resets_on_items() {
+		let (tx, rx) = futures_channel::mpsc::unbounded::<io::Result<()>>();
+		let mut stream = Box::pin(rx.timeout(Duration::from_secs(10)));
+		let start = Instant::now();
+
+		tokio::time::sleep(Duration::from_secs(5)).await;
+		tx.unbounded_send(Ok(())).unwrap();
+		stream.next().await.unwrap().unwrap();
+
+		assert!(stream.next().await.is_none());
+		assert_eq!(start.elapsed(), Duration::from_secs(15));
+	}
+}
@@ -0,0 +1,144 @@
+#!/usr/bin/env python3
+"""Regenerate the CLASS and TYPE tables in types/src/dns_consts.rs
+
+Reads the CSV files of the IANA "Domain Name System (DNS) Parameters"
+registry from tools/dns-parameters (or the given directory):
+
+- dns-parameters-2.csv: DNS CLASSes
+- dns-parameters-4.csv: Resource Record (RR) TYPEs
+
+and replaces the blocks between the `// BEGIN GENERATED ...` and
+`// END GENERATED ...` markers.  With `--fetch` the checked-in CSV files
+are downloaded from IANA first; the download date is stored in
+tools/dns-parameters/FETCHED and stated in the generated header.
+
+CI runs the script without arguments and checks the output matches the
+checked-in tables.
+
+Usage: tools/gen_dns_consts.py [--fetch | directory-with-csv-files]
+"""
+
+import csv
+import datetime
+import os
+import re
+import sys
+import urllib.request
+
+BASE_URL = 'https://www.iana.org/assignments/dns-parameters/'
+TARGET = os.path.join(os.path.dirname(__file__), '..', 'types', 'src', 'dns_consts.rs')
+SNAPSHOT = os.path.join(os.path.dirname(__file__), 'dns-parameters')
+FILES = ('dns-parameters-2.csv', 'dns-parameters-4.csv')
+FETCHED = os.path.join(SNAPSHOT, 'FETCHED')
+
+# mnemonic -> rust identifier for entries that aren't valid identifiers
+NAMES = {
+	'*': 'ANY',
+}
+
+# mnemonic used for display/parsing if the registry has none (or an
+# unusable one)
+MNEMONICS = {
+	'*': 'ANY',
+}
+
+
+def fetch():
+	for name in FILES:
+		with urllib.request.urlopen(BASE_URL + name) as r:
+			data = r.read()
+		with open(os.path.join(SNAPSHOT, name), 'wb') as f:
+			f.write(data)
+	with open(FETCHED, 'w', encoding='utf-8') as f:
+		f.write(datetime.date.today().isoformat() + '\n')
+
+
+def snapshot(source):
+	try:
+		with open(os.path.join(source, 'FETCHED'), encoding='utf-8') as f:
+			fetched = f.read().strip()
+	except FileNotFoundError:
+		fetched = None
+	if fetched:
+		yield '// Registry snapshot fetched from IANA on %s.' % fetched
+	else:
+		yield '// Registry snapshot was not fetched from IANA; run'
+		yield '// `tools/gen_dns_consts.py --fetch` to replace it.'
+
+
+def load(source, name):
+	with open(os.path.join(source, name), encoding='utf-8', newline='') as f:
+		return list(csv.DictReader(f))
+
+
+def reference(ref):
+	# "[RFC1035]" -> "RFC 1035", "[Name_Surname][http://...]" -> "Name Surname: http://..."
+	parts = re.findall(r'\[([^\]]*)\]', ref) or [ref]
+	parts = [re.sub(r'^RFC(\d+)', r'RFC \1', p).replace('_', ' ') for p in parts if p]
+	if len(parts) >= 2 and not parts[0].startswith('RFC'):
+		return parts[0] + ': ' + ', '.join(parts[1:])
+	return ', '.join(parts)
+
+
+def escape(s):
+	return ' '.join(s.split()).replace('\\', '\\\\').replace('"', '\\"')
+
+
+def entry(mnemonic, value, meaning, ref):
+	name = NAMES.get(mnemonic, mnemonic.replace('-', '_'))
+	display = MNEMONICS.get(mnemonic, mnemonic)
+	doc = ' '.join(meaning.split()) or name
+	return '\t/// %s\n\t%s = 0x%04x, "%s", "%s";' % (
+		doc, name, value, escape(display), escape(reference(ref)),
+	)
+
+
+def classes(rows):
+	for row in rows:
+		name = row['Name'].strip()
+		# skip ranges and unassigned/reserved values
+		if '-' in row['Decimal'] or not name or name.startswith(('Unassigned', 'Reserved')):
+			continue
+		# name is like "Internet (IN)" or "QCLASS NONE"
+		m = re.search(r'\((\w+)\)$', name) or re.match(r'QCLASS (\w+)', name)
+		if not m:
+			continue
+		mnemonic = m.group(1)
+		if mnemonic == 'CS':
+			continue  # CSNET: not just obsolete, but unassigned
+		yield entry(mnemonic, int(row['Decimal']), name, row['Reference'])
+
+
+def types(rows):
+	for row in rows:
+		mnemonic = row['TYPE'].strip()
+		if '-' in row['Value'] or mnemonic in ('', 'Unassigned', 'Private use', 'Reserved'):
+			continue
+		yield entry(mnemonic, int(row['Value']), row['Meaning'], row['Reference'])
+
+
+def replace_block(content, marker, lines):
+	begin = '// BEGIN GENERATED %s\n' % marker
+	end = '// END GENERATED %s\n' % marker
+	start = content.index(begin) + len(begin)
+	stop = content.index(end)
+	return content[:start] + '\n'.join(lines) + '\n' + content[stop:]
+
+
+def main():
+	source = SNAPSHOT
+	if sys.argv[1:] == ['--fetch']:
+		fetch()
+	elif len(sys.argv) > 1:
+		source = sys.argv[1]
+	with open(TARGET, encoding='utf-8') as f:
+		content = f.read()
+	content = replace_block(content, 'SNAPSHOT', snapshot(source))
+	content = replace_block(content, 'CLASSES', classes(load(source, 'dns-parameters-2.csv')))
+	content = replace_block(content, 'TYPES', types(load(source, 'dns-parameters-4.csv')))
+	with open(TARGET, 'w', encoding='utf-8') as f:
+		f.write(content)
+
+
+if __name__ == '__main__':
+	main()
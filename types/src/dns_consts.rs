@@ -0,0 +1,373 @@
+#![cfg_attr(rustfmt, rustfmt_skip)]
+
+// The tables below are generated from the IANA "Domain Name System (DNS)
+// Parameters" registry by `tools/gen_dns_consts.py`; don't edit them
+// manually, rerun the script instead.
+// BEGIN GENERATED SNAPSHOT
+// Registry snapshot was not fetched from IANA; run
+// `tools/gen_dns_consts.py --fetch` to replace it.
+// END GENERATED SNAPSHOT
+
+//! DNS CLASS and TYPE constants
+
+use core::{
+	fmt,
+	str,
+};
+
+/// Error returned when parsing an unknown [`Class`] or [`Type`] mnemonic
+///
+/// [`Class`]: struct.Class.html
+/// [`Type`]: struct.Type.html
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ParseMnemonicError;
+
+impl fmt::Display for ParseMnemonicError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("unknown mnemonic")
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseMnemonicError {}
+
+// Each entry: `/// description`, `NAME = value, "mnemonic", "reference";`
+//
+// Generates the constants, `mnemonic()`, `reference()`, `Display` and
+// `FromStr`.  Unknown values use the generic `PREFIX<number>` notation
+// from RFC 3597.
+macro_rules! dns_consts {
+	($t:ident, $generic:expr, {
+		$(
+			$(#[$attr:meta])*
+			$name:ident = $value:expr, $mnemonic:expr, $reference:expr;
+		)*
+	}) => {
+		impl $t {
+			$(
+				$(#[$attr])*
+				pub const $name: Self = Self($value);
+			)*
+
+			/// Mnemonic from the IANA registry (`None` for unknown values)
+			pub fn mnemonic(&self) -> Option<&'static str> {
+				match *self {
+					$( Self::$name => Some($mnemonic), )*
+					_ => None,
+				}
+			}
+
+			/// Reference (usually RFC) defining the value according to the
+			/// IANA registry (`None` for unknown values)
+			pub fn reference(&self) -> Option<&'static str> {
+				match *self {
+					$( Self::$name => Some($reference), )*
+					_ => None,
+				}
+			}
+		}
+
+		impl fmt::Display for $t {
+			fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+				match self.mnemonic() {
+					Some(mnemonic) => f.write_str(mnemonic),
+					None => write!(f, "{}{}", $generic, self.0), // RFC 3597
+				}
+			}
+		}
+
+		/// Parses mnemonics (case-insensitive) and the generic notation
+		/// from RFC 3597
+		impl str::FromStr for $t {
+			type Err = ParseMnemonicError;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				$(
+					if s.eq_ignore_ascii_case($mnemonic) {
+						return Ok(Self::$name);
+					}
+				)*
+				match s.get(..$generic.len()) {
+					Some(prefix) if prefix.eq_ignore_ascii_case($generic) => {
+						let number = &s[$generic.len()..];
+						if number.starts_with('+') {
+							return Err(ParseMnemonicError);
+						}
+						number.parse().map(Self).map_err(|_| ParseMnemonicError)
+					},
+					_ => Err(ParseMnemonicError),
+				}
+			}
+		}
+	};
+}
+
+/// DNS CLASS
+///
+/// Originally QCLASS was a superset of CLASS; RFC 6895 now defines:
+///
+/// > There are currently two subcategories of DNS CLASSes: normal,
+/// > data-containing classes; and QCLASSes that are only meaningful in
+/// > queries or updates.
+///
+/// ## `ANY`
+///
+/// QTYPE 255 either (rules from RFC 6895):
+///
+/// - doesn't have a mnemonic, violating the existence rule
+/// - has "*" as mnemonic, violating the formatting rule
+/// - has "ANY" as mnemonic, violating the uniquess rule (class ANY)
+///
+/// The QCLASS `ANY` is mostly useless anyway and shouldn't be used in
+/// normal queries.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Class(pub u16);
+
+dns_consts!(Class, "CLASS", {
+// BEGIN GENERATED CLASSES
+	/// Internet (IN)
+	IN = 0x0001, "IN", "RFC 1035";
+	/// Chaos (CH)
+	CH = 0x0003, "CH", "D. Moon, \"Chaosnet\", A.I. Memo 628, Massachusetts Institute of Technology Artificial Intelligence Laboratory, June 1981.";
+	/// Hesiod (HS)
+	HS = 0x0004, "HS", "Dyer, S., and F. Hsu, \"Hesiod\", Project Athena Technical Plan - Name Service, April 1987.";
+	/// QCLASS NONE
+	NONE = 0x00fe, "NONE", "RFC 2136";
+	/// QCLASS * (ANY)
+	ANY = 0x00ff, "ANY", "RFC 1035";
+// END GENERATED CLASSES
+});
+
+/// DNS (RR)TYPE
+///
+/// Originally QTYPE was a superset of TYPE; RFC 6895 now defines:
+///
+/// > There are three subcategories of RRTYPE numbers: data TYPEs,
+/// > QTYPEs, and Meta-TYPEs.
+///
+/// ## `ANY`
+///
+/// QTYPE 255 ("*") doesn't seem to have an official mnemonic; `ANY` is
+/// used in most tools though.
+///
+/// The `ANY` mnemonic conflicts with the QCLASS `ANY` though...
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Type(pub u16);
+
+dns_consts!(Type, "TYPE", {
+// BEGIN GENERATED TYPES
+	/// a host address
+	A = 0x0001, "A", "RFC 1035";
+	/// an authoritative name server
+	NS = 0x0002, "NS", "RFC 1035";
+	/// a mail destination (OBSOLETE - use MX)
+	MD = 0x0003, "MD", "RFC 1035";
+	/// a mail forwarder (OBSOLETE - use MX)
+	MF = 0x0004, "MF", "RFC 1035";
+	/// the canonical name for an alias
+	CNAME = 0x0005, "CNAME", "RFC 1035";
+	/// marks the start of a zone of authority
+	SOA = 0x0006, "SOA", "RFC 1035";
+	/// a mailbox domain name (EXPERIMENTAL)
+	MB = 0x0007, "MB", "RFC 1035";
+	/// a mail group member (EXPERIMENTAL)
+	MG = 0x0008, "MG", "RFC 1035";
+	/// a mail rename domain name (EXPERIMENTAL)
+	MR = 0x0009, "MR", "RFC 1035";
+	/// a null RR (EXPERIMENTAL)
+	NULL = 0x000a, "NULL", "RFC 1035";
+	/// a well known service description
+	WKS = 0x000b, "WKS", "RFC 1035";
+	/// a domain name pointer
+	PTR = 0x000c, "PTR", "RFC 1035";
+	/// host information
+	HINFO = 0x000d, "HINFO", "RFC 1035";
+	/// mailbox or mail list information
+	MINFO = 0x000e, "MINFO", "RFC 1035";
+	/// mail exchange
+	MX = 0x000f, "MX", "RFC 1035";
+	/// text strings
+	TXT = 0x0010, "TXT", "RFC 1035";
+	/// for Responsible Person
+	RP = 0x0011, "RP", "RFC 1183";
+	/// for AFS Data Base location
+	AFSDB = 0x0012, "AFSDB", "RFC 1183, RFC 5864";
+	/// for X.25 PSDN address
+	X25 = 0x0013, "X25", "RFC 1183";
+	/// for ISDN address
+	ISDN = 0x0014, "ISDN", "RFC 1183";
+	/// for Route Through
+	RT = 0x0015, "RT", "RFC 1183";
+	/// for NSAP address, NSAP style A record
+	NSAP = 0x0016, "NSAP", "RFC 1706";
+	/// for domain name pointer, NSAP style
+	NSAP_PTR = 0x0017, "NSAP-PTR", "RFC 1706";
+	/// for security signature
+	SIG = 0x0018, "SIG", "RFC 2536, RFC 2931, RFC 3110, RFC 4034";
+	/// for security key
+	KEY = 0x0019, "KEY", "RFC 2536, RFC 2539, RFC 3110, RFC 4034";
+	/// X.400 mail mapping information
+	PX = 0x001a, "PX", "RFC 2163";
+	/// Geographical Position
+	GPOS = 0x001b, "GPOS", "RFC 1712";
+	/// IP6 Address
+	AAAA = 0x001c, "AAAA", "RFC 3596";
+	/// Location Information
+	LOC = 0x001d, "LOC", "RFC 1876";
+	/// Next Domain (OBSOLETE)
+	NXT = 0x001e, "NXT", "RFC 2535, RFC 3755";
+	/// Endpoint Identifier
+	EID = 0x001f, "EID", "Michael Patton: http://ana-3.lcs.mit.edu/~jnc/nimrod/dns.txt";
+	/// Nimrod Locator
+	NIMLOC = 0x0020, "NIMLOC", "Michael Patton: http://ana-3.lcs.mit.edu/~jnc/nimrod/dns.txt";
+	/// Server Selection
+	SRV = 0x0021, "SRV", "RFC 2782";
+	/// ATM Address
+	ATMA = 0x0022, "ATMA", "ATM Forum Technical Committee, \"ATM Name System, V2.0\", Doc ID: AF-DANS-0152.000, July 2000. Available from and held in escrow by IANA.";
+	/// Naming Authority Pointer
+	NAPTR = 0x0023, "NAPTR", "RFC 3403";
+	/// Key Exchanger
+	KX = 0x0024, "KX", "RFC 2230";
+	/// CERT
+	CERT = 0x0025, "CERT", "RFC 4398";
+	/// A6 (OBSOLETE - use AAAA)
+	A6 = 0x0026, "A6", "RFC 2874, RFC 3226, RFC 6563";
+	/// DNAME
+	DNAME = 0x0027, "DNAME", "RFC 6672";
+	/// SINK
+	SINK = 0x0028, "SINK", "Donald E Eastlake: draft-eastlake-kitchen-sink";
+	/// OPT
+	OPT = 0x0029, "OPT", "RFC 3225, RFC 6891";
+	/// APL
+	APL = 0x002a, "APL", "RFC 3123";
+	/// Delegation Signer
+	DS = 0x002b, "DS", "RFC 4034";
+	/// SSH Key Fingerprint
+	SSHFP = 0x002c, "SSHFP", "RFC 4255";
+	/// IPSECKEY
+	IPSECKEY = 0x002d, "IPSECKEY", "RFC 4025";
+	/// RRSIG
+	RRSIG = 0x002e, "RRSIG", "RFC 4034";
+	/// NSEC
+	NSEC = 0x002f, "NSEC", "RFC 4034, RFC 9077";
+	/// DNSKEY
+	DNSKEY = 0x0030, "DNSKEY", "RFC 4034";
+	/// DHCID
+	DHCID = 0x0031, "DHCID", "RFC 4701";
+	/// NSEC3
+	NSEC3 = 0x0032, "NSEC3", "RFC 5155, RFC 9077";
+	/// NSEC3PARAM
+	NSEC3PARAM = 0x0033, "NSEC3PARAM", "RFC 5155";
+	/// TLSA
+	TLSA = 0x0034, "TLSA", "RFC 6698";
+	/// S/MIME cert association
+	SMIMEA = 0x0035, "SMIMEA", "RFC 8162";
+	/// Host Identity Protocol
+	HIP = 0x0037, "HIP", "RFC 8005";
+	/// NINFO
+	NINFO = 0x0038, "NINFO", "Jim Reid";
+	/// RKEY
+	RKEY = 0x0039, "RKEY", "Jim Reid";
+	/// Trust Anchor LINK
+	TALINK = 0x003a, "TALINK", "Wouter Wijngaards";
+	/// Child DS
+	CDS = 0x003b, "CDS", "RFC 7344";
+	/// DNSKEY(s) the Child wants reflected in DS
+	CDNSKEY = 0x003c, "CDNSKEY", "RFC 7344";
+	/// OpenPGP Key
+	OPENPGPKEY = 0x003d, "OPENPGPKEY", "RFC 7929";
+	/// Child-To-Parent Synchronization
+	CSYNC = 0x003e, "CSYNC", "RFC 7477";
+	/// Message Digest Over Zone Data
+	ZONEMD = 0x003f, "ZONEMD", "RFC 8976";
+	/// General-purpose service binding
+	SVCB = 0x0040, "SVCB", "RFC 9460";
+	/// SVCB-compatible type for use with HTTP
+	HTTPS = 0x0041, "HTTPS", "RFC 9460";
+	/// SPF
+	SPF = 0x0063, "SPF", "RFC 7208";
+	/// UINFO
+	UINFO = 0x0064, "UINFO", "IANA-Reserved";
+	/// UID
+	UID = 0x0065, "UID", "IANA-Reserved";
+	/// GID
+	GID = 0x0066, "GID", "IANA-Reserved";
+	/// UNSPEC
+	UNSPEC = 0x0067, "UNSPEC", "IANA-Reserved";
+	/// NID
+	NID = 0x0068, "NID", "RFC 6742";
+	/// L32
+	L32 = 0x0069, "L32", "RFC 6742";
+	/// L64
+	L64 = 0x006a, "L64", "RFC 6742";
+	/// LP
+	LP = 0x006b, "LP", "RFC 6742";
+	/// an EUI-48 address
+	EUI48 = 0x006c, "EUI48", "RFC 7043";
+	/// an EUI-64 address
+	EUI64 = 0x006d, "EUI64", "RFC 7043";
+	/// NXDOMAIN indicator for Compact Denial of Existence
+	NXNAME = 0x0080, "NXNAME", "RFC 9824";
+	/// Transaction Key
+	TKEY = 0x00f9, "TKEY", "RFC 2930";
+	/// Transaction Signature
+	TSIG = 0x00fa, "TSIG", "RFC 8945";
+	/// incremental transfer
+	IXFR = 0x00fb, "IXFR", "RFC 1995";
+	/// transfer of an entire zone
+	AXFR = 0x00fc, "AXFR", "RFC 1035, RFC 5936";
+	/// mailbox-related RRs (MB, MG or MR)
+	MAILB = 0x00fd, "MAILB", "RFC 1035";
+	/// mail agent RRs (OBSOLETE - see MX)
+	MAILA = 0x00fe, "MAILA", "RFC 1035";
+	/// A request for some or all records the server has available
+	ANY = 0x00ff, "ANY", "RFC 1035, RFC 6895, RFC 8482";
+	/// URI
+	URI = 0x0100, "URI", "RFC 7553";
+	/// Certification Authority Restriction
+	CAA = 0x0101, "CAA", "RFC 8659";
+	/// Application Visibility and Control
+	AVC = 0x0102, "AVC", "Wolfgang Riedel";
+	/// Digital Object Architecture
+	DOA = 0x0103, "DOA", "draft-durand-doa-over-dns";
+	/// Automatic Multicast Tunneling Relay
+	AMTRELAY = 0x0104, "AMTRELAY", "RFC 8777";
+	/// Resolver Information as Key/Value Pairs
+	RESINFO = 0x0105, "RESINFO", "RFC 9606";
+	/// Public wallet address
+	WALLET = 0x0106, "WALLET", "Paul Hoffman";
+	/// BP Convergence Layer Adapter
+	CLA = 0x0107, "CLA", "draft-johnson-dns-ipn-cla-07";
+	/// BP Node Number
+	IPN = 0x0108, "IPN", "draft-johnson-dns-ipn-cla-07";
+	/// DNSSEC Trust Authorities
+	TA = 0x8000, "TA", "Sam Weiler: Deploying DNSSEC Without a Signed Root. Technical Report 1999-19, Information Networking Institute, Carnegie Mellon University, April 2004.";
+	/// DNSSEC Lookaside Validation (OBSOLETE)
+	DLV = 0x8001, "DLV", "RFC 8749, RFC 4431";
+// END GENERATED TYPES
+});
+
+#[cfg(test)]
+mod tests {
+	use alloc::string::ToString;
+
+	use super::{
+		Class,
+		Type,
+	};
+
+	#[test]
+	fn mnemonics() {
+		assert_eq!(Type::NSAP_PTR.to_string(), "NSAP-PTR");
+		assert_eq!("nsap-ptr".parse(), Ok(Type::NSAP_PTR));
+		assert_eq!(Type(0xfff0).to_string(), "TYPE65520");
+		assert_eq!("type65520".parse(), Ok(Type(0xfff0)));
+		assert_eq!("TYPE1".parse(), Ok(Type::A));
+		assert!("TYPE".parse::<Type>().is_err());
+		assert!("TYPE+1".parse::<Type>().is_err());
+		assert!("TYPE65536".parse::<Type>().is_err());
+		assert_eq!("IN".parse(), Ok(Class::IN));
+		assert_eq!(Class(42).to_string(), "CLASS42");
+		assert_eq!(Type::SRV.reference(), Some("RFC 2782"));
+	}
+}
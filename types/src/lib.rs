@@ -0,0 +1,53 @@
+#![no_std]
+#![warn(missing_docs)]
+#![warn(rust_2018_idioms)]
+#![warn(unused_extern_crates, unused_qualifications)]
+//! # DNS-SD data types
+//!
+//! The data handling parts of [`async-dnssd`] without the discovery
+//! runtime (tokio, DNS-SD C library); only needs `alloc`:
+//!
+//! * [TXT records][`TxtRecord`]
+//! * [DNS CLASS][`Class`] and [TYPE][`Type`] constants
+//! * [Record data parsing and encoding][`rdata`]
+//! * [Name escaping and wire format][`name`]
+//!
+//! All types are re-exported by `async-dnssd`.
+//!
+//! ## Features
+//!
+//! * `std` (default): implement `std::error::Error` for the error types
+//!
+//! [`async-dnssd`]: https://docs.rs/async-dnssd
+//! [`TxtRecord`]: struct.TxtRecord.html
+//! [`Class`]: struct.Class.html
+//! [`Type`]: struct.Type.html
+//! [`rdata`]: rdata/index.html
+//! [`name`]: name/index.html
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub use self::{
+	dns_consts::{
+		Class,
+		ParseMnemonicError,
+		Type,
+	},
+	txt_record::{
+		MergePolicy,
+		TxtChange,
+		TxtDiff,
+		TxtRecord,
+		TxtRecordError,
+		TxtRecordIter,
+		TxtRecordRef,
+		TxtRecordStrings,
+	},
+};
+
+pub mod dns_consts;
+pub mod name;
+pub mod rdata;
+pub mod txt_record;
@@ -0,0 +1,279 @@
+//! Domain name escaping and wire format
+//!
+//! Names in presentation format separate labels by dots and escape
+//! special characters in labels as `\.`, `\\` or `\DDD` (decimal byte
+//! value); same as `DNSServiceConstructFullName`.
+
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+use core::fmt::{
+	self,
+	Write,
+};
+
+/// Maximum length of a label (unescaped)
+pub const MAX_LABEL: usize = 63;
+/// Maximum length of a name in wire format
+pub const MAX_WIRE: usize = 255;
+
+/// Error returned for invalid names (in presentation or wire format)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct NameError(&'static str);
+
+impl NameError {
+	/// Description of the problem
+	pub fn message(&self) -> &'static str {
+		self.0
+	}
+}
+
+impl fmt::Display for NameError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.0)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NameError {}
+
+/// Escape a label for presentation format (without trailing dot)
+///
+/// Dots and backslashes are escaped with a backslash, control
+/// characters (and space) as `\DDD`; bytes that aren't part of valid
+/// UTF-8 sequences are escaped as `\DDD` too.
+pub fn escape_label(label: &[u8]) -> String {
+	let mut result = String::with_capacity(label.len());
+	push_label(&mut result, label);
+	result
+}
+
+fn push_label(name: &mut String, label: &[u8]) {
+	for chunk in label.utf8_chunks() {
+		for c in chunk.valid().chars() {
+			match c {
+				'.' | '\\' => {
+					name.push('\\');
+					name.push(c);
+				},
+				'\0'..=' ' | '\x7f' => write!(name, "\\{:03}", c as u32).expect("write to String"),
+				_ => name.push(c),
+			}
+		}
+		for &b in chunk.invalid() {
+			write!(name, "\\{:03}", b).expect("write to String");
+		}
+	}
+}
+
+/// Split escaped name into (still escaped) labels; ignores a trailing
+/// dot
+///
+/// Returns `None` for empty labels and a trailing single backslash.
+pub fn split_labels(name: &str) -> Option<Vec<&str>> {
+	let bytes = name.as_bytes();
+	let mut labels = Vec::new();
+	let mut start = 0;
+	let mut pos = 0;
+	while pos < bytes.len() {
+		match bytes[pos] {
+			b'\\' => {
+				let digits = bytes.get(pos + 1..pos + 4);
+				if digits.is_some_and(|d| d.iter().all(u8::is_ascii_digit)) {
+					pos += 4;
+				} else if pos + 1 < bytes.len() {
+					pos += 2;
+				} else {
+					return None;
+				}
+			},
+			b'.' => {
+				if pos == start {
+					return None; // empty label
+				}
+				labels.push(&name[start..pos]);
+				pos += 1;
+				start = pos;
+			},
+			_ => pos += 1,
+		}
+	}
+	if start < bytes.len() {
+		labels.push(&name[start..]);
+	}
+	Some(labels)
+}
+
+/// Unescape a single label
+///
+/// Returns `None` for invalid escapes (`\DDD` above 255, trailing
+/// backslash).
+pub fn unescape_label(label: &str) -> Option<Vec<u8>> {
+	let bytes = label.as_bytes();
+	let mut result = Vec::with_capacity(bytes.len());
+	let mut pos = 0;
+	while pos < bytes.len() {
+		if bytes[pos] != b'\\' {
+			result.push(bytes[pos]);
+			pos += 1;
+			continue;
+		}
+		match bytes.get(pos + 1..pos + 4) {
+			Some(d) if d.iter().all(u8::is_ascii_digit) => {
+				let value = d.iter().fold(0u32, |v, &c| v * 10 + u32::from(c - b'0'));
+				result.push(u8::try_from(value).ok()?);
+				pos += 4;
+			},
+			_ => {
+				// escaped character might be multi-byte UTF-8; following
+				// bytes get copied anyway
+				result.push(*bytes.get(pos + 1)?);
+				pos += 2;
+			},
+		}
+	}
+	Some(result)
+}
+
+/// Decode wire format name at `start` of `message`
+///
+/// Follows compression pointers if `compression` is set; pointers must
+/// point before the previous jump target, which rules out loops.
+/// Returns the name in presentation format (with trailing dot) and the
+/// number of bytes used at `start`.
+pub fn read_name(
+	message: &[u8],
+	start: usize,
+	compression: bool,
+) -> Result<(String, usize), NameError> {
+	let truncated = NameError("truncated name");
+	let mut name = String::new();
+	let mut pos = start;
+	// end of the name at `start` once a pointer was followed
+	let mut end = None;
+	let mut limit = start;
+	let mut wire_len = 1; // root label
+	loop {
+		let len = *message.get(pos).ok_or(truncated)?;
+		match len & 0xc0 {
+			0x00 => (),
+			0xc0 if compression => {
+				let low = *message.get(pos + 1).ok_or(truncated)?;
+				let target = (usize::from(len & 0x3f) << 8) | usize::from(low);
+				end.get_or_insert(pos + 2);
+				if target >= limit {
+					return Err(NameError("compression pointer doesn't point backwards"));
+				}
+				limit = target;
+				pos = target;
+				continue;
+			},
+			0xc0 => return Err(NameError("compressed name not allowed")),
+			_ => return Err(NameError("unknown label type")),
+		}
+		let len = usize::from(len);
+		pos += 1;
+		if len == 0 {
+			break;
+		}
+		wire_len += 1 + len;
+		if wire_len > MAX_WIRE {
+			return Err(NameError("name too long"));
+		}
+		let label = message.get(pos..pos + len).ok_or(truncated)?;
+		pos += len;
+		push_label(&mut name, label);
+		name.push('.');
+	}
+	if name.is_empty() {
+		name.push('.');
+	}
+	Ok((name, end.unwrap_or(pos) - start))
+}
+
+/// Decode uncompressed wire format name at start of `data`
+///
+/// Returns the name in presentation format and the number of bytes used
+/// (see [`read_name`](fn.read_name.html)).
+pub fn decode_name(data: &[u8]) -> Option<(String, usize)> {
+	read_name(data, 0, false).ok()
+}
+
+/// Encode name in presentation format as uncompressed wire format
+pub fn encode_name(name: &str) -> Result<Vec<u8>, NameError> {
+	let mut wire = Vec::with_capacity(name.len() + 2);
+	if name != "." {
+		let labels = split_labels(name).ok_or(NameError("empty label or broken escape"))?;
+		for label in labels {
+			let label = unescape_label(label).ok_or(NameError("invalid escape"))?;
+			if label.is_empty() || label.len() > MAX_LABEL {
+				return Err(NameError("invalid label length"));
+			}
+			wire.push(label.len() as u8);
+			wire.extend_from_slice(&label);
+		}
+	}
+	wire.push(0);
+	if wire.len() > MAX_WIRE {
+		return Err(NameError("name too long"));
+	}
+	Ok(wire)
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::string::ToString;
+
+	use super::*;
+
+	#[test]
+	fn escape() {
+		assert_eq!(escape_label(b"My.Printer\\ 2"), "My\\.Printer\\\\\\0322");
+		assert_eq!(escape_label(b"caf\xc3\xa9\xff"), "caf\u{e9}\\255");
+		assert_eq!(
+			unescape_label("My\\.Printer\\\\\\0322").unwrap(),
+			b"My.Printer\\ 2"
+		);
+		assert_eq!(split_labels("a\\.b.c."), Some(alloc::vec!["a\\.b", "c"]));
+		assert_eq!(split_labels("a..b"), None);
+	}
+
+	#[test]
+	fn decode() {
+		assert_eq!(
+			decode_name(b"\x05my.pc\x05local\x00rest"),
+			Some(("my\\.pc.local.".to_string(), 13))
+		);
+		assert_eq!(decode_name(b"\x00"), Some((".".to_string(), 1)));
+		assert_eq!(decode_name(b"\x05ab"), None);
+		assert_eq!(
+			decode_name(b"\x02\xc3\x00\x00"),
+			Some(("\\195\\000.".to_string(), 4))
+		);
+	}
+
+	#[test]
+	fn compression() {
+		let message = b"\x05local\x00\x04host\xc0\x00\x03www\xc0\x07";
+		assert_eq!(
+			read_name(message, 14, true).unwrap(),
+			("www.host.local.".to_string(), 6)
+		);
+		assert!(read_name(message, 14, false).is_err());
+		// pointer to itself, and pointers jumping back and forth
+		assert!(read_name(b"\x01a\xc0\x00", 0, true).is_err());
+		assert!(read_name(b"\x01a\xc0\x04\x01b\xc0\x00", 4, true).is_err());
+	}
+
+	#[test]
+	fn encode() {
+		assert_eq!(
+			encode_name("my\\.pc.local.").unwrap(),
+			b"\x05my.pc\x05local\x00"
+		);
+		assert_eq!(encode_name(".").unwrap(), b"\x00");
+		assert!(encode_name("a..b").is_err());
+		assert!(encode_name(&"x".repeat(64)).is_err());
+	}
+}
@@ -0,0 +1,365 @@
+//! Parsing and encoding of record data (RDATA) used by DNS-SD
+//!
+//! The DNS-SD daemons deliver RDATA uncompressed, so names in RDATA
+//! don't need the full DNS message to be decoded.  [`Rdata`] wraps the
+//! common types; encode it with [`Rdata::to_wire`] to register records.
+//!
+//! [`Rdata`]: enum.Rdata.html
+//! [`Rdata::to_wire`]: enum.Rdata.html#method.to_wire
+
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+use core::{
+	fmt,
+	net::{
+		Ipv4Addr,
+		Ipv6Addr,
+	},
+};
+
+use crate::{
+	dns_consts::Type,
+	name::{
+		decode_name,
+		encode_name,
+		NameError,
+	},
+	txt_record::TxtRecord,
+};
+
+/// Error returned when typed record data can't be encoded
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RdataError(&'static str);
+
+impl RdataError {
+	/// Description of the problem
+	pub fn message(&self) -> &'static str {
+		self.0
+	}
+}
+
+impl fmt::Display for RdataError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.0)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RdataError {}
+
+impl From<NameError> for RdataError {
+	fn from(e: NameError) -> Self {
+		Self(e.message())
+	}
+}
+
+// maximum length of RDATA (RDLENGTH is 16 bits)
+fn check_len(wire: Vec<u8>) -> Result<Vec<u8>, RdataError> {
+	if wire.len() > usize::from(u16::MAX) {
+		return Err(RdataError("record data too long"));
+	}
+	Ok(wire)
+}
+
+/// Data of a `SRV` record (RFC 2782)
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Srv {
+	/// priority of the target host
+	pub priority: u16,
+	/// relative weight for entries with the same priority
+	pub weight: u16,
+	/// port the service is provided on
+	pub port: u16,
+	/// hostname the service is provided on (presentation format)
+	pub target: String,
+}
+
+impl Srv {
+	/// Parse `SRV` RDATA
+	pub fn parse(rdata: &[u8]) -> Option<Self> {
+		if rdata.len() <= 6 {
+			return None;
+		}
+		Some(Self {
+			priority: u16::from_be_bytes([rdata[0], rdata[1]]),
+			weight: u16::from_be_bytes([rdata[2], rdata[3]]),
+			port: u16::from_be_bytes([rdata[4], rdata[5]]),
+			target: parse_name(&rdata[6..])?,
+		})
+	}
+
+	/// Encode as `SRV` RDATA
+	pub fn to_wire(&self) -> Result<Vec<u8>, RdataError> {
+		let mut wire = Vec::new();
+		wire.extend_from_slice(&self.priority.to_be_bytes());
+		wire.extend_from_slice(&self.weight.to_be_bytes());
+		wire.extend_from_slice(&self.port.to_be_bytes());
+		wire.extend_from_slice(&encode_name(&self.target)?);
+		Ok(wire)
+	}
+}
+
+/// Data of a `NSEC` record (RFC 4034)
+///
+/// mDNS uses `NSEC` records to state which types exist for a name
+/// (negative answers).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Nsec {
+	/// next name (mDNS: the owner name itself; presentation format)
+	pub next: String,
+	/// types existing for the owner name (sorted)
+	pub types: Vec<Type>,
+}
+
+impl Nsec {
+	/// Parse `NSEC` RDATA
+	pub fn parse(rdata: &[u8]) -> Option<Self> {
+		let (next, used) = decode_name(rdata)?;
+		let mut bitmaps = &rdata[used..];
+		let mut types = Vec::new();
+		let mut last_window = None;
+		while !bitmaps.is_empty() {
+			let window = *bitmaps.first()?;
+			let len = usize::from(*bitmaps.get(1)?);
+			// windows must be sorted and not empty
+			if last_window.is_some_and(|last| window <= last) || !(1..=32).contains(&len) {
+				return None;
+			}
+			last_window = Some(window);
+			let bitmap = bitmaps.get(2..2 + len)?;
+			for (index, &byte) in bitmap.iter().enumerate() {
+				for bit in 0..8 {
+					if byte & (0x80 >> bit) != 0 {
+						let low = (index * 8 + bit) as u16;
+						types.push(Type((u16::from(window) << 8) | low));
+					}
+				}
+			}
+			bitmaps = &bitmaps[2 + len..];
+		}
+		Some(Self { next, types })
+	}
+
+	/// Encode as `NSEC` RDATA
+	pub fn to_wire(&self) -> Result<Vec<u8>, RdataError> {
+		let mut wire = encode_name(&self.next)?;
+		let mut types = self.types.clone();
+		types.sort();
+		types.dedup();
+		let mut types = &types[..];
+		while let Some(first) = types.first() {
+			let window = (first.0 >> 8) as u8;
+			let count = types
+				.iter()
+				.take_while(|t| (t.0 >> 8) as u8 == window)
+				.count();
+			let mut bitmap = [0u8; 32];
+			let mut len = 0;
+			for t in &types[..count] {
+				let low = usize::from(t.0 & 0xff);
+				bitmap[low / 8] |= 0x80 >> (low % 8);
+				len = low / 8 + 1;
+			}
+			wire.push(window);
+			wire.push(len as u8);
+			wire.extend_from_slice(&bitmap[..len]);
+			types = &types[count..];
+		}
+		check_len(wire)
+	}
+}
+
+/// Data of a `HINFO` record (RFC 1035)
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Hinfo {
+	/// CPU type
+	pub cpu: Vec<u8>,
+	/// operating system
+	pub os: Vec<u8>,
+}
+
+impl Hinfo {
+	/// Parse `HINFO` RDATA
+	pub fn parse(rdata: &[u8]) -> Option<Self> {
+		let (cpu, rest) = split_character_string(rdata)?;
+		let (os, rest) = split_character_string(rest)?;
+		if !rest.is_empty() {
+			return None;
+		}
+		Some(Self {
+			cpu: cpu.into(),
+			os: os.into(),
+		})
+	}
+
+	/// Encode as `HINFO` RDATA
+	pub fn to_wire(&self) -> Result<Vec<u8>, RdataError> {
+		let mut wire = Vec::with_capacity(self.cpu.len() + self.os.len() + 2);
+		for string in [&self.cpu, &self.os] {
+			let len =
+				u8::try_from(string.len()).map_err(|_| RdataError("character-string too long"))?;
+			wire.push(len);
+			wire.extend_from_slice(string);
+		}
+		Ok(wire)
+	}
+}
+
+fn split_character_string(data: &[u8]) -> Option<(&[u8], &[u8])> {
+	let len = usize::from(*data.first()?);
+	let string = data.get(1..1 + len)?;
+	Some((string, &data[1 + len..]))
+}
+
+/// Typed record data
+///
+/// Addresses assume class `IN`.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Rdata {
+	/// `A` record
+	A(Ipv4Addr),
+	/// `AAAA` record
+	Aaaa(Ipv6Addr),
+	/// `PTR` record (name in presentation format)
+	Ptr(String),
+	/// `CNAME` record (name in presentation format)
+	Cname(String),
+	/// `SRV` record
+	Srv(Srv),
+	/// `TXT` record
+	Txt(TxtRecord),
+	/// `NSEC` record
+	Nsec(Nsec),
+	/// `HINFO` record
+	Hinfo(Hinfo),
+	/// Record of another type
+	Raw {
+		/// type of the record
+		rr_type: Type,
+		/// RDATA in wire format
+		data: Vec<u8>,
+	},
+}
+
+impl Rdata {
+	/// Parse RDATA of the given type
+	///
+	/// Types without a variant of their own become [`Raw`]; returns
+	/// `None` if the data isn't valid for the type.
+	///
+	/// [`Raw`]: #variant.Raw
+	pub fn parse(rr_type: Type, rdata: &[u8]) -> Option<Self> {
+		Some(match rr_type {
+			Type::A => Self::A(parse_a(rdata)?),
+			Type::AAAA => Self::Aaaa(parse_aaaa(rdata)?),
+			Type::PTR => Self::Ptr(parse_name(rdata)?),
+			Type::CNAME => Self::Cname(parse_name(rdata)?),
+			Type::SRV => Self::Srv(Srv::parse(rdata)?),
+			Type::TXT => Self::Txt(TxtRecord::parse(rdata)?),
+			Type::NSEC => Self::Nsec(Nsec::parse(rdata)?),
+			Type::HINFO => Self::Hinfo(Hinfo::parse(rdata)?),
+			_ => Self::Raw {
+				rr_type,
+				data: rdata.into(),
+			},
+		})
+	}
+
+	/// Type of the record
+	pub fn rr_type(&self) -> Type {
+		match self {
+			Self::A(_) => Type::A,
+			Self::Aaaa(_) => Type::AAAA,
+			Self::Ptr(_) => Type::PTR,
+			Self::Cname(_) => Type::CNAME,
+			Self::Srv(_) => Type::SRV,
+			Self::Txt(_) => Type::TXT,
+			Self::Nsec(_) => Type::NSEC,
+			Self::Hinfo(_) => Type::HINFO,
+			Self::Raw { rr_type, .. } => *rr_type,
+		}
+	}
+
+	/// Encode as RDATA (e.g. to register the record)
+	pub fn to_wire(&self) -> Result<Vec<u8>, RdataError> {
+		match self {
+			Self::A(addr) => Ok(addr.octets().into()),
+			Self::Aaaa(addr) => Ok(addr.octets().into()),
+			Self::Ptr(name) | Self::Cname(name) => Ok(encode_name(name)?),
+			Self::Srv(srv) => srv.to_wire(),
+			Self::Txt(txt) => check_len(txt.rdata().into()),
+			Self::Nsec(nsec) => nsec.to_wire(),
+			Self::Hinfo(hinfo) => hinfo.to_wire(),
+			Self::Raw { data, .. } => check_len(data.clone()),
+		}
+	}
+}
+
+/// Parse RDATA consisting of a single name (e.g. `PTR`, `CNAME`)
+///
+/// Returns the name in presentation format.
+pub fn parse_name(rdata: &[u8]) -> Option<String> {
+	match decode_name(rdata)? {
+		(name, used) if used == rdata.len() => Some(name),
+		_ => None,
+	}
+}
+
+/// Parse `A` RDATA
+pub fn parse_a(rdata: &[u8]) -> Option<Ipv4Addr> {
+	Some(<[u8; 4]>::try_from(rdata).ok()?.into())
+}
+
+/// Parse `AAAA` RDATA
+pub fn parse_aaaa(rdata: &[u8]) -> Option<Ipv6Addr> {
+	Some(<[u8; 16]>::try_from(rdata).ok()?.into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn nsec() {
+		let nsec = Nsec {
+			next: "host.local.".into(),
+			types: alloc::vec![Type::AAAA, Type::A, Type(1234)],
+		};
+		let wire = nsec.to_wire().unwrap();
+		// window 0: A and AAAA; window 4: 1234 in the last of 27 bytes
+		assert_eq!(&wire[12..20], b"\x00\x04\x40\x00\x00\x08\x04\x1b");
+		assert_eq!(wire.len(), 20 + 27);
+		assert_eq!(wire.last(), Some(&0x20));
+		let parsed = Nsec::parse(&wire).unwrap();
+		assert_eq!(parsed.types, [Type::A, Type::AAAA, Type(1234)]);
+		// windows out of order
+		assert!(Nsec::parse(b"\x00\x04\x01\x40\x00\x01\x40").is_none());
+	}
+
+	#[test]
+	fn rdata_roundtrip() {
+		let hinfo = Rdata::parse(Type::HINFO, b"\x03x86\x05Linux").unwrap();
+		assert!(matches!(&hinfo, Rdata::Hinfo(h) if h.os == b"Linux"));
+		assert_eq!(hinfo.to_wire().unwrap(), b"\x03x86\x05Linux");
+		let ptr = Rdata::parse(Type::PTR, b"\x04host\x05local\x00").unwrap();
+		assert!(matches!(&ptr, Rdata::Ptr(name) if name == "host.local."));
+		assert_eq!(
+			Rdata::parse(Type::A, b"\x01\x02\x03").map(|r| r.rr_type()),
+			None
+		);
+		let raw = Rdata::parse(Type::LOC, b"\x00").unwrap();
+		assert_eq!(raw.rr_type(), Type::LOC);
+	}
+
+	#[test]
+	fn srv() {
+		let srv = Srv::parse(b"\x00\x01\x00\x02\x1f\x90\x04host\x05local\x00").unwrap();
+		assert_eq!((srv.priority, srv.weight, srv.port), (1, 2, 8080));
+		assert_eq!(srv.target, "host.local.");
+		assert!(Srv::parse(b"\x00\x01\x00\x02\x1f\x90\x00trailing").is_none());
+		assert!(Srv::parse(b"\x00\x01\x00\x02\x1f\x90").is_none());
+	}
+}
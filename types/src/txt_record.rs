@@ -0,0 +1,780 @@
+//! DNS-SD TXT records
+
+use alloc::{
+	vec,
+	vec::Vec,
+};
+use core::ops::Range;
+
+/// Key-Value container that uses DNS `TXT` RDATA as representation
+///
+/// The binary representation can be used as RDATA for `DNS-SD TXT
+/// Records` (see [RFC 6763, section 6]).
+///
+/// Each entry results in one string in the `TXT` represenation; `TXT`
+/// RDATA contains many (but at least one) possibly empty strings, each
+/// up to 255 bytes.
+///
+/// Key and value are separated by the first `=` in an entry, and the
+/// key must consist of printable ASCII characters (0x20...0x7E) apart
+/// from `=`.  Keys should be 9 characters or fewer.
+///
+/// Values can be any binary string (but the total length of an entry
+/// cannot exceed 255 bytes).
+///
+/// An entry also can have no value at all (which is different from
+/// having an empty value) if there is no `=` separator in the entry.
+///
+/// [RFC 6763, section 6]: https://tools.ietf.org/html/rfc6763#section-6
+///     "RFC 6763, 6. Data Syntax for DNS-SD TXT Records"
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TxtRecord(Vec<u8>);
+
+impl TxtRecord {
+	/// Recommended maximum size of TXT RDATA so it fits into a single
+	/// ethernet packet
+	///
+	/// See [RFC 6763, section 6.2](https://tools.ietf.org/html/rfc6763#section-6.2).
+	pub const RECOMMENDED_MAX_LEN: usize = 1300;
+
+	/// Constructs a new, empty `TxtRecord`.
+	pub fn new() -> Self {
+		Self(Vec::new())
+	}
+
+	/// Parse binary blob as TXT RDATA
+	///
+	/// Same as [`parse`] but takes ownership of buffer.
+	///
+	/// [`parse`]: #method.parse
+	pub fn parse_vec(data: Vec<u8>) -> Option<Self> {
+		let len = TxtRecordRef::parse(&data)?.0.len();
+		let mut data = data;
+		data.truncate(len);
+		Some(Self(data))
+	}
+
+	/// Parse some binary blob as TXT RDATA
+	///
+	/// A single empty string (encoded as `0x00`) gets decoded as "empty" `TxtRecord` (i.e. the
+	/// reverse th `rdata()`); an empty slice is treated the same, although it wouldn't be valid
+	/// RDATA.
+	///
+	/// This only fails when the length of a chunk exceeds the remaining data.
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		Self::parse_vec(data.into())
+	}
+
+	/// Constructs a new, empty `TxtRecord` with the specified capacity.
+	///
+	/// The inserting operations will still reallocate if necessary.
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self(Vec::with_capacity(capacity))
+	}
+
+	/// Reserves capacity for at least `additional` more bytes to be
+	/// used by inserting operations.
+	///
+	/// Each entry requires 1 byte for the total length, the length
+	/// of the key for the key; if there is a value 1 byte for the
+	/// separator `=` and the length of the value for the value.
+	pub fn reserve(&mut self, additional: usize) {
+		self.0.reserve(additional);
+	}
+
+	/// Returns `true` if the `TxtRecord` contains no elements (both in
+	/// bytes and key-value entries).
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Clears the `TxtRecord`, removing all entries.
+	pub fn clear(&mut self) {
+		self.0.clear();
+	}
+
+	/// if not empty this returns valid TXT RDATA, otherwise just an
+	/// empty slice.
+	pub fn data(&self) -> &[u8] {
+		&self.0
+	}
+
+	/// always returns valid TXT RDATA; when the container is empty it
+	/// will return a TXT record with a single empty string (i.e.
+	/// `&[0x00]`).
+	pub fn rdata(&self) -> &[u8] {
+		if self.0.is_empty() {
+			&[0x00] // empty RDATA not allowed, use single empty chunk instead
+		} else {
+			&self.0
+		}
+	}
+
+	/// Returns valid TXT RDATA (like [`rdata`](#method.rdata)) with the
+	/// entries sorted by key
+	///
+	/// Keys are compared case-insensitive (ASCII); entries with equal
+	/// keys keep their relative order.  Useful to compare records or
+	/// detect changes independent of insertion order.
+	pub fn sorted_rdata(&self) -> Vec<u8> {
+		if self.0.is_empty() {
+			return vec![0x00];
+		}
+		let mut entries: Vec<_> = self._position_keys().collect();
+		entries.sort_by(|(_, a), (_, b)| {
+			a.iter()
+				.map(u8::to_ascii_lowercase)
+				.cmp(b.iter().map(u8::to_ascii_lowercase))
+		});
+		let mut data = Vec::with_capacity(self.0.len());
+		for (loc, _) in entries {
+			data.extend_from_slice(&self.0[loc]);
+		}
+		data
+	}
+
+	fn _position_keys(&self) -> PositionKeyIter<'_> {
+		PositionKeyIter {
+			pos: 0,
+			data: &self.0,
+		}
+	}
+
+	/// Borrow as [`TxtRecordRef`](struct.TxtRecordRef.html)
+	pub fn as_txt_ref(&self) -> TxtRecordRef<'_> {
+		TxtRecordRef(&self.0)
+	}
+
+	/// Iterate over all `(key, value)` pairs.
+	pub fn iter(&self) -> TxtRecordIter<'_> {
+		self.as_txt_ref().iter()
+	}
+
+	/// Iterate over all strings verbatim (without splitting into key and
+	/// value).
+	pub fn iter_strings(&self) -> TxtRecordStrings<'_> {
+		self.as_txt_ref().iter_strings()
+	}
+
+	/// Get value for entry with given key
+	///
+	/// Returns `None` if there is no such entry, `Some(None)` if the
+	/// entry exists but has no value, and `Some(Some(value))` if the
+	/// entry exists and has a value.
+	#[allow(clippy::option_option)]
+	pub fn get(&self, key: &[u8]) -> Option<Option<&[u8]>> {
+		self.as_txt_ref().get(key)
+	}
+
+	/// Remove entry with given key (if it exists)
+	pub fn remove(&mut self, key: &[u8]) {
+		if let Some((loc, _)) = self._position_keys().find(|&(_, k)| key == k) {
+			self.0.drain(loc);
+		}
+	}
+
+	/// Length of the (non-empty) TXT RDATA in bytes
+	///
+	/// Same as `data().len()`.
+	pub fn len_bytes(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Number of bytes left until the RDATA reaches the recommended
+	/// maximum size of [`RECOMMENDED_MAX_LEN`] bytes
+	///
+	/// [`RECOMMENDED_MAX_LEN`]: #associatedconstant.RECOMMENDED_MAX_LEN
+	pub fn remaining_recommended(&self) -> usize {
+		Self::RECOMMENDED_MAX_LEN.saturating_sub(self.rdata().len())
+	}
+
+	// returns length of entry
+	fn check_entry(key: &[u8], value: Option<&[u8]>) -> Result<usize, TxtRecordError> {
+		for &k in key {
+			if k == b'=' || !(0x20..=0x7e).contains(&k) {
+				return Err(TxtRecordError::InvalidKey);
+			}
+		}
+		let entry_len = key.len() + value.map(|v| v.len() + 1).unwrap_or(0);
+		if entry_len > 255 {
+			return Err(TxtRecordError::EntryTooLong);
+		}
+		Ok(entry_len)
+	}
+
+	/// Like [`set`](#method.set), but fails with
+	/// [`TxtRecordError::RecordTooLong`] instead of growing the RDATA
+	/// beyond [`RECOMMENDED_MAX_LEN`] bytes
+	///
+	/// Returns the number of bytes still left (see
+	/// [`remaining_recommended`](#method.remaining_recommended)).  The
+	/// record is not modified on errors.
+	///
+	/// [`TxtRecordError::RecordTooLong`]: enum.TxtRecordError.html#variant.RecordTooLong
+	/// [`RECOMMENDED_MAX_LEN`]: #associatedconstant.RECOMMENDED_MAX_LEN
+	pub fn try_set(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<usize, TxtRecordError> {
+		let entry_len = Self::check_entry(key, value)?;
+		let old_len = self
+			._position_keys()
+			.find(|&(_, k)| key == k)
+			.map(|(loc, _)| loc.len())
+			.unwrap_or(0);
+		if self.0.len() - old_len + 1 + entry_len > Self::RECOMMENDED_MAX_LEN {
+			return Err(TxtRecordError::RecordTooLong);
+		}
+		self.set(key, value)?;
+		Ok(self.remaining_recommended())
+	}
+
+	/// Like [`try_set`](#method.try_set) with no value
+	pub fn try_set_no_value(&mut self, key: &[u8]) -> Result<usize, TxtRecordError> {
+		self.try_set(key, None)
+	}
+
+	/// Like [`try_set`](#method.try_set) with the given value
+	pub fn try_set_value(&mut self, key: &[u8], value: &[u8]) -> Result<usize, TxtRecordError> {
+		self.try_set(key, Some(value))
+	}
+
+	/// Insert or update the entry with `key` to have the given value or on value
+	pub fn set(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<(), TxtRecordError> {
+		let entry_len = Self::check_entry(key, value)?;
+		self.remove(key);
+
+		self.0.push(entry_len as u8);
+		self.0.extend_from_slice(key);
+		if let Some(value) = value {
+			self.0.push(b'=');
+			self.0.extend_from_slice(value);
+		}
+
+		Ok(())
+	}
+
+	/// Append a string verbatim
+	///
+	/// Unlike [`set`](#method.set) this neither checks for a valid key
+	/// nor removes existing entries; useful for records that don't use
+	/// the key/value convention.
+	pub fn push_raw(&mut self, entry: &[u8]) -> Result<(), TxtRecordError> {
+		if entry.len() > 255 {
+			return Err(TxtRecordError::EntryTooLong);
+		}
+		self.0.push(entry.len() as u8);
+		self.0.extend_from_slice(entry);
+		Ok(())
+	}
+
+	/// Insert or update the entry with `key` to have no value
+	pub fn set_no_value(&mut self, key: &[u8]) -> Result<(), TxtRecordError> {
+		self.set(key, None)
+	}
+
+	/// Insert or update the entry with `key` to have the given value
+	pub fn set_value(&mut self, key: &[u8], value: &[u8]) -> Result<(), TxtRecordError> {
+		self.set(key, Some(value))
+	}
+
+	/// Changes needed to turn this record into `new`
+	///
+	/// Only the first entry with a key counts (like [`get`](#method.get)).
+	pub fn diff(&self, new: &TxtRecord) -> TxtDiff {
+		let mut changes = Vec::new();
+		let mut seen: Vec<&[u8]> = Vec::new();
+		for (key, value) in new {
+			if seen.contains(&key) {
+				continue;
+			}
+			seen.push(key);
+			match self.get(key) {
+				None => changes.push(TxtChange::Added {
+					key: key.to_vec(),
+					value: value.map(<[u8]>::to_vec),
+				}),
+				Some(old) if old != value => changes.push(TxtChange::Changed {
+					key: key.to_vec(),
+					old: old.map(<[u8]>::to_vec),
+					new: value.map(<[u8]>::to_vec),
+				}),
+				Some(_) => (),
+			}
+		}
+		for (key, value) in self {
+			if !seen.contains(&key) {
+				seen.push(key);
+				changes.push(TxtChange::Removed {
+					key: key.to_vec(),
+					old: value.map(<[u8]>::to_vec),
+				});
+			}
+		}
+		TxtDiff(changes)
+	}
+
+	/// Add the entries of `other`; `policy` decides about keys present
+	/// in both records
+	///
+	/// E.g. to layer overrides over defaults before publishing the
+	/// record.  The record is not modified on errors (e.g. an entry of
+	/// `other` without a valid key).
+	pub fn merge(&mut self, other: &TxtRecord, policy: MergePolicy) -> Result<(), TxtRecordError> {
+		let mut merged = self.clone();
+		for (key, value) in other {
+			let present = merged.get(key).is_some();
+			if present && (policy == MergePolicy::KeepExisting || self.get(key).is_none()) {
+				// keep existing entry (or first entry of `other` with this key)
+				continue;
+			}
+			merged.set(key, value)?;
+		}
+		*self = merged;
+		Ok(())
+	}
+}
+
+/// How [`TxtRecord::merge`] handles keys present in both records
+///
+/// [`TxtRecord::merge`]: struct.TxtRecord.html#method.merge
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum MergePolicy {
+	/// Keep the entry of the record merged into
+	KeepExisting,
+	/// Replace the entry with the one from the other record
+	#[default]
+	Overwrite,
+}
+
+/// Change of a single TXT entry
+///
+/// Part of [`TxtDiff`](struct.TxtDiff.html) (and `async-dnssd` TXT
+/// change events).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum TxtChange {
+	/// New entry
+	Added {
+		/// key
+		key: Vec<u8>,
+		/// value (`None` for entries without `=`)
+		value: Option<Vec<u8>>,
+	},
+	/// Entry with a different value
+	Changed {
+		/// key
+		key: Vec<u8>,
+		/// previous value
+		old: Option<Vec<u8>>,
+		/// new value
+		new: Option<Vec<u8>>,
+	},
+	/// Entry was removed
+	Removed {
+		/// key
+		key: Vec<u8>,
+		/// previous value
+		old: Option<Vec<u8>>,
+	},
+}
+
+impl TxtChange {
+	/// Key of the changed entry
+	pub fn key(&self) -> &[u8] {
+		match self {
+			Self::Added { key, .. } | Self::Changed { key, .. } | Self::Removed { key, .. } => key,
+		}
+	}
+}
+
+/// Differences between two [`TxtRecord`]s
+///
+/// Created by [`TxtRecord::diff`]; lists changed values first, then
+/// added and removed entries, in the order of the records.
+///
+/// [`TxtRecord`]: struct.TxtRecord.html
+/// [`TxtRecord::diff`]: struct.TxtRecord.html#method.diff
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct TxtDiff(Vec<TxtChange>);
+
+impl TxtDiff {
+	/// Whether the records have the same entries
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// All changes
+	pub fn changes(&self) -> &[TxtChange] {
+		&self.0
+	}
+
+	/// All changes
+	pub fn into_changes(self) -> Vec<TxtChange> {
+		self.0
+	}
+
+	/// Keys of added entries
+	pub fn added(&self) -> impl Iterator<Item = &[u8]> {
+		self.keys(|c| matches!(c, TxtChange::Added { .. }))
+	}
+
+	/// Keys of entries with a changed value
+	pub fn changed(&self) -> impl Iterator<Item = &[u8]> {
+		self.keys(|c| matches!(c, TxtChange::Changed { .. }))
+	}
+
+	/// Keys of removed entries
+	pub fn removed(&self) -> impl Iterator<Item = &[u8]> {
+		self.keys(|c| matches!(c, TxtChange::Removed { .. }))
+	}
+
+	fn keys(&self, filter: fn(&TxtChange) -> bool) -> impl Iterator<Item = &[u8]> {
+		self.0.iter().filter(move |c| filter(c)).map(TxtChange::key)
+	}
+}
+
+impl Default for TxtRecord {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a> IntoIterator for &'a TxtRecord {
+	type IntoIter = TxtRecordIter<'a>;
+	type Item = (&'a [u8], Option<&'a [u8]>);
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+/// Borrowed view of TXT RDATA with the read-only API of
+/// [`TxtRecord`](struct.TxtRecord.html)
+///
+/// Avoids copying the data (e.g. `async-dnssd` `ResolveResult::txt`)
+/// just to look at some entries.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TxtRecordRef<'a>(&'a [u8]);
+
+impl<'a> TxtRecordRef<'a> {
+	/// Parse some binary blob as TXT RDATA
+	///
+	/// Same rules as [`TxtRecord::parse`](struct.TxtRecord.html#method.parse).
+	pub fn parse(data: &'a [u8]) -> Option<Self> {
+		if data.len() == 1 && data[0] == 0 {
+			return Some(Self(&data[..0]));
+		}
+		let mut pos = 0;
+		while pos < data.len() {
+			let len = data[pos] as usize;
+			let new_pos = pos + 1 + len;
+			if new_pos > data.len() {
+				return None;
+			}
+			pos = new_pos;
+		}
+		Some(Self(data))
+	}
+
+	/// Returns `true` if there are no entries
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// if not empty this returns valid TXT RDATA, otherwise just an
+	/// empty slice.
+	pub fn data(&self) -> &'a [u8] {
+		self.0
+	}
+
+	/// Iterate over all `(key, value)` pairs.
+	pub fn iter(&self) -> TxtRecordIter<'a> {
+		TxtRecordIter {
+			pos: 0,
+			data: self.0,
+		}
+	}
+
+	/// Iterate over all strings verbatim (without splitting into key and
+	/// value).
+	pub fn iter_strings(&self) -> TxtRecordStrings<'a> {
+		TxtRecordStrings { data: self.0 }
+	}
+
+	/// Get value for entry with given key
+	///
+	/// See [`TxtRecord::get`](struct.TxtRecord.html#method.get).
+	#[allow(clippy::option_option)]
+	pub fn get(&self, key: &[u8]) -> Option<Option<&'a [u8]>> {
+		self.iter().find(|&(k, _)| key == k).map(|(_, value)| value)
+	}
+
+	/// Copy into an owned [`TxtRecord`](struct.TxtRecord.html)
+	pub fn to_owned(&self) -> TxtRecord {
+		TxtRecord(self.0.into())
+	}
+}
+
+impl<'a> From<&'a TxtRecord> for TxtRecordRef<'a> {
+	fn from(txt: &'a TxtRecord) -> Self {
+		txt.as_txt_ref()
+	}
+}
+
+impl<'a> IntoIterator for TxtRecordRef<'a> {
+	type IntoIter = TxtRecordIter<'a>;
+	type Item = (&'a [u8], Option<&'a [u8]>);
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+/// Error returned when inserting new entries failed
+///
+/// New variants might be added in minor releases.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[non_exhaustive]
+pub enum TxtRecordError {
+	/// Key contained invalid characters
+	InvalidKey,
+	/// Total entry would be longer than 255 bytes
+	EntryTooLong,
+	/// Total RDATA would be longer than
+	/// [`TxtRecord::RECOMMENDED_MAX_LEN`](struct.TxtRecord.html#associatedconstant.RECOMMENDED_MAX_LEN)
+	RecordTooLong,
+}
+
+struct PositionKeyIter<'a> {
+	pos: usize,
+	data: &'a [u8],
+}
+
+impl<'a> Iterator for PositionKeyIter<'a> {
+	// (start..end, key)
+	type Item = (Range<usize>, &'a [u8]);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.data.is_empty() {
+			return None;
+		}
+		let len = self.data[0] as usize;
+		let entry_pos = self.pos;
+		let entry = &self.data[1..][..len];
+		self.data = &self.data[len + 1..];
+		self.pos += len + 1;
+
+		Some(match entry.iter().position(|&b| b == b'=') {
+			Some(pos) => (entry_pos..self.pos, &entry[..pos]),
+			None => (entry_pos..self.pos, entry),
+		})
+	}
+}
+
+/// Iterator for entries in `TxtRecord`
+///
+/// Items are `(key, value)` pairs.
+pub struct TxtRecordIter<'a> {
+	pos: usize,
+	data: &'a [u8],
+}
+
+impl<'a> Iterator for TxtRecordIter<'a> {
+	// key, value
+	type Item = (&'a [u8], Option<&'a [u8]>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.data.is_empty() {
+			return None;
+		}
+		let len = self.data[0] as usize;
+		let entry = &self.data[1..][..len];
+		self.data = &self.data[len + 1..];
+		self.pos += len + 1;
+
+		Some(match entry.iter().position(|&b| b == b'=') {
+			Some(pos) => (&entry[..pos], Some(&entry[pos + 1..])),
+			None => (entry, None),
+		})
+	}
+}
+
+/// Iterator for strings in `TxtRecord`
+///
+/// Items are the raw strings (without length prefix).
+pub struct TxtRecordStrings<'a> {
+	data: &'a [u8],
+}
+
+impl<'a> Iterator for TxtRecordStrings<'a> {
+	type Item = &'a [u8];
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.data.is_empty() {
+			return None;
+		}
+		let len = self.data[0] as usize;
+		let entry = &self.data[1..][..len];
+		self.data = &self.data[len + 1..];
+		Some(entry)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{
+		vec,
+		vec::Vec,
+	};
+
+	use super::{
+		MergePolicy,
+		TxtChange,
+		TxtRecord,
+		TxtRecordRef,
+	};
+
+	fn txt(entries: &[&[u8]]) -> TxtRecord {
+		let mut txt = TxtRecord::new();
+		for entry in entries {
+			txt.push_raw(entry).unwrap();
+		}
+		txt
+	}
+
+	#[test]
+	fn diff() {
+		let old = txt(&[b"status=idle", b"paper", b"color=1"]);
+		let new = txt(&[b"status=busy", b"color=1", b"jobs=2"]);
+		let diff = old.diff(&new);
+		assert_eq!(
+			diff.changes(),
+			[
+				TxtChange::Changed {
+					key: b"status".to_vec(),
+					old: Some(b"idle".to_vec()),
+					new: Some(b"busy".to_vec()),
+				},
+				TxtChange::Added {
+					key: b"jobs".to_vec(),
+					value: Some(b"2".to_vec()),
+				},
+				TxtChange::Removed {
+					key: b"paper".to_vec(),
+					old: None,
+				},
+			]
+		);
+		assert_eq!(diff.removed().collect::<Vec<_>>(), [b"paper"]);
+		assert!(new.diff(&new).is_empty());
+	}
+
+	#[test]
+	fn merge() {
+		let defaults = txt(&[b"path=/", b"tls=0"]);
+		let mut r = txt(&[b"tls=1", b"name=x"]);
+		r.merge(&defaults, MergePolicy::KeepExisting).unwrap();
+		assert_eq!(r.data(), b"\x05tls=1\x06name=x\x06path=/");
+		r.merge(&defaults, MergePolicy::Overwrite).unwrap();
+		assert_eq!(r.get(b"tls"), Some(Some(&b"0"[..])));
+	}
+
+	#[test]
+	fn modifications() {
+		let mut r = TxtRecord::new();
+		assert!(r.is_empty());
+		assert_eq!(r.data(), b"");
+		assert_eq!(r.rdata(), b"\x00");
+
+		r.set(b"foo", Some(b"bar")).unwrap();
+		assert!(!r.is_empty());
+		assert_eq!(r.data(), b"\x07foo=bar");
+		assert_eq!(r.rdata(), b"\x07foo=bar");
+
+		r.set(b"u", Some(b"vw")).unwrap();
+		assert!(!r.is_empty());
+		assert_eq!(r.data(), b"\x07foo=bar\x04u=vw");
+		assert_eq!(r.rdata(), b"\x07foo=bar\x04u=vw");
+		assert_eq!(
+			r.iter().collect::<Vec<_>>(),
+			vec![
+				(b"foo" as &[u8], Some(b"bar" as &[u8])),
+				(b"u", Some(b"vw")),
+			]
+		);
+
+		r.set(b"foo", None).unwrap();
+		assert!(!r.is_empty());
+		assert_eq!(r.data(), b"\x04u=vw\x03foo");
+		assert_eq!(r.rdata(), b"\x04u=vw\x03foo");
+		assert_eq!(
+			r.iter().collect::<Vec<_>>(),
+			vec![(b"u" as &[u8], Some(b"vw" as &[u8])), (b"foo", None),]
+		);
+
+		r.set(b"foo", Some(b"bar")).unwrap();
+		assert!(!r.is_empty());
+		assert_eq!(r.data(), b"\x04u=vw\x07foo=bar");
+		assert_eq!(r.rdata(), b"\x04u=vw\x07foo=bar");
+
+		r.remove(b"foo");
+		assert!(!r.is_empty());
+		assert_eq!(r.data(), b"\x04u=vw");
+		assert_eq!(r.rdata(), b"\x04u=vw");
+	}
+	#[test]
+	fn borrowed() {
+		assert!(TxtRecordRef::parse(b"\x05foo").is_none());
+		assert!(TxtRecordRef::parse(b"\x00").unwrap().is_empty());
+
+		let r = TxtRecordRef::parse(b"\x07foo=bar\x03baz").unwrap();
+		assert_eq!(r.get(b"foo"), Some(Some(b"bar" as &[u8])));
+		assert_eq!(r.get(b"baz"), Some(None));
+		assert_eq!(r.get(b"u"), None);
+		assert_eq!(r.to_owned().data(), r.data());
+	}
+
+	#[test]
+	fn sorted() {
+		let mut r = TxtRecord::new();
+		assert_eq!(r.sorted_rdata(), b"\x00");
+		r.set_value(b"b", b"1").unwrap();
+		r.set_no_value(b"A").unwrap();
+		r.set_value(b"a2", b"").unwrap();
+		assert_eq!(r.sorted_rdata(), b"\x01A\x03a2=\x03b=1");
+		assert_eq!(r.data(), b"\x03b=1\x01A\x03a2=");
+	}
+
+	#[test]
+	fn capacity() {
+		let mut r = TxtRecord::new();
+		assert_eq!(r.len_bytes(), 0);
+		assert_eq!(r.remaining_recommended(), 1299);
+		assert_eq!(r.try_set_value(b"k", &[b'x'; 253]), Ok(1300 - 256));
+		for i in 0..4 {
+			let key = [b'a' + i];
+			assert_eq!(
+				r.try_set_value(&key, &[b'x'; 253]),
+				Ok(1300 - 256 * (2 + i as usize))
+			);
+		}
+		assert_eq!(r.len_bytes(), 1280);
+		assert_eq!(
+			r.try_set_value(b"e", &[b'x'; 30]),
+			Err(super::TxtRecordError::RecordTooLong)
+		);
+		// replacing existing entry frees its space
+		assert_eq!(r.try_set_value(b"k", &[b'x'; 30]), Ok(1300 - 1280 + 223));
+	}
+
+	#[test]
+	fn raw_strings() {
+		let mut r = TxtRecord::new();
+		r.push_raw(b"=positional").unwrap();
+		r.push_raw(b"").unwrap();
+		r.set_value(b"k", b"v").unwrap();
+		assert_eq!(r.data(), b"\x0b=positional\x00\x03k=v");
+		assert_eq!(
+			r.iter_strings().collect::<Vec<_>>(),
+			vec![b"=positional" as &[u8], b"", b"k=v"]
+		);
+		assert!(r.push_raw(&[b'x'; 256]).is_err());
+	}
+}